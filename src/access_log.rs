@@ -0,0 +1,451 @@
+use crate::*;
+use std::cell::{Cell, Ref, RefCell};
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+/// Direction of a memory access
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// A read or write seen by a memory part
+///
+/// An access is recorded when it starts and again whenever its address or data changes, rather
+/// than on every tick it is held
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AccessRecord {
+    /// Graph tick when the access was seen
+    pub tick: usize,
+
+    /// Port of the part that was accessed. Always 0 for single-port parts
+    pub port: usize,
+
+    /// Read or write
+    pub access: Access,
+
+    /// Address bus. Undriven (`Off`) bits are shown as errors
+    pub addr: BusValue,
+
+    /// Data read or written
+    pub data: BusValue,
+}
+
+/// Timing and signal problems detected on memory writes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The address had `Error` or `Off` bits while writing. Writes to addresses with `Error` bits
+    /// are dropped; `Off` bits are written as Low
+    UnstableAddress,
+
+    /// The data had `Error` or `Off` bits while writing. Those bits are stored as `Error`
+    InvalidData,
+
+    /// The address changed while the write enable was still asserted, so more than one word was
+    /// written
+    AddressChangedDuringWrite,
+}
+
+/// A problem reported by a memory part instead of panicking
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// What went wrong
+    pub violation: Violation,
+
+    /// The access that caused it
+    pub record: AccessRecord,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "tick {}: port {}: {:?} writing {:?} to {:?}",
+            self.record.tick, self.record.port, self.violation, self.record.data, self.record.addr
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct AccessLogImpl {
+    enabled: bool,
+    records: Vec<AccessRecord>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Shared record of the accesses and diagnostics of a memory part
+///
+/// Accesses are only recorded once turned on with `set_enabled`, so long runs don't keep growing
+/// the log. Diagnostics are always kept
+#[derive(Clone, Debug, Default)]
+pub struct AccessLog(Rc<RefCell<AccessLogImpl>>);
+
+impl AccessLog {
+    /// Creates an empty log with recording turned off
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turns recording of accesses on or off
+    pub fn set_enabled(&self, enabled: bool) {
+        self.0.borrow_mut().enabled = enabled;
+    }
+
+    /// The reads and writes recorded so far, oldest first
+    pub fn records(&self) -> Ref<'_, Vec<AccessRecord>> {
+        Ref::map(self.0.borrow(), |log| &log.records)
+    }
+
+    /// The problems detected so far, oldest first
+    pub fn diagnostics(&self) -> Ref<'_, Vec<Diagnostic>> {
+        Ref::map(self.0.borrow(), |log| &log.diagnostics)
+    }
+
+    /// Removes all records and diagnostics
+    pub fn clear(&self) {
+        let mut log = self.0.borrow_mut();
+        log.records.clear();
+        log.diagnostics.clear();
+    }
+}
+
+/// Reads a bus, treating undriven (`Off`) bits as `Error`
+pub(crate) fn driven_val(pins: &[PinState]) -> BusValue {
+    let mut val = pins.iter().val();
+    for (i, pin) in pins.iter().enumerate() {
+        if pin.sig() == Signal::Off {
+            val.error |= 1 << i;
+        }
+    }
+    val
+}
+
+/// Watches the accesses on one port of a memory part and reports them to its `AccessLog`
+pub(crate) struct AccessMonitor {
+    log: AccessLog,
+    port: usize,
+    tick_count: Rc<Cell<usize>>,
+
+    /// Address of the write in progress, if any
+    write_addr: Option<BusValue>,
+
+    /// The most recent access, to avoid recording the same one every tick
+    last: Option<(Access, BusValue, BusValue)>,
+}
+
+impl AccessMonitor {
+    pub fn new(graph: &Graph, log: &AccessLog, port: usize) -> Self {
+        Self {
+            log: log.clone(),
+            port,
+            tick_count: graph.tick_counter(),
+            write_addr: None,
+            last: None,
+        }
+    }
+
+    /// Records that the port isn't being accessed
+    pub fn idle(&mut self) {
+        self.write_addr = None;
+        self.last = None;
+    }
+
+    /// Records a read of `data` from the (driven) address
+    pub fn read(&mut self, addr: BusValue, data: BusValue) {
+        self.write_addr = None;
+        self.record(Access::Read, addr, data);
+    }
+
+    /// Checks and records a write from the address and data pins
+    ///
+    /// Returns the address and word to store, or None if the write must be dropped
+    pub fn write(
+        &mut self,
+        addr_pins: &[PinState],
+        data_pins: &[PinState],
+    ) -> Option<(usize, BusValue)> {
+        let addr = driven_val(addr_pins);
+        let data = driven_val(data_pins);
+
+        let mut violations = vec![];
+        if matches!(self.write_addr, Some(prev) if prev != addr) {
+            violations.push(Violation::AddressChangedDuringWrite);
+        }
+        if addr.error != 0 {
+            violations.push(Violation::UnstableAddress);
+        }
+        if data.error != 0 {
+            violations.push(Violation::InvalidData);
+        }
+        self.write_addr = Some(addr);
+
+        if let Some(record) = self.record(Access::Write, addr, data) {
+            let mut log = self.log.0.borrow_mut();
+            for violation in violations {
+                log.diagnostics.push(Diagnostic { violation, record });
+            }
+        }
+
+        let lenient_addr = addr_pins.iter().val();
+        (lenient_addr.error == 0).then_some((lenient_addr.val, data))
+    }
+
+    /// Adds an access to the log unless it is the same as the previous one
+    fn record(&mut self, access: Access, addr: BusValue, data: BusValue) -> Option<AccessRecord> {
+        if self.last == Some((access, addr, data)) {
+            return None;
+        }
+        self.last = Some((access, addr, data));
+
+        let record = AccessRecord {
+            tick: self.tick_count.get(),
+            port: self.port,
+            access,
+            addr,
+            data,
+        };
+        let mut log = self.log.0.borrow_mut();
+        if log.enabled {
+            log.records.push(record);
+        }
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod test_access_log {
+    use crate::test_util::*;
+    use crate::*;
+
+    /// A RAM with its control and address pins driven by the host, and its data driven through a
+    /// tristate buffer so it can be read back
+    struct Bench {
+        graph: Graph,
+        ram: IcCY7C199,
+        oe_inv: Pin,
+        we_inv: Pin,
+        addr: Vec<Pin>,
+        data: Vec<Pin>,
+        data_en: Pin,
+    }
+
+    impl Bench {
+        fn new() -> Self {
+            let mut graph = Graph::new();
+            let ram = IcCY7C199::new(&mut graph, "ram");
+            let buffer = BusTristate::new(&mut graph, "buffer", 8);
+            drive_pin(&mut graph, ram.ce_inv(), Signal::Low);
+            let oe_inv = drive_pin(&mut graph, ram.oe_inv(), Signal::High);
+            let we_inv = drive_pin(&mut graph, ram.we_inv(), Signal::High);
+            let addr = drive(&mut graph, ram.addr());
+            let data = drive(&mut graph, buffer.input());
+            let data_en = drive_pin(&mut graph, buffer.en(), Signal::Low);
+            for (out, io) in buffer.output().iter().zip(ram.io()) {
+                graph.connect(out, io);
+            }
+            graph.run();
+            Self {
+                graph,
+                ram,
+                oe_inv,
+                we_inv,
+                addr,
+                data,
+                data_en,
+            }
+        }
+
+        /// Drives the data, then asserts the write enable at the current address
+        fn start_write(&mut self, data: &[Signal]) {
+            set_signals(&mut self.data, data);
+            self.data_en.set_output(Signal::High);
+            self.graph.run();
+            self.we_inv.set_output(Signal::Low);
+            self.graph.run();
+        }
+
+        /// Releases the write enable and the data
+        fn end_write(&mut self) {
+            self.we_inv.set_output(Signal::High);
+            self.data_en.set_output(Signal::Low);
+            self.graph.run();
+        }
+
+        /// Writes a word
+        fn write(&mut self, addr: usize, data: &[Signal]) {
+            set_bus(&mut self.addr, addr);
+            self.start_write(data);
+            self.end_write();
+        }
+
+        /// Reads a word with the output enable held Low
+        fn read(&mut self, addr: usize) -> BusValue {
+            set_bus(&mut self.addr, addr);
+            self.oe_inv.set_output(Signal::Low);
+            self.graph.run();
+            let data = self.ram.io().iter().val();
+            self.oe_inv.set_output(Signal::High);
+            self.graph.run();
+            data
+        }
+    }
+
+    /// The signals of a byte, least significant first
+    fn byte(val: u8) -> Vec<Signal> {
+        (0..8).map(|i| Signal::from(val & (1 << i) != 0)).collect()
+    }
+
+    /// The access, address and data of each record
+    fn accesses(log: &AccessLog) -> Vec<(Access, BusValue, BusValue)> {
+        log.records()
+            .iter()
+            .map(|record| (record.access, record.addr, record.data))
+            .collect()
+    }
+
+    #[test]
+    fn test_records() {
+        let mut bench = Bench::new();
+        let log = bench.ram.access_log().clone();
+        log.set_enabled(true);
+        assert!(log.records().is_empty());
+
+        bench.write(5, &byte(0x42));
+        bench.write(0x7fff, &byte(0x17));
+        assert_eq!(bench.read(5), BusValue::new_val(0x42));
+        assert_eq!(bench.read(0x7fff), BusValue::new_val(0x17));
+        assert_eq!(bench.read(6), BusValue::new_val(0xff));
+
+        let val = BusValue::new_val;
+        assert_eq!(
+            accesses(&log),
+            [
+                (Access::Write, val(5), val(0x42)),
+                (Access::Write, val(0x7fff), val(0x17)),
+                (Access::Read, val(5), val(0x42)),
+                (Access::Read, val(0x7fff), val(0x17)),
+                (Access::Read, val(6), val(0xff)),
+            ]
+        );
+        let records = log.records();
+        assert!(records.iter().all(|record| record.port == 0));
+        assert!(records.windows(2).all(|pair| pair[0].tick < pair[1].tick));
+        assert!(log.diagnostics().is_empty());
+        drop(records);
+
+        // Holding an access doesn't record it again, but moving the address does
+        bench.oe_inv.set_output(Signal::Low);
+        bench.graph.run();
+        bench.graph.run();
+        set_bus(&mut bench.addr, 5);
+        bench.graph.run();
+        assert_eq!(log.records().len(), 7);
+        assert_eq!(
+            accesses(&log)[5..],
+            [
+                (Access::Read, val(6), val(0xff)),
+                (Access::Read, val(5), val(0x42)),
+            ]
+        );
+
+        log.clear();
+        assert!(log.records().is_empty());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut bench = Bench::new();
+        let log = bench.ram.access_log().clone();
+        log.set_enabled(true);
+
+        // An Error data bit is stored as an error
+        let mut data = byte(0x42);
+        data[0] = Signal::Error;
+        bench.write(3, &data);
+        assert_eq!(
+            bench.ram.memory().peek(3),
+            BusValue {
+                val: 0x42,
+                error: 1
+            }
+        );
+
+        // An undriven address bit is written as Low
+        set_bus(&mut bench.addr, 4);
+        bench.addr[14].set_output(Signal::Off);
+        bench.start_write(&byte(0x24));
+        bench.end_write();
+        assert_eq!(bench.ram.memory().peek(4), BusValue::new_val(0x24));
+
+        // Moving the address while writing writes both words
+        set_bus(&mut bench.addr, 8);
+        bench.start_write(&byte(0x99));
+        set_bus(&mut bench.addr, 9);
+        bench.graph.run();
+        bench.end_write();
+        assert_eq!(bench.ram.memory().peek(8), BusValue::new_val(0x99));
+        assert_eq!(bench.ram.memory().peek(9), BusValue::new_val(0x99));
+
+        let diagnostics = log.diagnostics();
+        let found: Vec<(Violation, BusValue)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.violation, diagnostic.record.addr))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Violation::InvalidData, BusValue::new_val(3)),
+                (
+                    Violation::UnstableAddress,
+                    BusValue {
+                        val: 4,
+                        error: 1 << 14
+                    }
+                ),
+                (Violation::AddressChangedDuringWrite, BusValue::new_val(9)),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].record.data,
+            BusValue {
+                val: 0x42,
+                error: 1
+            }
+        );
+        assert!(diagnostics[0].to_string().starts_with(&format!(
+            "tick {}: port 0: InvalidData",
+            diagnostics[0].record.tick
+        )));
+
+        // Each diagnostic comes with the write that caused it
+        let records = log.records();
+        for diagnostic in diagnostics.iter() {
+            assert_eq!(diagnostic.record.access, Access::Write);
+            assert!(records.contains(&diagnostic.record));
+        }
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut bench = Bench::new();
+        let log = bench.ram.access_log().clone();
+
+        // Recording starts off. Accesses still happen and problems are still reported, but nothing
+        // is recorded
+        let mut data = byte(0x42);
+        data[7] = Signal::Error;
+        bench.write(1, &data);
+        assert_eq!(bench.read(1).error, 0x80);
+        assert!(log.records().is_empty());
+        let violations: Vec<Violation> = log.diagnostics().iter().map(|d| d.violation).collect();
+        assert_eq!(violations, [Violation::InvalidData]);
+
+        log.set_enabled(true);
+        bench.write(2, &byte(0x11));
+        assert_eq!(
+            accesses(&log),
+            [(Access::Write, BusValue::new_val(2), BusValue::new_val(0x11))]
+        );
+    }
+}
@@ -0,0 +1,816 @@
+use crate::*;
+
+// Off inputs count as Low. The chips do their math on whole words, so an Error on any input makes
+// every output Error.
+
+/// Reads a word from input pins, least-significant first
+fn word(pins: &[PinState]) -> BusValue {
+    pins.iter().map(|pin| level(*pin)).val()
+}
+
+/// Whether any of the pins is Error
+fn any_error(pins: &[PinState]) -> bool {
+    pins.iter()
+        .any(|pin| pin.sig() == Signal::Error && matches!(pin, PinState::Input(_)))
+}
+
+/// Drives every output pin Error
+fn set_error(pins: &mut [PinState]) {
+    for pin in pins.iter_mut() {
+        if matches!(pin, PinState::Output(_)) {
+            *pin = PinState::Output(Signal::Error);
+        }
+    }
+}
+
+/// Joins the pins of two 4-bit chips into one 8-bit word, least-significant first
+fn join<'a>(low: &'a [Pin], high: &'a [Pin]) -> [&'a Pin; 8] {
+    [
+        &low[0], &low[1], &low[2], &low[3], &high[0], &high[1], &high[2], &high[3],
+    ]
+}
+
+/// 4-bit binary full adder with fast carry, 74283
+///
+/// S = A + B + C0, with the carry out on C4. Chain C4 to the next adder's C0 for wider words.
+#[derive(Debug)]
+pub struct Ic74283 {
+    pins: Vec<Pin>,
+}
+
+impl Ic74283 {
+    /// A input starting index
+    pub(crate) const A_START: usize = 0;
+
+    /// B input starting index
+    pub(crate) const B_START: usize = Self::A_START + 4;
+
+    /// Carry input pin index
+    pub(crate) const CARRY_IN: usize = Self::B_START + 4;
+
+    /// Sum output starting index
+    pub(crate) const SUM_START: usize = Self::CARRY_IN + 1;
+
+    /// Carry output pin index
+    pub(crate) const CARRY_OUT: usize = Self::SUM_START + 4;
+
+    /// Total number of pins in part
+    pub(crate) const NUM_PINS: usize = Self::CARRY_OUT + 1;
+
+    /// Creates a 74283 adder
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let mut states = [PinState::INPUT; Self::NUM_PINS];
+        states[Self::SUM_START..].fill(PinState::OUTPUT);
+        let pins = graph.new_part(name, &states, |pins| {
+            if any_error(pins) {
+                set_error(pins);
+                return;
+            }
+            let a = word(&pins[Self::A_START..Self::B_START]).val;
+            let b = word(&pins[Self::B_START..Self::CARRY_IN]).val;
+            let carry = word(&pins[Self::CARRY_IN..=Self::CARRY_IN]).val;
+            let sum = a + b + carry;
+            drive_bus(
+                &mut pins[Self::SUM_START..Self::NUM_PINS],
+                BusValue::new_val(sum),
+            );
+        });
+        Self { pins }
+    }
+
+    /// A inputs (A1-A4), least-significant first
+    pub fn input_a(&self) -> &[Pin] {
+        &self.pins[Self::A_START..Self::B_START]
+    }
+
+    /// B inputs (B1-B4), least-significant first
+    pub fn input_b(&self) -> &[Pin] {
+        &self.pins[Self::B_START..Self::CARRY_IN]
+    }
+
+    /// Carry input (C0)
+    pub fn carry_in(&self) -> &Pin {
+        &self.pins[Self::CARRY_IN]
+    }
+
+    /// Sum outputs (S1-S4), least-significant first
+    pub fn sum(&self) -> &[Pin] {
+        &self.pins[Self::SUM_START..Self::CARRY_OUT]
+    }
+
+    /// Carry output (C4)
+    pub fn carry_out(&self) -> &Pin {
+        &self.pins[Self::CARRY_OUT]
+    }
+}
+
+/// 8-bit adder made of a pair of 74283s
+#[derive(Debug)]
+pub struct Adder8Bit {
+    adder1: Ic74283,
+    adder2: Ic74283,
+}
+
+impl Adder8Bit {
+    /// A inputs
+    pub fn input_a(&self) -> [&Pin; 8] {
+        join(self.adder1.input_a(), self.adder2.input_a())
+    }
+
+    /// B inputs
+    pub fn input_b(&self) -> [&Pin; 8] {
+        join(self.adder1.input_b(), self.adder2.input_b())
+    }
+
+    /// Carry input
+    pub fn carry_in(&self) -> &Pin {
+        self.adder1.carry_in()
+    }
+
+    /// Sum outputs
+    pub fn sum(&self) -> [&Pin; 8] {
+        join(self.adder1.sum(), self.adder2.sum())
+    }
+
+    /// Carry output
+    pub fn carry_out(&self) -> &Pin {
+        self.adder2.carry_out()
+    }
+
+    /// Create a new Adder8Bit
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let adder1 = Ic74283::new(graph, &format!("{}.adder1", name));
+        let adder2 = Ic74283::new(graph, &format!("{}.adder2", name));
+        graph.connect(adder1.carry_out(), adder2.carry_in());
+        Self { adder1, adder2 }
+    }
+}
+
+/// 4-bit magnitude comparator, 7485
+///
+/// Compares A with B. When they're equal, the cascade inputs from the next less-significant
+/// comparator decide the outputs. The least-significant comparator should have its I(A=B) input
+/// High and the others Low.
+#[derive(Debug)]
+pub struct Ic7485 {
+    pins: Vec<Pin>,
+}
+
+impl Ic7485 {
+    /// A input starting index
+    pub(crate) const A_START: usize = 0;
+
+    /// B input starting index
+    pub(crate) const B_START: usize = Self::A_START + 4;
+
+    /// Cascade A > B input pin index
+    pub(crate) const IN_GT: usize = Self::B_START + 4;
+
+    /// Cascade A < B input pin index
+    pub(crate) const IN_LT: usize = Self::IN_GT + 1;
+
+    /// Cascade A = B input pin index
+    pub(crate) const IN_EQ: usize = Self::IN_LT + 1;
+
+    /// A > B output pin index
+    pub(crate) const OUT_GT: usize = Self::IN_EQ + 1;
+
+    /// A < B output pin index
+    pub(crate) const OUT_LT: usize = Self::OUT_GT + 1;
+
+    /// A = B output pin index
+    pub(crate) const OUT_EQ: usize = Self::OUT_LT + 1;
+
+    /// Total number of pins in part
+    pub(crate) const NUM_PINS: usize = Self::OUT_EQ + 1;
+
+    /// Creates a 7485 comparator
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let mut states = [PinState::INPUT; Self::NUM_PINS];
+        states[Self::OUT_GT..].fill(PinState::OUTPUT);
+        let pins = graph.new_part(name, &states, |pins| {
+            if any_error(pins) {
+                set_error(pins);
+                return;
+            }
+            let a = word(&pins[Self::A_START..Self::B_START]).val;
+            let b = word(&pins[Self::B_START..Self::IN_GT]).val;
+            let (gt, lt, eq) = if a != b {
+                (a > b, a < b, false)
+            } else {
+                // Follows the datasheet's function table for equal words
+                let in_gt = level(pins[Self::IN_GT]).is_high();
+                let in_lt = level(pins[Self::IN_LT]).is_high();
+                let in_eq = level(pins[Self::IN_EQ]).is_high();
+                if in_eq {
+                    (false, false, true)
+                } else {
+                    (!in_lt, !in_gt, false)
+                }
+            };
+            pins[Self::OUT_GT] = PinState::Output(gt.into());
+            pins[Self::OUT_LT] = PinState::Output(lt.into());
+            pins[Self::OUT_EQ] = PinState::Output(eq.into());
+        });
+        Self { pins }
+    }
+
+    /// A inputs (A0-A3), least-significant first
+    pub fn input_a(&self) -> &[Pin] {
+        &self.pins[Self::A_START..Self::B_START]
+    }
+
+    /// B inputs (B0-B3), least-significant first
+    pub fn input_b(&self) -> &[Pin] {
+        &self.pins[Self::B_START..Self::IN_GT]
+    }
+
+    /// Cascade input from the less-significant A > B output
+    pub fn in_gt(&self) -> &Pin {
+        &self.pins[Self::IN_GT]
+    }
+
+    /// Cascade input from the less-significant A < B output
+    pub fn in_lt(&self) -> &Pin {
+        &self.pins[Self::IN_LT]
+    }
+
+    /// Cascade input from the less-significant A = B output
+    pub fn in_eq(&self) -> &Pin {
+        &self.pins[Self::IN_EQ]
+    }
+
+    /// High when A > B
+    pub fn out_gt(&self) -> &Pin {
+        &self.pins[Self::OUT_GT]
+    }
+
+    /// High when A < B
+    pub fn out_lt(&self) -> &Pin {
+        &self.pins[Self::OUT_LT]
+    }
+
+    /// High when A = B
+    pub fn out_eq(&self) -> &Pin {
+        &self.pins[Self::OUT_EQ]
+    }
+}
+
+/// 8-bit magnitude comparator made of a pair of 7485s
+///
+/// The cascade inputs are those of the less-significant comparator.
+#[derive(Debug)]
+pub struct Comparator8Bit {
+    comparator1: Ic7485,
+    comparator2: Ic7485,
+}
+
+impl Comparator8Bit {
+    /// A inputs
+    pub fn input_a(&self) -> [&Pin; 8] {
+        join(self.comparator1.input_a(), self.comparator2.input_a())
+    }
+
+    /// B inputs
+    pub fn input_b(&self) -> [&Pin; 8] {
+        join(self.comparator1.input_b(), self.comparator2.input_b())
+    }
+
+    /// Cascade A > B input
+    pub fn in_gt(&self) -> &Pin {
+        self.comparator1.in_gt()
+    }
+
+    /// Cascade A < B input
+    pub fn in_lt(&self) -> &Pin {
+        self.comparator1.in_lt()
+    }
+
+    /// Cascade A = B input
+    pub fn in_eq(&self) -> &Pin {
+        self.comparator1.in_eq()
+    }
+
+    /// High when A > B
+    pub fn out_gt(&self) -> &Pin {
+        self.comparator2.out_gt()
+    }
+
+    /// High when A < B
+    pub fn out_lt(&self) -> &Pin {
+        self.comparator2.out_lt()
+    }
+
+    /// High when A = B
+    pub fn out_eq(&self) -> &Pin {
+        self.comparator2.out_eq()
+    }
+
+    /// Create a new Comparator8Bit
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let comparator1 = Ic7485::new(graph, &format!("{}.comparator1", name));
+        let comparator2 = Ic7485::new(graph, &format!("{}.comparator2", name));
+        graph.connect_pairs(&[
+            (comparator1.out_gt(), comparator2.in_gt()),
+            (comparator1.out_lt(), comparator2.in_lt()),
+            (comparator1.out_eq(), comparator2.in_eq()),
+        ]);
+        Self {
+            comparator1,
+            comparator2,
+        }
+    }
+}
+
+/// 4-bit arithmetic logic unit, 74181, using the datasheet's active-High data table
+///
+/// S0-S3 pick one of 16 functions of A and B. With M High they are logic functions of each bit pair,
+/// and with M Low they are arithmetic, plus one when C̅n is Low. E.g. S = 1001 is A plus B and
+/// S = 0110 is A minus B minus 1, so A minus B with C̅n Low.
+///
+/// C̅n+4 is Low on a carry out, and goes to the next ALU's C̅n for ripple carry. P̅ and G̅ are the
+/// active-Low carry propagate and generate outputs for a 74182 look-ahead carry generator. A=B is
+/// High when every F output is High, as it is after A minus B minus 1 of equal words.
+#[derive(Debug)]
+pub struct Ic74181 {
+    pins: Vec<Pin>,
+}
+
+impl Ic74181 {
+    /// A input starting index
+    pub(crate) const A_START: usize = 0;
+
+    /// B input starting index
+    pub(crate) const B_START: usize = Self::A_START + 4;
+
+    /// Function select starting index
+    pub(crate) const SELECT_START: usize = Self::B_START + 4;
+
+    /// Mode pin index
+    pub(crate) const MODE: usize = Self::SELECT_START + 4;
+
+    /// Inverse carry input pin index
+    pub(crate) const CARRY_IN_INV: usize = Self::MODE + 1;
+
+    /// Function output starting index
+    pub(crate) const F_START: usize = Self::CARRY_IN_INV + 1;
+
+    /// Inverse carry output pin index
+    pub(crate) const CARRY_OUT_INV: usize = Self::F_START + 4;
+
+    /// Inverse carry propagate pin index
+    pub(crate) const PROPAGATE_INV: usize = Self::CARRY_OUT_INV + 1;
+
+    /// Inverse carry generate pin index
+    pub(crate) const GENERATE_INV: usize = Self::PROPAGATE_INV + 1;
+
+    /// Comparator output pin index
+    pub(crate) const A_EQ_B: usize = Self::GENERATE_INV + 1;
+
+    /// Total number of pins in part
+    pub(crate) const NUM_PINS: usize = Self::A_EQ_B + 1;
+
+    /// Creates a 74181 ALU
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let mut states = [PinState::INPUT; Self::NUM_PINS];
+        states[Self::F_START..].fill(PinState::OUTPUT);
+        let pins = graph.new_part(name, &states, |pins| {
+            if any_error(pins) {
+                set_error(pins);
+                return;
+            }
+            let a = word(&pins[Self::A_START..Self::B_START]).val;
+            let b = word(&pins[Self::B_START..Self::SELECT_START]).val;
+            let s = word(&pins[Self::SELECT_START..Self::MODE]).val;
+            let logic = level(pins[Self::MODE]).is_high();
+            let carry_in = level(pins[Self::CARRY_IN_INV]) == Signal::Low;
+
+            // Each bit's propagate and generate terms, from the gates at the chip's inputs. The
+            // arithmetic functions are P plus G, and the logic functions are P xnor G
+            let select = |bit: usize, word: usize| if s & (1 << bit) != 0 { word } else { 0 };
+            let p = (a | select(0, b) | select(1, !b)) & 0xf;
+            let g = (select(2, a & !b) | select(3, a & b)) & 0xf;
+
+            let sum = p + g + carry_in as usize;
+            let f = if logic { !(p ^ g) & 0xf } else { sum & 0xf };
+
+            // Group propagate and generate over the four bits
+            let propagate = p == 0xf;
+            let generate = p + g > 0xf;
+
+            drive_bus(
+                &mut pins[Self::F_START..Self::CARRY_OUT_INV],
+                BusValue::new_val(f),
+            );
+            pins[Self::CARRY_OUT_INV] = PinState::Output((sum <= 0xf).into());
+            pins[Self::PROPAGATE_INV] = PinState::Output((!propagate).into());
+            pins[Self::GENERATE_INV] = PinState::Output((!generate).into());
+            pins[Self::A_EQ_B] = PinState::Output((f == 0xf).into());
+        });
+        Self { pins }
+    }
+
+    /// A inputs (A0-A3), least-significant first
+    pub fn input_a(&self) -> &[Pin] {
+        &self.pins[Self::A_START..Self::B_START]
+    }
+
+    /// B inputs (B0-B3), least-significant first
+    pub fn input_b(&self) -> &[Pin] {
+        &self.pins[Self::B_START..Self::SELECT_START]
+    }
+
+    /// Function select inputs (S0-S3), least-significant first
+    pub fn select(&self) -> &[Pin] {
+        &self.pins[Self::SELECT_START..Self::MODE]
+    }
+
+    /// Mode, High for logic and Low for arithmetic (M)
+    pub fn mode(&self) -> &Pin {
+        &self.pins[Self::MODE]
+    }
+
+    /// Inverse carry input (C̅n)
+    pub fn carry_in_inv(&self) -> &Pin {
+        &self.pins[Self::CARRY_IN_INV]
+    }
+
+    /// Function outputs (F0-F3), least-significant first
+    pub fn output(&self) -> &[Pin] {
+        &self.pins[Self::F_START..Self::CARRY_OUT_INV]
+    }
+
+    /// Inverse carry output (C̅n+4)
+    pub fn carry_out_inv(&self) -> &Pin {
+        &self.pins[Self::CARRY_OUT_INV]
+    }
+
+    /// Inverse carry propagate output (P̅)
+    pub fn propagate_inv(&self) -> &Pin {
+        &self.pins[Self::PROPAGATE_INV]
+    }
+
+    /// Inverse carry generate output (G̅)
+    pub fn generate_inv(&self) -> &Pin {
+        &self.pins[Self::GENERATE_INV]
+    }
+
+    /// High when every F output is High (A=B)
+    pub fn a_eq_b(&self) -> &Pin {
+        &self.pins[Self::A_EQ_B]
+    }
+}
+
+/// 8-bit ALU made of a pair of 74181s with ripple carry
+#[derive(Debug)]
+pub struct Alu8Bit {
+    alu1: Ic74181,
+    alu2: Ic74181,
+    a_eq_b: BinaryGate,
+}
+
+impl Alu8Bit {
+    /// A inputs
+    pub fn input_a(&self) -> [&Pin; 8] {
+        join(self.alu1.input_a(), self.alu2.input_a())
+    }
+
+    /// B inputs
+    pub fn input_b(&self) -> [&Pin; 8] {
+        join(self.alu1.input_b(), self.alu2.input_b())
+    }
+
+    /// Function select inputs (S0-S3)
+    pub fn select(&self) -> &[Pin] {
+        self.alu1.select()
+    }
+
+    /// Mode, High for logic and Low for arithmetic
+    pub fn mode(&self) -> &Pin {
+        self.alu1.mode()
+    }
+
+    /// Inverse carry input
+    pub fn carry_in_inv(&self) -> &Pin {
+        self.alu1.carry_in_inv()
+    }
+
+    /// Function outputs
+    pub fn output(&self) -> [&Pin; 8] {
+        join(self.alu1.output(), self.alu2.output())
+    }
+
+    /// Inverse carry output
+    pub fn carry_out_inv(&self) -> &Pin {
+        self.alu2.carry_out_inv()
+    }
+
+    /// High when every output is High
+    pub fn a_eq_b(&self) -> &Pin {
+        self.a_eq_b.output()
+    }
+
+    /// Create a new Alu8Bit
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let make_name = |n| format!("{}.{}", name, n);
+        let alu1 = Ic74181::new(graph, &make_name("alu1"));
+        let alu2 = Ic74181::new(graph, &make_name("alu2"));
+        // Stands in for wiring the open-collector A=B outputs together
+        let a_eq_b = and_gate(graph, &make_name("a_eq_b"));
+        graph.connect(alu1.carry_out_inv(), alu2.carry_in_inv());
+        graph.connect(alu1.mode(), alu2.mode());
+        for (s1, s2) in alu1.select().iter().zip(alu2.select()) {
+            graph.connect(s1, s2);
+        }
+        graph.connect(alu1.a_eq_b(), a_eq_b.input_a());
+        graph.connect(alu2.a_eq_b(), a_eq_b.input_b());
+        Self { alu1, alu2, a_eq_b }
+    }
+}
+
+#[cfg(test)]
+mod test_arithmetic {
+    use crate::test_util::*;
+    use crate::*;
+
+    /// Reads a value from pins, panicking on errors
+    fn get(pins: &[&Pin]) -> usize {
+        pins.iter().copied().val().unwrap()
+    }
+
+    #[test]
+    fn test_74283() {
+        let mut graph = Graph::new();
+        let adder = Ic74283::new(&mut graph, "adder");
+        let mut a = drive(&mut graph, adder.input_a());
+        let mut b = drive(&mut graph, adder.input_b());
+        let mut c = drive(&mut graph, [adder.carry_in()]);
+        let mut outputs: Vec<&Pin> = adder.sum().iter().collect();
+        outputs.push(adder.carry_out());
+
+        for (x, y, carry) in combos(16, 16, 2) {
+            set_bus(&mut a, x);
+            set_bus(&mut b, y);
+            set_bus(&mut c, carry);
+            graph.run();
+            assert_eq!(get(&outputs), x + y + carry, "{} + {} + {}", x, y, carry);
+        }
+    }
+
+    /// Every combination of values below the limits
+    fn combos(x: usize, y: usize, z: usize) -> impl Iterator<Item = (usize, usize, usize)> {
+        (0..x).flat_map(move |i| (0..y).flat_map(move |j| (0..z).map(move |k| (i, j, k))))
+    }
+
+    #[test]
+    fn test_adder_8bit() {
+        let mut graph = Graph::new();
+        let adder = Adder8Bit::new(&mut graph, "adder");
+        let mut a = drive(&mut graph, adder.input_a());
+        let mut b = drive(&mut graph, adder.input_b());
+        let mut c = drive(&mut graph, [adder.carry_in()]);
+        let mut outputs = adder.sum().to_vec();
+        outputs.push(adder.carry_out());
+
+        for (x, y, carry) in [(0, 0, 0), (0x0f, 0x01, 0), (0xff, 0x00, 1), (0xa7, 0x6c, 1)] {
+            set_bus(&mut a, x);
+            set_bus(&mut b, y);
+            set_bus(&mut c, carry);
+            graph.run();
+            assert_eq!(get(&outputs), x + y + carry);
+        }
+    }
+
+    #[test]
+    fn test_7485() {
+        let mut graph = Graph::new();
+        let cmp = Ic7485::new(&mut graph, "cmp");
+        let mut a = drive(&mut graph, cmp.input_a());
+        let mut b = drive(&mut graph, cmp.input_b());
+        let mut cascade = drive(&mut graph, [cmp.in_gt(), cmp.in_lt(), cmp.in_eq()]);
+        let outputs = [cmp.out_gt(), cmp.out_lt(), cmp.out_eq()];
+
+        // Unequal words ignore the cascade inputs
+        for (x, y, cascade_val) in combos(16, 16, 8) {
+            if x == y {
+                continue;
+            }
+            set_bus(&mut a, x);
+            set_bus(&mut b, y);
+            set_bus(&mut cascade, cascade_val);
+            graph.run();
+            let expected = if x > y { 0b001 } else { 0b010 };
+            assert_eq!(get(&outputs), expected, "{} vs {}", x, y);
+        }
+
+        // Equal words follow the datasheet, with the cascade inputs and outputs as > < =
+        set_bus(&mut a, 9);
+        set_bus(&mut b, 9);
+        for (cascade_val, expected) in [
+            (0b001, 0b001),
+            (0b010, 0b010),
+            (0b100, 0b100),
+            (0b101, 0b100),
+            (0b111, 0b100),
+            (0b011, 0b000),
+            (0b000, 0b011),
+        ] {
+            set_bus(&mut cascade, cascade_val);
+            graph.run();
+            assert_eq!(get(&outputs), expected, "cascade {:03b}", cascade_val);
+        }
+    }
+
+    #[test]
+    fn test_comparator_8bit() {
+        let mut graph = Graph::new();
+        let cmp = Comparator8Bit::new(&mut graph, "cmp");
+        let mut a = drive(&mut graph, cmp.input_a());
+        let mut b = drive(&mut graph, cmp.input_b());
+        let _cascade = drive(&mut graph, [cmp.in_gt(), cmp.in_lt()]);
+        let high = graph.new_output("high", Signal::High);
+        graph.connect(&high, cmp.in_eq());
+        let outputs = [cmp.out_gt(), cmp.out_lt(), cmp.out_eq()];
+
+        for (x, y) in [
+            (0x12, 0x21),
+            (0x21, 0x12),
+            (0x35, 0x35),
+            (0x35, 0x36),
+            (0xf0, 0x0f),
+        ] {
+            set_bus(&mut a, x);
+            set_bus(&mut b, y);
+            graph.run();
+            let expected = match x.cmp(&y) {
+                std::cmp::Ordering::Greater => 0b001,
+                std::cmp::Ordering::Less => 0b010,
+                std::cmp::Ordering::Equal => 0b100,
+            };
+            assert_eq!(get(&outputs), expected, "{:x} vs {:x}", x, y);
+        }
+    }
+
+    /// Expected 74181 logic functions by S, from the datasheet
+    fn logic(s: usize, a: usize, b: usize) -> usize {
+        let result = match s {
+            0b0000 => !a,
+            0b0001 => !(a | b),
+            0b0010 => !a & b,
+            0b0011 => 0,
+            0b0100 => !(a & b),
+            0b0101 => !b,
+            0b0110 => a ^ b,
+            0b0111 => a & !b,
+            0b1000 => !a | b,
+            0b1001 => !(a ^ b),
+            0b1010 => b,
+            0b1011 => a & b,
+            0b1100 => 0xf,
+            0b1101 => a | !b,
+            0b1110 => a | b,
+            _ => a,
+        };
+        result & 0xf
+    }
+
+    /// Expected 74181 arithmetic functions by S, without carry, from the datasheet
+    fn arithmetic(s: usize, a: usize, b: usize) -> usize {
+        let (or, or_inv, and_inv, and) = ((a | b) & 0xf, (a | !b) & 0xf, a & !b & 0xf, a & b);
+        match s {
+            0b0000 => a,
+            0b0001 => or,
+            0b0010 => or_inv,
+            0b0011 => 0xf,
+            0b0100 => a + and_inv,
+            0b0101 => or + and_inv,
+            0b0110 => a + (!b & 0xf),
+            0b0111 => and_inv + 0xf,
+            0b1000 => a + and,
+            0b1001 => a + b,
+            0b1010 => or_inv + and,
+            0b1011 => and + 0xf,
+            0b1100 => a + a,
+            0b1101 => or + a,
+            0b1110 => or_inv + a,
+            _ => a + 0xf,
+        }
+    }
+
+    #[test]
+    fn test_74181() {
+        let mut graph = Graph::new();
+        let alu = Ic74181::new(&mut graph, "alu");
+        let mut a = drive(&mut graph, alu.input_a());
+        let mut b = drive(&mut graph, alu.input_b());
+        let mut s = drive(&mut graph, alu.select());
+        let mut m = drive(&mut graph, [alu.mode()]);
+        let mut c = drive(&mut graph, [alu.carry_in_inv()]);
+        let f: Vec<&Pin> = alu.output().iter().collect();
+
+        for (func, x, y) in combos(16, 16, 16) {
+            set_bus(&mut a, x);
+            set_bus(&mut b, y);
+            set_bus(&mut s, func);
+
+            set_bus(&mut m, 1);
+            graph.run();
+            assert_eq!(get(&f), logic(func, x, y), "S={:04b} {} {}", func, x, y);
+            assert_eq!(alu.a_eq_b().sig(), Signal::from(logic(func, x, y) == 0xf));
+
+            set_bus(&mut m, 0);
+            for carry in 0..2 {
+                set_bus(&mut c, 1 - carry);
+                graph.run();
+                let sum = arithmetic(func, x, y) + carry;
+                let msg = format!("S={:04b} {} {} carry {}", func, x, y, carry);
+                assert_eq!(get(&f), sum & 0xf, "{}", msg);
+                assert_eq!(
+                    alu.carry_out_inv().sig(),
+                    Signal::from(sum <= 0xf),
+                    "{}",
+                    msg
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_74181_look_ahead() {
+        let mut graph = Graph::new();
+        let alu = Ic74181::new(&mut graph, "alu");
+        let mut a = drive(&mut graph, alu.input_a());
+        let mut b = drive(&mut graph, alu.input_b());
+        let mut s = drive(&mut graph, alu.select());
+        let _m = drive(&mut graph, [alu.mode()]);
+        let _c = drive(&mut graph, [alu.carry_in_inv()]);
+
+        // G is a carry out without a carry in, and P or G is a carry out with one. A minus B is
+        // A plus not B
+        for (func, negate) in [(0b1001, false), (0b0110, true)] {
+            set_bus(&mut s, func);
+            for (x, y) in combos(16, 16, 1).map(|(x, y, _)| (x, y)) {
+                set_bus(&mut a, x);
+                set_bus(&mut b, y);
+                graph.run();
+                let addend = if negate { !y & 0xf } else { y };
+                let msg = format!("S={:04b} {} {}", func, x, y);
+                let generate = alu.generate_inv().sig() == Signal::Low;
+                let propagate = alu.propagate_inv().sig() == Signal::Low;
+                assert_eq!(generate, x + addend > 0xf, "{}", msg);
+                assert_eq!(propagate || generate, x + addend >= 0xf, "{}", msg);
+                assert_eq!(propagate, x | addend == 0xf, "{}", msg);
+            }
+        }
+    }
+
+    #[test]
+    fn test_alu_8bit() {
+        let mut graph = Graph::new();
+        let alu = Alu8Bit::new(&mut graph, "alu");
+        let mut a = drive(&mut graph, alu.input_a());
+        let mut b = drive(&mut graph, alu.input_b());
+        let mut s = drive(&mut graph, alu.select());
+        let _m = drive(&mut graph, [alu.mode()]);
+        let mut c = drive(&mut graph, [alu.carry_in_inv()]);
+        let output = alu.output();
+
+        // A plus B
+        set_bus(&mut s, 0b1001);
+        set_bus(&mut c, 1);
+        for (x, y) in [(0x0f, 0x01), (0x7f, 0x80), (0xc8, 0x64)] {
+            set_bus(&mut a, x);
+            set_bus(&mut b, y);
+            graph.run();
+            let sum = x + y;
+            assert_eq!(get(&output), sum & 0xff);
+            assert_eq!(alu.carry_out_inv().sig(), Signal::from(sum <= 0xff));
+        }
+
+        // A minus B, with a carry in and A=B for a compare
+        set_bus(&mut s, 0b0110);
+        set_bus(&mut c, 0);
+        set_bus(&mut a, 0x42);
+        set_bus(&mut b, 0x17);
+        graph.run();
+        assert_eq!(get(&output), 0x42 - 0x17);
+        set_bus(&mut c, 1);
+        set_bus(&mut b, 0x42);
+        graph.run();
+        assert_eq!(alu.a_eq_b().sig(), Signal::High);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut graph = Graph::new();
+        let adder = Ic74283::new(&mut graph, "adder");
+        let mut a = graph.new_output("a", Signal::Low);
+        graph.connect(&a, &adder.input_a()[0]);
+        graph.run();
+        assert_eq!(adder.sum()[0].sig(), Signal::Low);
+        a.set_output(Signal::Error);
+        graph.run();
+        assert!(adder.sum().iter().all(|pin| pin.sig() == Signal::Error));
+        assert_eq!(adder.carry_out().sig(), Signal::Error);
+    }
+}
@@ -0,0 +1,360 @@
+use crate::*;
+
+use std::fmt::{Debug, Formatter};
+use std::iter::zip;
+
+/// The outcome of `Bfpu::step`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// An instruction was executed, or fetched and skipped by the loop unit
+    Executed,
+
+    /// `,` is waiting for input from the host. Stepping again picks up where it left off
+    Waiting,
+
+    /// The program has halted
+    Halted,
+}
+
+/// A complete brainfuck processor built from the control, data, program, loop and I/O blocks
+///
+/// The clock, reset and clear signals are driven by the bfpu itself through `reset` and `step`.
+pub struct Bfpu {
+    clock: Pin,
+    reset: Pin,
+    clear: Pin,
+    control: ControlBlock,
+    data: DataBlock,
+    program: ProgramBlock,
+    loop_unit: Box<dyn LoopUnit>,
+    io: IoBlock,
+    zero: Pin,
+    halted: Pin,
+    cycles: usize,
+    instructions: usize,
+    skipped: bool,
+    stats: RunStats,
+    phase: usize,
+}
+
+impl Debug for Bfpu {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bfpu")
+            .field("program", &self.program)
+            .field("control", &self.control)
+            .field("data", &self.data)
+            .field("skip", &self.loop_unit.outputs().skip().sig())
+            .finish()
+    }
+}
+
+impl Bfpu {
+    pub fn control(&self) -> &ControlBlock {
+        &self.control
+    }
+
+    pub fn data(&self) -> &DataBlock {
+        &self.data
+    }
+
+    pub fn program(&self) -> &ProgramBlock {
+        &self.program
+    }
+
+    pub fn loop_unit(&self) -> &dyn LoopUnit {
+        self.loop_unit.as_ref()
+    }
+
+    pub fn io(&self) -> &IoBlock {
+        &self.io
+    }
+
+    /// High while the current cell is zero
+    pub fn zero(&self) -> &Pin {
+        &self.zero
+    }
+
+    /// High once `Halt` has been fetched and isn't being skipped
+    pub fn halted(&self) -> &Pin {
+        &self.halted
+    }
+
+    /// Clock cycles since the bfpu was created
+    pub fn cycles(&self) -> usize {
+        self.cycles
+    }
+
+    /// Instructions executed since the bfpu was created
+    ///
+    /// Instructions fetched while the loop unit is skipping aren't counted
+    pub fn instructions(&self) -> usize {
+        self.instructions
+    }
+
+    /// Totals of the ticks and node updates of every graph run done by `reset` and `step`
+    ///
+    /// `cycle` is left at 0, since the cycle lengths of separate runs don't add up to anything
+    pub fn run_stats(&self) -> RunStats {
+        self.stats
+    }
+
+    pub fn new(graph: &mut Graph, name: &str, loop_kind: LoopKind, host: IoHost) -> Self {
+        let make_name = |n: &str| format!("{}.{}", name, n);
+
+        let control = ControlBlock::new(graph, &make_name("control"));
+        let data = DataBlock::new(graph, &make_name("data"));
+        let program = ProgramBlock::new(graph, &make_name("program"));
+        let loop_unit = loop_kind.new_unit(graph, &make_name("loop"));
+        let io = IoBlock::new(graph, &make_name("io"), host);
+
+        control.connect_data_block(graph, &data);
+        io.connect_blocks(graph, &control, &data);
+        let hold = !io.ready();
+        graph.connect(&hold, control.hold());
+        graph.connect(control.instr(), program.fetch());
+        for (pin, input) in zip(program.instruction(), control.instruction()) {
+            graph.connect(pin, input);
+        }
+
+        // The zero flag constantly reads from the bus for use in control signals
+        let zero = nor_nary(graph, &make_name("zero"), data.data().len());
+        zero.connect_inputs(&data.data().iter().collect::<Vec<_>>());
+        let zero = zero.output().clone();
+        loop_unit.connect(graph, &control, &program, &zero);
+
+        let clock = graph.new_output(&make_name("clock"), Signal::Low);
+        let reset = graph.new_output(&make_name("reset"), Signal::High);
+        let clear = graph.new_output(&make_name("clear"), Signal::Low);
+        graph.connect(&clock, control.clock());
+        graph.connect(&clear, data.clear());
+        graph.connect_all(&[
+            &reset,
+            control.reset(),
+            data.reset(),
+            program.reset(),
+            loop_unit.inputs().reset(),
+        ]);
+
+        let halted = control.decoded(Opcode::Halt) & &!loop_unit.outputs().skip();
+
+        Self {
+            clock,
+            reset,
+            clear,
+            control,
+            data,
+            program,
+            loop_unit,
+            io,
+            zero,
+            halted,
+            cycles: 0,
+            instructions: 0,
+            skipped: false,
+            stats: RunStats::default(),
+            phase: 0,
+        }
+    }
+
+    /// Tick limit for each edge of the clear clock burst. A clear pulse settles well within this
+    const CLEAR_TICKS: usize = 1000;
+
+    /// Resets the bfpu so the program starts from address 0, with a clear tape and the data
+    /// pointer at 0
+    ///
+    /// The clear clock is burst once for every cell of the tape, then until the pointer wraps back
+    /// to 0
+    pub fn reset(&mut self, graph: &mut Graph) {
+        self.reset_with_clears(graph, IcCY7C199::NUM_WORDS);
+    }
+
+    /// Resets the bfpu without clearing the tape, leaving it and the data pointer as they were
+    ///
+    /// Much faster than `reset`, for when the host sets up the tape itself
+    pub fn quick_reset(&mut self, graph: &mut Graph) {
+        self.reset_with_clears(graph, 0);
+    }
+
+    fn reset_with_clears(&mut self, graph: &mut Graph, clears: usize) {
+        self.reset.set_output(Signal::High);
+        self.add_stats(graph.run());
+        let burst = graph.burst_output(&mut self.clear, clears, Self::CLEAR_TICKS);
+        self.add_stats(burst);
+        if clears > 0 {
+            // Carry on sweeping until the pointer wraps back to 0
+            let addr = self.data.addr().iter().val().val;
+            let rest = (IcCY7C199::NUM_WORDS - addr) % IcCY7C199::NUM_WORDS;
+            let burst = graph.burst_output(&mut self.clear, rest, Self::CLEAR_TICKS);
+            self.add_stats(burst);
+        }
+        self.reset.set_output(Signal::Low);
+        self.add_stats(graph.run());
+        self.phase = 0;
+    }
+
+    /// Fetches and executes one instruction
+    ///
+    /// Stops after the fetch if the instruction is `Halt`, or before the count phase while `,` is
+    /// waiting for input
+    pub fn step(&mut self, graph: &mut Graph) -> Step {
+        if self.phase == 0 {
+            self.pulse(graph);
+            self.skipped = self.loop_unit.outputs().skip().sig() == Signal::High;
+        }
+        if self.halted.sig() == Signal::High {
+            return Step::Halted;
+        }
+
+        while self.phase != 0 {
+            if self.io.ready().sig() != Signal::High {
+                // Let the io block see any input pushed since the last step
+                self.add_stats(graph.run());
+                if self.io.ready().sig() != Signal::High {
+                    return Step::Waiting;
+                }
+            }
+            self.pulse(graph);
+        }
+        if !self.skipped {
+            self.instructions += 1;
+        }
+        Step::Executed
+    }
+
+    /// Steps until the program halts or waits for input
+    pub fn run(&mut self, graph: &mut Graph) -> Step {
+        loop {
+            match self.step(graph) {
+                Step::Executed => continue,
+                step => return step,
+            }
+        }
+    }
+
+    /// Adds the ticks and updates of a graph run to the totals
+    fn add_stats(&mut self, stats: RunStats) {
+        self.stats.ticks += stats.ticks;
+        self.stats.updates += stats.updates;
+    }
+
+    /// Pulses the clock for one phase
+    fn pulse(&mut self, graph: &mut Graph) {
+        let stats = graph.pulse_output(&mut self.clock);
+        self.add_stats(stats);
+        self.cycles += 1;
+        self.phase = (self.phase + 1) % 3;
+    }
+}
+
+#[cfg(test)]
+mod test_bfpu {
+    use crate::*;
+
+    #[test]
+    fn test_reset() {
+        let mut graph = Graph::new();
+        let mut bfpu = Bfpu::new(&mut graph, "bfpu", LoopKind::Linear, IoHost::new([]));
+        let program = bfpu.program().program();
+        OpcodeEncoding::default().load(program, ">>+").unwrap();
+        let tape = bfpu.data().tape().clone();
+        tape.fill(0xaa);
+
+        bfpu.reset(&mut graph);
+        assert!(tape
+            .dump(0..tape.len())
+            .iter()
+            .all(|word| *word == BusValue::new_val(0)));
+        assert_eq!(bfpu.data().addr().iter().val(), BusValue::new_val(0));
+
+        assert_eq!(bfpu.run(&mut graph), Step::Halted);
+        assert_eq!(bfpu.data().addr().iter().val(), BusValue::new_val(2));
+        assert_eq!(bfpu.data().data().iter().val(), BusValue::new_val(1));
+
+        // Resetting again sweeps back around to the start of a clear tape
+        bfpu.reset(&mut graph);
+        assert!(tape
+            .dump(0..tape.len())
+            .iter()
+            .all(|word| *word == BusValue::new_val(0)));
+        assert_eq!(bfpu.data().addr().iter().val(), BusValue::new_val(0));
+    }
+
+    #[test]
+    fn test_reset_after_random_power_on() {
+        let val = BusValue::new_val;
+        for kind in LoopKind::ALL {
+            for seed in [1, 2, 3] {
+                let msg = format!("{:?} seed {}", kind, seed);
+                let mut graph = Graph::new();
+                let mut bfpu = Bfpu::new(&mut graph, "bfpu", kind, IoHost::new([]));
+                graph.power_on(PowerOnState::Random(seed));
+                let program = bfpu.program().program();
+                OpcodeEncoding::default().load(program, ">>+").unwrap();
+
+                bfpu.reset(&mut graph);
+                let tape = bfpu.data().tape();
+                let words = tape.dump(0..tape.len());
+                assert!(words.iter().all(|word| *word == val(0)), "{}", msg);
+                assert_eq!(bfpu.data().data().iter().val(), val(0), "{}", msg);
+                assert_eq!(bfpu.data().addr().iter().val(), val(0), "{}", msg);
+                assert_eq!(bfpu.program().pc().iter().val(), val(0), "{}", msg);
+
+                // Only the preprocessed loop unit has work to do after reset, filling its table
+                let outputs = bfpu.loop_unit().outputs();
+                let skip = Signal::from(kind == LoopKind::Preprocessed);
+                assert_eq!(outputs.skip().sig(), skip, "{}", msg);
+                assert_eq!(outputs.reverse().sig(), Signal::Low, "{}", msg);
+                assert_eq!(outputs.load_inv().sig(), Signal::High, "{}", msg);
+
+                assert_eq!(bfpu.run(&mut graph), Step::Halted, "{}", msg);
+                assert_eq!(bfpu.data().addr().iter().val(), val(2), "{}", msg);
+                assert_eq!(bfpu.data().data().iter().val(), val(1), "{}", msg);
+            }
+        }
+    }
+
+    #[test]
+    fn test_counts() {
+        // Each program skips a loop, so the linear loop unit fetches instructions it doesn't run
+        for source in ["[+>]+", "++[-]", "+[>+<-]>[-]"] {
+            let mut interpreter = Interpreter::new(source, IoHost::new([])).unwrap();
+            assert_eq!(interpreter.run(), Step::Halted);
+
+            for kind in LoopKind::ALL {
+                let mut graph = Graph::new();
+                let mut bfpu = Bfpu::new(&mut graph, "bfpu", kind, IoHost::new([]));
+                let program = bfpu.program().program();
+                OpcodeEncoding::default().load(program, source).unwrap();
+                bfpu.data().tape().fill(0);
+                bfpu.quick_reset(&mut graph);
+                let mut steps = 0;
+                while bfpu.step(&mut graph) == Step::Executed {
+                    steps += 1;
+                }
+
+                assert_eq!(
+                    bfpu.instructions(),
+                    interpreter.instructions(),
+                    "{:?} {}",
+                    kind,
+                    source
+                );
+                assert!(steps >= bfpu.instructions(), "{:?} {}", kind, source);
+                assert_eq!(bfpu.run_stats().cycle, 0, "{:?} {}", kind, source);
+                assert!(bfpu.run_stats().ticks > 0, "{:?} {}", kind, source);
+            }
+        }
+    }
+
+    #[test]
+    fn test_timing() {
+        // Clocked parts like the io block and the counters break every feedback path
+        for kind in LoopKind::ALL {
+            let mut graph = Graph::new();
+            let _bfpu = Bfpu::new(&mut graph, "bfpu", kind, IoHost::new([]));
+            let report = graph.timing();
+            assert_eq!(report.loops, Vec::<Vec<String>>::new(), "{:?}", kind);
+            assert!(report.critical.is_some(), "{:?}", kind);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use crate::*;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// A brainfuck instruction, plus `Halt` to mark the end of a program in memory
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    /// `>` - move the data pointer right
+    Right,
+
+    /// `<` - move the data pointer left
+    Left,
+
+    /// `+` - increment the current cell
+    Inc,
+
+    /// `-` - decrement the current cell
+    Dec,
+
+    /// `.` - output the current cell
+    Output,
+
+    /// `,` - input into the current cell
+    Input,
+
+    /// `[` - jump past the matching `]` if the current cell is zero
+    LoopStart,
+
+    /// `]` - jump back to the matching `[` if the current cell is not zero
+    LoopEnd,
+
+    /// End of program
+    Halt,
+}
+
+impl Opcode {
+    /// All opcodes, in encoding order
+    pub const ALL: [Opcode; 9] = [
+        Opcode::Right,
+        Opcode::Left,
+        Opcode::Inc,
+        Opcode::Dec,
+        Opcode::Output,
+        Opcode::Input,
+        Opcode::LoopStart,
+        Opcode::LoopEnd,
+        Opcode::Halt,
+    ];
+
+    /// Gets the opcode for a character of brainfuck source. Other characters are comments
+    pub fn from_char(c: char) -> Option<Opcode> {
+        match c {
+            '>' => Some(Opcode::Right),
+            '<' => Some(Opcode::Left),
+            '+' => Some(Opcode::Inc),
+            '-' => Some(Opcode::Dec),
+            '.' => Some(Opcode::Output),
+            ',' => Some(Opcode::Input),
+            '[' => Some(Opcode::LoopStart),
+            ']' => Some(Opcode::LoopEnd),
+            _ => None,
+        }
+    }
+
+    /// The source character for the opcode. `Halt` has no source form and is shown as `!`
+    pub fn to_char(self) -> char {
+        match self {
+            Opcode::Right => '>',
+            Opcode::Left => '<',
+            Opcode::Inc => '+',
+            Opcode::Dec => '-',
+            Opcode::Output => '.',
+            Opcode::Input => ',',
+            Opcode::LoopStart => '[',
+            Opcode::LoopEnd => ']',
+            Opcode::Halt => '!',
+        }
+    }
+
+    /// Parses brainfuck source into opcodes, skipping comments. No `Halt` is added
+    pub fn parse(source: &str) -> Vec<Opcode> {
+        source.chars().filter_map(Opcode::from_char).collect()
+    }
+
+    pub(crate) fn index(self) -> usize {
+        Opcode::ALL.iter().position(|op| *op == self).unwrap()
+    }
+}
+
+/// The byte values used to store each opcode in program memory
+///
+/// Bytes that don't match any opcode are executed as no-ops
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OpcodeEncoding {
+    codes: [u8; 9],
+}
+
+impl Default for OpcodeEncoding {
+    /// `>` through `]` are 1 through 8 in source order, and `Halt` is 0
+    fn default() -> Self {
+        Self {
+            codes: [1, 2, 3, 4, 5, 6, 7, 8, 0],
+        }
+    }
+}
+
+impl OpcodeEncoding {
+    /// Stores each opcode as its ASCII source character, with `Halt` as 0
+    pub fn ascii() -> Self {
+        let mut builder = Self::default().builder();
+        for op in Opcode::ALL {
+            if op != Opcode::Halt {
+                builder = builder.with(op, op.to_char() as u8);
+            }
+        }
+        builder.build().expect("ASCII characters are distinct")
+    }
+
+    /// Starts a custom encoding from this one
+    pub fn builder(self) -> EncodingBuilder {
+        EncodingBuilder { codes: self.codes }
+    }
+
+    /// Gets the byte value for an opcode
+    pub fn encode(&self, op: Opcode) -> u8 {
+        self.codes[op.index()]
+    }
+
+    /// Gets the opcode for a byte value, or None for a no-op
+    pub fn decode(&self, code: u8) -> Option<Opcode> {
+        Opcode::ALL.into_iter().find(|op| self.encode(*op) == code)
+    }
+
+    /// Converts brainfuck source into a program image terminated by `Halt`
+    pub fn pack(&self, source: &str) -> Vec<u8> {
+        Opcode::parse(source)
+            .into_iter()
+            .chain([Opcode::Halt])
+            .map(|op| self.encode(op))
+            .collect()
+    }
+
+    /// Packs brainfuck source into memory starting at address 0
+    pub fn load(&self, memory: &Memory, source: &str) -> Result<(), ImageError> {
+        let words: Vec<usize> = self.pack(source).iter().map(|b| *b as usize).collect();
+        memory.load_words(0, &words)
+    }
+
+    /// Loads a program file into memory
+    ///
+    /// `.bf` and `.b` files are brainfuck source packed with this encoding. Anything else is an
+    /// image in the format given by `ImageFormat::from_path`
+    pub fn load_file(&self, memory: &Memory, path: &Path) -> Result<(), ImageError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("bf" | "b") => {
+                let source = std::fs::read_to_string(path)?;
+                self.load(memory, &source)
+            }
+            _ => memory.load_file(path, ImageFormat::from_path(path)),
+        }
+    }
+}
+
+/// Two opcodes given the same byte value
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateCode {
+    /// The shared byte value
+    pub code: u8,
+
+    /// The opcodes that share it, in encoding order
+    pub ops: [Opcode; 2],
+}
+
+impl Display for DuplicateCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} and {:?} are both encoded as {:#04x}",
+            self.ops[0], self.ops[1], self.code
+        )
+    }
+}
+
+impl std::error::Error for DuplicateCode {}
+
+/// Changes the byte values of an `OpcodeEncoding`
+///
+/// Codes are only checked for duplicates by `build`, so two opcodes can swap codes one at a time
+#[derive(Copy, Clone, Debug)]
+pub struct EncodingBuilder {
+    codes: [u8; 9],
+}
+
+impl EncodingBuilder {
+    /// Changes the byte value for one opcode
+    pub fn with(mut self, op: Opcode, code: u8) -> Self {
+        self.codes[op.index()] = code;
+        self
+    }
+
+    /// Finishes the encoding, or reports the first byte value given to more than one opcode
+    pub fn build(self) -> Result<OpcodeEncoding, DuplicateCode> {
+        for (i, code) in self.codes.iter().enumerate() {
+            if let Some(j) = self.codes[i + 1..].iter().position(|other| other == code) {
+                return Err(DuplicateCode {
+                    code: *code,
+                    ops: [Opcode::ALL[i], Opcode::ALL[i + 1 + j]],
+                });
+            }
+        }
+        Ok(OpcodeEncoding { codes: self.codes })
+    }
+}
+
+#[cfg(test)]
+mod test_brainfuck {
+    use crate::*;
+
+    #[test]
+    fn test_encoding() {
+        let encoding = OpcodeEncoding::default();
+        assert_eq!(encoding.pack("+[-]> comment ."), [3, 7, 4, 8, 1, 5, 0]);
+        assert_eq!(encoding.decode(7), Some(Opcode::LoopStart));
+        assert_eq!(encoding.decode(0x42), None);
+
+        let ascii = OpcodeEncoding::ascii();
+        assert_eq!(ascii.pack("+."), b"+.\0");
+
+        let custom = encoding.builder().with(Opcode::Halt, 0xff).build().unwrap();
+        assert_eq!(custom.pack(""), [0xff]);
+        assert_eq!(custom.decode(0), None);
+    }
+
+    #[test]
+    fn test_duplicate_encoding() {
+        let builder = OpcodeEncoding::default().builder().with(Opcode::Inc, 4);
+        assert_eq!(
+            builder.build(),
+            Err(DuplicateCode {
+                code: 4,
+                ops: [Opcode::Inc, Opcode::Dec]
+            })
+        );
+
+        // Swapping two codes passes through a duplicate
+        let swapped = builder.with(Opcode::Dec, 3).build().unwrap();
+        assert_eq!(swapped.pack("+-"), [4, 3, 0]);
+    }
+}
@@ -0,0 +1,473 @@
+use crate::*;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Drives a value onto bus pins, least significant bit first
+pub(crate) fn drive_bus(pins: &mut [PinState], val: BusValue) {
+    for (i, state) in pins.iter_mut().enumerate() {
+        *state = PinState::Output(val.sig(i));
+    }
+}
+
+/// Turns bus pins into inputs, keeping their current signals
+pub(crate) fn release_bus(pins: &mut [PinState]) {
+    for state in pins.iter_mut() {
+        *state = PinState::Input(state.sig());
+    }
+}
+
+// Each chip maps its DIP pin numbers to part pins, so a board can be wired by pin number and match
+// the simulation exactly. `None` marks power pins.
+
+/// Looks up a pin by DIP pin number, starting from pin 1
+pub(crate) fn dip_pin<'a>(pins: &'a [Pin], dip: &[Option<usize>], number: usize) -> &'a Pin {
+    match dip.get(number.wrapping_sub(1)) {
+        Some(Some(index)) => &pins[*index],
+        _ => panic!("Pin {} is not a logic pin", number),
+    }
+}
+
+/// Octal D register with tri-state outputs, 74HC574
+///
+/// The D inputs are loaded on the rising edge of the clock. The Q outputs drive the stored value
+/// while OE̅ is Low and are HiZ otherwise, so the register can sit on a shared bus. Loading works
+/// with the outputs disabled. The 74HC374 is the same register with a different pinout, see
+/// `Ic74HC574::new_374`.
+#[derive(Debug)]
+pub struct Ic74HC574 {
+    pins: Vec<Pin>,
+    value: Rc<Cell<BusValue>>,
+    dip: &'static [Option<usize>; 20],
+}
+
+impl Ic74HC574 {
+    /// Inverse output enable pin index
+    pub(crate) const OE_INV: usize = 0;
+
+    /// Clock pin index
+    pub(crate) const CLOCK: usize = 1;
+
+    /// Size of word (number of D and Q pins)
+    pub(crate) const WORD_SIZE: usize = 8;
+
+    /// D pin starting index
+    pub(crate) const D_START: usize = 2;
+
+    /// D pin ending index
+    pub(crate) const D_END: usize = Self::D_START + Self::WORD_SIZE;
+
+    /// Q pin starting index
+    pub(crate) const Q_START: usize = Self::D_END;
+
+    /// Q pin ending index
+    pub(crate) const Q_END: usize = Self::Q_START + Self::WORD_SIZE;
+
+    // internal
+    /// Previous clock state index
+    const CLOCK_PREV: usize = Self::Q_END;
+
+    /// Total number of pins in part
+    pub(crate) const NUM_PINS: usize = Self::CLOCK_PREV + 1;
+
+    /// Pin indexes of the 74HC574 by DIP pin number, starting from pin 1
+    const DIP_574: [Option<usize>; 20] = [
+        Some(Self::OE_INV),
+        Some(Self::D_START),
+        Some(Self::D_START + 1),
+        Some(Self::D_START + 2),
+        Some(Self::D_START + 3),
+        Some(Self::D_START + 4),
+        Some(Self::D_START + 5),
+        Some(Self::D_START + 6),
+        Some(Self::D_START + 7),
+        None,
+        Some(Self::CLOCK),
+        Some(Self::Q_START + 7),
+        Some(Self::Q_START + 6),
+        Some(Self::Q_START + 5),
+        Some(Self::Q_START + 4),
+        Some(Self::Q_START + 3),
+        Some(Self::Q_START + 2),
+        Some(Self::Q_START + 1),
+        Some(Self::Q_START),
+        None,
+    ];
+
+    /// Pin indexes of the 74HC374 by DIP pin number, starting from pin 1
+    const DIP_374: [Option<usize>; 20] = [
+        Some(Self::OE_INV),
+        Some(Self::Q_START),
+        Some(Self::D_START),
+        Some(Self::D_START + 1),
+        Some(Self::Q_START + 1),
+        Some(Self::Q_START + 2),
+        Some(Self::D_START + 2),
+        Some(Self::D_START + 3),
+        Some(Self::Q_START + 3),
+        None,
+        Some(Self::CLOCK),
+        Some(Self::Q_START + 4),
+        Some(Self::D_START + 4),
+        Some(Self::D_START + 5),
+        Some(Self::Q_START + 5),
+        Some(Self::Q_START + 6),
+        Some(Self::D_START + 6),
+        Some(Self::D_START + 7),
+        Some(Self::Q_START + 7),
+        None,
+    ];
+
+    /// Creates a 74HC574 register
+    ///
+    /// The register starts as all zeros, or in the graph's power-on state after `Graph::power_on`
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        Self::new_with_dip(graph, name, &Self::DIP_574)
+    }
+
+    /// Creates a 74HC374 register, which only differs from the 74HC574 in its pinout
+    pub fn new_374(graph: &mut Graph, name: &str) -> Self {
+        Self::new_with_dip(graph, name, &Self::DIP_374)
+    }
+
+    fn new_with_dip(graph: &mut Graph, name: &str, dip: &'static [Option<usize>; 20]) -> Self {
+        let mut states = [PinState::INPUT; Self::NUM_PINS];
+        states[Self::Q_START..Self::Q_END].fill(PinState::HiZ);
+
+        let value = Rc::new(Cell::new(BusValue::new_val(0)));
+        let register = value.clone();
+        let init_register = value.clone();
+        let pins = graph.new_stateful_part(
+            name,
+            &states,
+            move |pins| Self::update(&register, pins),
+            move |power_on, _| {
+                init_register.set(power_on.word((1 << Self::WORD_SIZE) - 1));
+            },
+        );
+
+        Self { pins, value, dip }
+    }
+
+    /// Part updater
+    fn update(register: &Cell<BusValue>, pins: &mut [PinState]) {
+        let edge = rising_edge(pins[Self::CLOCK], pins[Self::CLOCK_PREV]);
+        pins[Self::CLOCK_PREV] = pins[Self::CLOCK];
+
+        if edge != Signal::Low {
+            let d = pins[Self::D_START..Self::D_END].iter().val();
+            let old = register.get();
+            let new = (0..Self::WORD_SIZE)
+                .map(|i| choose(edge, d.sig(i), old.sig(i)))
+                .val();
+            register.set(new);
+        }
+
+        let oe = !pins[Self::OE_INV];
+        let q_pins = &mut pins[Self::Q_START..Self::Q_END];
+        if oe.is_high() {
+            drive_bus(q_pins, register.get());
+        } else {
+            q_pins.fill(PinState::HiZ);
+        }
+    }
+
+    /// Output enable (active Low)
+    pub fn oe_inv(&self) -> &Pin {
+        &self.pins[Self::OE_INV]
+    }
+
+    /// Clock input. D is loaded on the rising edge
+    pub fn clock(&self) -> &Pin {
+        &self.pins[Self::CLOCK]
+    }
+
+    /// Data inputs, least-significant first
+    pub fn d(&self) -> &[Pin] {
+        &self.pins[Self::D_START..Self::D_END]
+    }
+
+    /// Data outputs, least-significant first
+    pub fn q(&self) -> &[Pin] {
+        &self.pins[Self::Q_START..Self::Q_END]
+    }
+
+    /// The pin with the given DIP pin number
+    ///
+    /// Panics for the power pins, 10 and 20
+    pub fn dip(&self, number: usize) -> &Pin {
+        dip_pin(&self.pins, self.dip, number)
+    }
+
+    /// The stored value, whether or not the outputs are enabled
+    pub fn value(&self) -> BusValue {
+        self.value.get()
+    }
+}
+
+/// Octal bidirectional bus transceiver, 74HC245
+///
+/// While OE̅ is Low, DIR High drives the B pins from the A pins and DIR Low drives A from B. The
+/// driving side's pins are inputs and the driven side's are outputs. When OE̅ is High both sides are
+/// inputs. An Error on DIR drives Error onto both sides, like two drivers fighting.
+#[derive(Debug)]
+pub struct Ic74HC245 {
+    pins: Vec<Pin>,
+}
+
+impl Ic74HC245 {
+    /// Inverse output enable pin index
+    pub(crate) const OE_INV: usize = 0;
+
+    /// Direction pin index
+    pub(crate) const DIR: usize = 1;
+
+    /// Size of word (number of A and B pins)
+    pub(crate) const WORD_SIZE: usize = 8;
+
+    /// A pin starting index
+    pub(crate) const A_START: usize = 2;
+
+    /// A pin ending index
+    pub(crate) const A_END: usize = Self::A_START + Self::WORD_SIZE;
+
+    /// B pin starting index
+    pub(crate) const B_START: usize = Self::A_END;
+
+    /// B pin ending index
+    pub(crate) const B_END: usize = Self::B_START + Self::WORD_SIZE;
+
+    /// Total number of pins in part
+    pub(crate) const NUM_PINS: usize = Self::B_END;
+
+    /// Pin indexes by DIP pin number, starting from pin 1
+    const DIP: [Option<usize>; 20] = [
+        Some(Self::DIR),
+        Some(Self::A_START),
+        Some(Self::A_START + 1),
+        Some(Self::A_START + 2),
+        Some(Self::A_START + 3),
+        Some(Self::A_START + 4),
+        Some(Self::A_START + 5),
+        Some(Self::A_START + 6),
+        Some(Self::A_START + 7),
+        None,
+        Some(Self::B_START + 7),
+        Some(Self::B_START + 6),
+        Some(Self::B_START + 5),
+        Some(Self::B_START + 4),
+        Some(Self::B_START + 3),
+        Some(Self::B_START + 2),
+        Some(Self::B_START + 1),
+        Some(Self::B_START),
+        Some(Self::OE_INV),
+        None,
+    ];
+
+    /// Creates a transceiver, starting with both sides as inputs
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let pins = graph.new_part(name, &[PinState::INPUT; Self::NUM_PINS], Self::update);
+        Self { pins }
+    }
+
+    /// Part updater
+    fn update(pins: &mut [PinState]) {
+        let oe = !pins[Self::OE_INV];
+        let dir = pins[Self::DIR].sig();
+
+        let (a_pins, b_pins) = pins[Self::A_START..Self::B_END].split_at_mut(Self::WORD_SIZE);
+        if !oe.is_high() {
+            release_bus(a_pins);
+            release_bus(b_pins);
+        } else if dir == Signal::Error {
+            let error = BusValue::new_error((1 << Self::WORD_SIZE) - 1);
+            drive_bus(a_pins, error);
+            drive_bus(b_pins, error);
+        } else if dir.is_high() {
+            Self::drive(a_pins, b_pins);
+        } else {
+            Self::drive(b_pins, a_pins);
+        }
+    }
+
+    /// Makes `from` inputs and drives their signals onto `to`
+    fn drive(from: &mut [PinState], to: &mut [PinState]) {
+        release_bus(from);
+        for (from, to) in from.iter().zip(to.iter_mut()) {
+            *to = PinState::Output(from.sig());
+        }
+    }
+
+    /// Output enable (active Low)
+    pub fn oe_inv(&self) -> &Pin {
+        &self.pins[Self::OE_INV]
+    }
+
+    /// Direction, High for A to B and Low for B to A
+    pub fn dir(&self) -> &Pin {
+        &self.pins[Self::DIR]
+    }
+
+    /// A side pins, least-significant first
+    pub fn a(&self) -> &[Pin] {
+        &self.pins[Self::A_START..Self::A_END]
+    }
+
+    /// B side pins, least-significant first
+    pub fn b(&self) -> &[Pin] {
+        &self.pins[Self::B_START..Self::B_END]
+    }
+
+    /// The pin with the given DIP pin number
+    ///
+    /// Panics for the power pins, 10 and 20
+    pub fn dip(&self, number: usize) -> &Pin {
+        dip_pin(&self.pins, &Self::DIP, number)
+    }
+}
+
+#[cfg(test)]
+mod test_bus_interface {
+    use crate::test_util::*;
+    use crate::*;
+
+    #[test]
+    fn test_register() {
+        let mut graph = Graph::new();
+        let reg = Ic74HC574::new(&mut graph, "reg");
+        let mut clock = drive_pin(&mut graph, reg.clock(), Signal::Low);
+        let mut oe_inv = drive_pin(&mut graph, reg.oe_inv(), Signal::High);
+        let mut data = drive(&mut graph, reg.d());
+        graph.run();
+        assert!(reg.q().iter().all(|pin| pin.sig() == Signal::Off));
+
+        // Loads on the rising edge, with the outputs disabled
+        set_bus(&mut data, 0xa5);
+        graph.run();
+        assert_eq!(reg.value(), BusValue::new_val(0));
+        clock.set_output(Signal::High);
+        graph.run();
+        assert_eq!(reg.value(), BusValue::new_val(0xa5));
+
+        // Holds while the clock stays High or falls
+        set_bus(&mut data, 0x3c);
+        graph.run();
+        clock.set_output(Signal::Low);
+        graph.run();
+        assert_eq!(reg.value(), BusValue::new_val(0xa5));
+
+        oe_inv.set_output(Signal::Low);
+        graph.run();
+        assert_eq!(reg.q().iter().val(), BusValue::new_val(0xa5));
+
+        // An Error clock only spoils the bits that would change
+        clock.set_output(Signal::Error);
+        graph.run();
+        assert_eq!(
+            reg.q().iter().val(),
+            BusValue {
+                val: 0xa5 & 0x3c,
+                error: 0xa5 ^ 0x3c
+            }
+        );
+    }
+
+    #[test]
+    fn test_dip() {
+        let mut graph = Graph::new();
+        let reg_574 = Ic74HC574::new(&mut graph, "reg_574");
+        let reg_374 = Ic74HC574::new_374(&mut graph, "reg_374");
+        let xcvr = Ic74HC245::new(&mut graph, "xcvr");
+
+        assert_eq!(reg_574.dip(1).name(), reg_574.oe_inv().name());
+        assert_eq!(reg_574.dip(2).name(), reg_574.d()[0].name());
+        assert_eq!(reg_574.dip(11).name(), reg_574.clock().name());
+        assert_eq!(reg_574.dip(19).name(), reg_574.q()[0].name());
+        assert_eq!(reg_374.dip(2).name(), reg_374.q()[0].name());
+        assert_eq!(reg_374.dip(3).name(), reg_374.d()[0].name());
+        assert_eq!(reg_374.dip(18).name(), reg_374.d()[7].name());
+        assert_eq!(xcvr.dip(1).name(), xcvr.dir().name());
+        assert_eq!(xcvr.dip(11).name(), xcvr.b()[7].name());
+        assert_eq!(xcvr.dip(19).name(), xcvr.oe_inv().name());
+
+        // Every logic pin appears once
+        for chip_pins in [
+            (1..=20)
+                .filter(|n| n % 10 != 0)
+                .map(|n| reg_374.dip(n).name().to_string())
+                .collect::<Vec<_>>(),
+            (1..=20)
+                .filter(|n| n % 10 != 0)
+                .map(|n| xcvr.dip(n).name().to_string())
+                .collect(),
+        ] {
+            for (i, pin) in chip_pins.iter().enumerate() {
+                assert!(!chip_pins[i + 1..].contains(pin));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_dip_power() {
+        let mut graph = Graph::new();
+        let reg = Ic74HC574::new(&mut graph, "reg");
+        reg.dip(10);
+    }
+
+    #[test]
+    fn test_transceiver() {
+        // Registers on both sides of the transceiver take turns driving their buses
+        let mut graph = Graph::new();
+        let xcvr = Ic74HC245::new(&mut graph, "xcvr");
+        let a_reg = Ic74HC574::new(&mut graph, "a_reg");
+        let b_reg = Ic74HC574::new(&mut graph, "b_reg");
+        let mut data = drive(&mut graph, b_reg.d());
+        let mut a_clock = drive_pin(&mut graph, a_reg.clock(), Signal::Low);
+        let mut b_clock = drive_pin(&mut graph, b_reg.clock(), Signal::Low);
+        let mut a_oe_inv = drive_pin(&mut graph, a_reg.oe_inv(), Signal::High);
+        let mut b_oe_inv = drive_pin(&mut graph, b_reg.oe_inv(), Signal::High);
+        let mut oe_inv = drive_pin(&mut graph, xcvr.oe_inv(), Signal::High);
+        let mut dir = drive_pin(&mut graph, xcvr.dir(), Signal::Low);
+        for i in 0..8 {
+            graph.connect_all(&[&a_reg.d()[i], &a_reg.q()[i], &xcvr.a()[i]]);
+            graph.connect(&b_reg.q()[i], &xcvr.b()[i]);
+        }
+        let is_output = |pins: &[Pin]| {
+            pins.iter()
+                .all(|pin| matches!(pin.state(), PinState::Output(_)))
+        };
+        let is_input = |pins: &[Pin]| {
+            pins.iter()
+                .all(|pin| matches!(pin.state(), PinState::Input(_)))
+        };
+        graph.run();
+        assert!(is_input(xcvr.a()) && is_input(xcvr.b()));
+        assert!(xcvr.a().iter().all(|pin| pin.sig() == Signal::Off));
+
+        // B to A, from the B register into the A register
+        set_bus(&mut data, 0x5a);
+        b_clock.set_output(Signal::High);
+        b_oe_inv.set_output(Signal::Low);
+        oe_inv.set_output(Signal::Low);
+        graph.run();
+        assert!(is_output(xcvr.a()) && is_input(xcvr.b()));
+        a_clock.set_output(Signal::High);
+        graph.run();
+        assert_eq!(a_reg.value(), BusValue::new_val(0x5a));
+
+        // A to B, once the B register lets go of its bus
+        set_bus(&mut data, 0);
+        b_oe_inv.set_output(Signal::High);
+        dir.set_output(Signal::High);
+        a_oe_inv.set_output(Signal::Low);
+        graph.run();
+        assert!(is_input(xcvr.a()) && is_output(xcvr.b()));
+        assert_eq!(xcvr.b().iter().val(), BusValue::new_val(0x5a));
+
+        // Disabled, nothing drives the B bus
+        oe_inv.set_output(Signal::High);
+        graph.run();
+        assert!(is_input(xcvr.b()));
+        assert!(b_reg.q().iter().all(|pin| pin.sig() == Signal::Off));
+    }
+}
@@ -0,0 +1,368 @@
+use crate::*;
+
+// Clock parts keep time with a `Timer`, waking themselves for their next edge. That also keeps `run`
+// and `run_for` going until the clock stops.
+
+/// Flips an output pin
+fn toggle(pin: &mut PinState) {
+    *pin = PinState::Output(!pin.sig());
+}
+
+/// A free-running clock that toggles its output every `half_period` ticks
+///
+/// The output starts Low and first rises `half_period` ticks after the graph starts running. Clocks
+/// in the same graph share ticks, so their half periods set their frequency ratios, e.g. half
+/// periods of 5 and 15 give a 3:1 ratio.
+///
+/// A graph with a running clock never settles, so `run` only returns once it finds a cycle. Use
+/// `run_for` to advance the clock by a number of ticks.
+#[derive(Debug)]
+pub struct Clock {
+    output: Pin,
+}
+
+impl Clock {
+    /// Clock output
+    pub fn output(&self) -> &Pin {
+        &self.output
+    }
+
+    /// Output pin index
+    const OUTPUT: usize = 0;
+
+    /// Creates a clock with a period of `2 * half_period` ticks
+    pub fn new(graph: &mut Graph, name: &str, half_period: usize) -> Self {
+        assert!(half_period > 0, "Clock half period must be at least 1 tick");
+        // Tick of the next edge, counted from the first update
+        let mut next = None;
+        let pins = graph.new_timed_part(
+            name,
+            &[PinState::Output(Signal::Low)],
+            move |pins, timer| {
+                let due = *next.get_or_insert(timer.now() + half_period - 1);
+                if timer.now() >= due {
+                    toggle(&mut pins[Self::OUTPUT]);
+                    next = Some(due + half_period);
+                }
+                timer.wake_at(next.unwrap());
+            },
+        );
+        graph.mark_sequential(&pins[0]);
+        Self {
+            output: pins[Self::OUTPUT].clone(),
+        }
+    }
+}
+
+/// A clock that only runs while enabled
+///
+/// Like `Clock` while `enable` is High. When `enable` goes Low, a High output finishes its half
+/// period before the clock stops, so there are no runt pulses. A stopped clock is Low and restarts
+/// from the beginning of a Low half period.
+#[derive(Debug)]
+pub struct GatedClock {
+    enable: Pin,
+    output: Pin,
+}
+
+impl GatedClock {
+    /// Runs the clock while High
+    pub fn enable(&self) -> &Pin {
+        &self.enable
+    }
+
+    /// Clock output
+    pub fn output(&self) -> &Pin {
+        &self.output
+    }
+
+    /// Enable pin index
+    const ENABLE: usize = 0;
+
+    /// Output pin index
+    const OUTPUT: usize = 1;
+
+    /// Creates a stopped clock with a period of `2 * half_period` ticks
+    pub fn new(graph: &mut Graph, name: &str, half_period: usize) -> Self {
+        assert!(half_period > 0, "Clock half period must be at least 1 tick");
+        // Tick of the next edge while running
+        let mut next = None;
+        let pins = graph.new_timed_part(
+            name,
+            &[PinState::INPUT, PinState::Output(Signal::Low)],
+            move |pins, timer| {
+                if pins[Self::ENABLE].is_lowish() && pins[Self::OUTPUT].is_lowish() {
+                    next = None;
+                    return;
+                }
+                let due = *next.get_or_insert(timer.now() + half_period - 1);
+                if timer.now() >= due {
+                    toggle(&mut pins[Self::OUTPUT]);
+                    next = Some(due + half_period);
+                }
+                timer.wake_at(next.unwrap());
+            },
+        );
+        graph.mark_sequential(&pins[0]);
+        Self {
+            enable: pins[Self::ENABLE].clone(),
+            output: pins[Self::OUTPUT].clone(),
+        }
+    }
+}
+
+/// A clock that runs for a set number of cycles each time it is triggered
+///
+/// A rising edge on `trigger` raises the output straight away, then it toggles every `half_period`
+/// ticks until `cycles` full cycles have gone by. `busy` is High from the trigger until the last
+/// falling edge. Triggers while busy are ignored.
+#[derive(Debug)]
+pub struct BurstClock {
+    trigger: Pin,
+    output: Pin,
+    busy: Pin,
+}
+
+impl BurstClock {
+    /// Starts a burst on the rising edge
+    pub fn trigger(&self) -> &Pin {
+        &self.trigger
+    }
+
+    /// Clock output
+    pub fn output(&self) -> &Pin {
+        &self.output
+    }
+
+    /// High during a burst
+    pub fn busy(&self) -> &Pin {
+        &self.busy
+    }
+
+    /// Trigger pin index
+    const TRIGGER: usize = 0;
+
+    /// Output pin index
+    const OUTPUT: usize = 1;
+
+    /// Busy pin index
+    const BUSY: usize = 2;
+
+    // internal
+    /// Previous trigger state index
+    const TRIGGER_PREV: usize = 3;
+
+    /// Creates a burst clock that runs `cycles` periods of `2 * half_period` ticks per trigger
+    pub fn new(graph: &mut Graph, name: &str, half_period: usize, cycles: usize) -> Self {
+        assert!(half_period > 0, "Clock half period must be at least 1 tick");
+        // Tick of the next edge during a burst
+        let mut next = 0;
+        let mut edges_left = 0;
+        let pins = graph.new_timed_part(
+            name,
+            &[
+                PinState::INPUT,
+                PinState::Output(Signal::Low),
+                PinState::Output(Signal::Low),
+                PinState::INPUT,
+            ],
+            move |pins, timer| {
+                let triggered =
+                    pins[Self::TRIGGER].is_high() && pins[Self::TRIGGER_PREV].is_lowish();
+                pins[Self::TRIGGER_PREV] = pins[Self::TRIGGER];
+
+                if edges_left == 0 && triggered && cycles > 0 {
+                    next = timer.now() + half_period;
+                    edges_left = cycles * 2 - 1;
+                    pins[Self::OUTPUT] = PinState::Output(Signal::High);
+                } else if edges_left > 0 && timer.now() >= next {
+                    next += half_period;
+                    edges_left -= 1;
+                    toggle(&mut pins[Self::OUTPUT]);
+                }
+
+                let busy = if edges_left > 0 {
+                    timer.wake_at(next);
+                    Signal::High
+                } else {
+                    Signal::Low
+                };
+                pins[Self::BUSY] = PinState::Output(busy);
+            },
+        );
+        graph.mark_sequential(&pins[0]);
+        Self {
+            trigger: pins[Self::TRIGGER].clone(),
+            output: pins[Self::OUTPUT].clone(),
+            busy: pins[Self::BUSY].clone(),
+        }
+    }
+}
+
+/// A free-running multi-phase clock with non-overlapping phases
+///
+/// Each phase is High for `width` ticks in turn, followed by `gap` ticks with every phase Low, so
+/// that one phase has time to fall before the next rises. E.g. four phases for instruction, count,
+/// store and clear. The first phase rises on the first tick.
+#[derive(Debug)]
+pub struct PhaseClock {
+    phases: Vec<Pin>,
+}
+
+impl PhaseClock {
+    /// Phase outputs, in order
+    pub fn phases(&self) -> &[Pin] {
+        &self.phases
+    }
+
+    /// Output for the given phase
+    pub fn phase(&self, i: usize) -> &Pin {
+        &self.phases[i]
+    }
+
+    /// Creates a clock with a period of `phases * (width + gap)` ticks
+    pub fn new(graph: &mut Graph, name: &str, phases: usize, width: usize, gap: usize) -> Self {
+        assert!(phases > 0, "Phase clock needs at least one phase");
+        assert!(width > 0, "Phase width must be at least 1 tick");
+        let slot = width + gap;
+        let period = phases * slot;
+        // Tick of the first update, where the first phase rises
+        let mut start = None;
+        let pins = graph.new_timed_part(
+            name,
+            &vec![PinState::Output(Signal::Low); phases],
+            move |pins, timer| {
+                let position = (timer.now() - *start.get_or_insert(timer.now())) % period;
+                let active = position / slot;
+                let high = position % slot < width;
+                for (i, pin) in pins.iter_mut().enumerate() {
+                    let sig = if high && i == active {
+                        Signal::High
+                    } else {
+                        Signal::Low
+                    };
+                    *pin = PinState::Output(sig);
+                }
+                timer.wake_in(1);
+            },
+        );
+        graph.mark_sequential(&pins[0]);
+        Self { phases: pins }
+    }
+}
+
+#[cfg(test)]
+mod test_clock {
+    use crate::*;
+
+    /// Ticks the graph, recording the signal of each pin after every tick
+    fn trace(graph: &mut Graph, pins: &[&Pin], ticks: usize) -> Vec<Vec<Signal>> {
+        (0..ticks)
+            .map(|_| {
+                graph.tick();
+                pins.iter().map(|pin| pin.sig()).collect()
+            })
+            .collect()
+    }
+
+    /// Turns a trace of one pin into a string of 0s and 1s
+    fn bits(trace: &[Vec<Signal>], i: usize) -> String {
+        trace
+            .iter()
+            .map(|sigs| if sigs[i] == Signal::High { '1' } else { '0' })
+            .collect()
+    }
+
+    #[test]
+    fn test_clock() {
+        let mut graph = Graph::new();
+        let fast = Clock::new(&mut graph, "fast", 1);
+        let slow = Clock::new(&mut graph, "slow", 3);
+        let trace = trace(&mut graph, &[fast.output(), slow.output()], 12);
+        assert_eq!(bits(&trace, 0), "101010101010");
+        assert_eq!(bits(&trace, 1), "001110001110");
+
+        // The clock keeps the graph going
+        let stats = graph.run_for(100);
+        assert_eq!(stats.ticks, 100);
+    }
+
+    #[test]
+    fn test_gated() {
+        let mut graph = Graph::new();
+        let mut enable = graph.new_output("enable", Signal::Low);
+        let clock = GatedClock::new(&mut graph, "clock", 3);
+        graph.connect(&enable, clock.enable());
+        graph.run();
+        assert_eq!(clock.output().sig(), Signal::Low);
+
+        // The first tick propagates the enable
+        enable.set_output(Signal::High);
+        let trace_on = trace(&mut graph, &[clock.output()], 4);
+        assert_eq!(bits(&trace_on, 0), "0001");
+
+        // Stopping while High finishes the half period
+        enable.set_output(Signal::Low);
+        let trace_off = trace(&mut graph, &[clock.output()], 2);
+        assert_eq!(bits(&trace_off, 0), "11");
+        graph.run();
+        assert_eq!(clock.output().sig(), Signal::Low);
+    }
+
+    #[test]
+    fn test_burst() {
+        let mut graph = Graph::new();
+        let mut trigger = graph.new_output("trigger", Signal::Low);
+        let clock = BurstClock::new(&mut graph, "clock", 10, 3);
+        graph.connect(&trigger, clock.trigger());
+
+        // Count the rising edges with a counter
+        let counter = Counter8Bit::new(&mut graph, "counter");
+        let high = graph.new_output("high", Signal::High);
+        let low = graph.new_output("low", Signal::Low);
+        let mut clear = graph.new_output("clear", Signal::High);
+        graph.connect(clock.output(), counter.up());
+        graph.connect_all(&[&high, counter.down(), counter.load_inv()]);
+        graph.connect(&clear, counter.clear());
+        for pin in counter.input() {
+            graph.connect(&low, pin);
+        }
+        graph.run();
+        clear.set_output(Signal::Low);
+        graph.run();
+        let count = || counter.output().iter().val();
+        assert_eq!(count(), BusValue::new_val(0));
+
+        trigger.set_output(Signal::High);
+        graph.run_for(2);
+        assert_eq!(clock.busy().sig(), Signal::High);
+
+        // The graph settles on its own once the burst is done
+        let stats = graph.run();
+        assert_eq!(stats.cycle, 0);
+        assert_eq!(clock.busy().sig(), Signal::Low);
+        assert_eq!(clock.output().sig(), Signal::Low);
+        assert_eq!(count(), BusValue::new_val(3));
+
+        // Another burst needs another rising edge
+        graph.run();
+        assert_eq!(count(), BusValue::new_val(3));
+        trigger.set_output(Signal::Low);
+        graph.run();
+        trigger.set_output(Signal::High);
+        graph.run();
+        assert_eq!(count(), BusValue::new_val(6));
+    }
+
+    #[test]
+    fn test_phases() {
+        let mut graph = Graph::new();
+        let clock = PhaseClock::new(&mut graph, "clock", 4, 2, 1);
+        let pins = clock.phases().iter().collect::<Vec<_>>();
+        let trace = trace(&mut graph, &pins, 24);
+        assert_eq!(bits(&trace, 0), "110000000000110000000000");
+        assert_eq!(bits(&trace, 1), "000110000000000110000000");
+        assert_eq!(bits(&trace, 2), "000000110000000000110000");
+        assert_eq!(bits(&trace, 3), "000000000110000000000110");
+    }
+}
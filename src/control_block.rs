@@ -0,0 +1,362 @@
+use crate::*;
+
+use std::fmt::{Debug, Formatter};
+use std::iter::zip;
+
+/// Decodes instructions and generates the clock phases that drive the `DataBlock`
+///
+/// Each instruction takes three cycles of `clock`. The phases are high while `clock` is high:
+///
+/// Instruction - latches the next instruction and increments the program counter
+/// Count - increments any enabled counters (pointers, registers, etc.)
+/// Store - commits any changes (e.g. writing ram after data increments)
+pub struct ControlBlock {
+    clock: Pin,
+    reset: Pin,
+    skip: Pin,
+    hold: Pin,
+    instruction: Vec<Pin>,
+    decoded: Vec<Pin>,
+    instr: Pin,
+    count: Pin,
+    store: Pin,
+    d_ce: Pin,
+    p_ce: Pin,
+    i_ce: Pin,
+    o_ce: Pin,
+    up: Pin,
+    down: Pin,
+    phase: Ic74193,
+}
+
+impl Debug for ControlBlock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let phase = self.phase.output()[..2].iter().val();
+        f.debug_struct("ControlBlock")
+            .field("instruction", &self.instruction().iter().val())
+            .field("op", &self.opcode())
+            .field("phase", &phase)
+            .finish()
+    }
+}
+
+impl ControlBlock {
+    /// Master clock input. Each phase takes one cycle
+    pub fn clock(&self) -> &Pin {
+        &self.clock
+    }
+
+    /// Reset input
+    ///
+    /// While high, all phases are held low and the next cycle is an instruction phase
+    pub fn reset(&self) -> &Pin {
+        &self.reset
+    }
+
+    /// Skip input
+    ///
+    /// While high, instructions are fetched but don't enable the data or pointer counters. Loop
+    /// units use it to step over instructions without executing them
+    pub fn skip(&self) -> &Pin {
+        &self.skip
+    }
+
+    /// Hold input
+    ///
+    /// While high, the clock is ignored so the current phase is stretched, e.g. to wait for input
+    pub fn hold(&self) -> &Pin {
+        &self.hold
+    }
+
+    /// Instruction bus input
+    pub fn instruction(&self) -> &[Pin] {
+        &self.instruction
+    }
+
+    /// Output that is high while the instruction bus holds the given opcode, even when skipping
+    pub fn decoded(&self, op: Opcode) -> &Pin {
+        &self.decoded[op.index()]
+    }
+
+    /// The decoded instruction, or None for a no-op
+    pub fn opcode(&self) -> Option<Opcode> {
+        Opcode::ALL
+            .into_iter()
+            .find(|op| self.decoded(*op).sig().is_high())
+    }
+
+    /// Instruction phase clock output
+    pub fn instr(&self) -> &Pin {
+        &self.instr
+    }
+
+    /// Count phase clock output
+    pub fn count(&self) -> &Pin {
+        &self.count
+    }
+
+    /// Store phase clock output
+    pub fn store(&self) -> &Pin {
+        &self.store
+    }
+
+    /// Data count enable output, for `+` and `-`
+    pub fn d_ce(&self) -> &Pin {
+        &self.d_ce
+    }
+
+    /// Pointer count enable output, for `>` and `<`
+    pub fn p_ce(&self) -> &Pin {
+        &self.p_ce
+    }
+
+    /// Input enable output, for `,` after the instruction phase
+    pub fn i_ce(&self) -> &Pin {
+        &self.i_ce
+    }
+
+    /// Output enable output, for `.` after the instruction phase
+    pub fn o_ce(&self) -> &Pin {
+        &self.o_ce
+    }
+
+    /// Count up output, for `+` and `>`
+    pub fn up(&self) -> &Pin {
+        &self.up
+    }
+
+    /// Count down output, for `-` and `<`
+    pub fn down(&self) -> &Pin {
+        &self.down
+    }
+
+    /// Connects the outputs to the matching inputs of a `DataBlock`
+    ///
+    /// `i_ce` is left for the caller, since input has to go through I/O first
+    pub fn connect_data_block(&self, graph: &mut Graph, d_block: &DataBlock) {
+        graph.connect_pairs(&[
+            (&self.up, d_block.up()),
+            (&self.down, d_block.down()),
+            (&self.count, d_block.count()),
+            (&self.store, d_block.store()),
+            (&self.p_ce, d_block.p_ce()),
+            (&self.d_ce, d_block.d_ce()),
+        ]);
+    }
+
+    /// Create a new control block using the default opcode encoding
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        Self::with_encoding(graph, name, OpcodeEncoding::default())
+    }
+
+    /// Create a new control block that decodes the given opcode encoding
+    pub fn with_encoding(graph: &mut Graph, name: &str, encoding: OpcodeEncoding) -> Self {
+        let make_name = |n: &str| format!("{}.{}", name, n);
+
+        let clock = graph.new_input(&make_name("clock"));
+        let reset = graph.new_input(&make_name("reset"));
+        let skip = graph.new_input(&make_name("skip"));
+        let hold = graph.new_input(&make_name("hold"));
+        let instruction = graph.new_pins(&make_name("instruction"), &[PinState::INPUT; 8]);
+
+        // Each opcode is an 8-input and gate fed by the true or inverted instruction bits
+        let instruction_inv: Vec<Pin> = instruction.iter().map(|pin| !pin).collect();
+        let decoded: Vec<Pin> = Opcode::ALL
+            .iter()
+            .map(|op| {
+                let code = BusValue::new_val(encoding.encode(*op) as usize);
+                let gate = and_nary(graph, &make_name(&format!("{:?}", op)), 8);
+                for (i, (bit, bit_inv)) in zip(&instruction, &instruction_inv).enumerate() {
+                    if code.sig(i).is_high() {
+                        graph.connect(bit, gate.input_n(i));
+                    } else {
+                        graph.connect(bit_inv, gate.input_n(i));
+                    }
+                }
+                gate.output().clone()
+            })
+            .collect();
+        let op = |op: Opcode| &decoded[op.index()];
+
+        let execute = !&skip;
+        let d_ce = &(op(Opcode::Inc) | op(Opcode::Dec)) & &execute;
+        let p_ce = &(op(Opcode::Right) | op(Opcode::Left)) & &execute;
+        let down = op(Opcode::Dec) | op(Opcode::Left);
+        let up = !&down;
+
+        // Holding masks the clock, so the phase counter and the phase outputs stay put
+        let hold_inv = !&hold;
+        let clock_held = &clock & &hold_inv;
+
+        // The phase counter steps through 0, 1, 2 on the falling edge of the clock. Reaching 3
+        // clears it straight back to 0 while the clock is still low, so no phase sees it
+        let phase = Ic74193::new(graph, &make_name("phase"));
+        let high = graph.new_output(&make_name("HIGH"), Signal::High);
+        high.connect_all(&[phase.down(), phase.load_inv()]);
+        let clock_inv = !&clock_held;
+        graph.connect(&clock_inv, phase.up());
+
+        let [q0, q1, ..] = phase.output();
+        let wrap = q0 & q1;
+        let clear = &reset | &wrap;
+        graph.connect(&clear, phase.clear());
+
+        let q0_inv = !q0;
+        let q1_inv = !q1;
+        let reset_inv = !&reset;
+        let make_phase = |graph: &mut Graph, n: &str, a: &Pin, b: &Pin| {
+            let gate = and_nary(graph, &make_name(n), 4);
+            gate.connect_inputs(&[&clock_held, &reset_inv, a, b]);
+            gate.output().clone()
+        };
+        let instr = make_phase(graph, "instr", &q0_inv, &q1_inv);
+        let count = make_phase(graph, "count", q0, &q1_inv);
+        let store = make_phase(graph, "store", &q0_inv, q1);
+
+        // I/O is only enabled after the fetch, so back to back transfers are kept apart
+        let io_ce = &execute & &!&instr;
+        let i_ce = op(Opcode::Input) & &io_ce;
+        let o_ce = op(Opcode::Output) & &io_ce;
+
+        Self {
+            clock,
+            reset,
+            skip,
+            hold,
+            instruction,
+            decoded,
+            instr,
+            count,
+            store,
+            d_ce,
+            p_ce,
+            i_ce,
+            o_ce,
+            up,
+            down,
+            phase,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_control_block {
+    use crate::test_util::*;
+    use crate::*;
+
+    #[test]
+    fn test_phases() {
+        let mut graph = Graph::new();
+        let control = ControlBlock::new(&mut graph, "control");
+        let mut clock = drive_pin(&mut graph, control.clock(), Signal::Low);
+        let mut reset = drive_pin(&mut graph, control.reset(), Signal::High);
+        let mut skip = drive_pin(&mut graph, control.skip(), Signal::Low);
+        let mut hold = drive_pin(&mut graph, control.hold(), Signal::Low);
+        graph.run();
+        graph.pulse_output(&mut clock);
+        reset.set_output(Signal::Low);
+        graph.run();
+
+        let phases = [control.instr(), control.count(), control.store()];
+        let check_cycle = |graph: &mut Graph, clock: &mut Pin, phase: Option<usize>| {
+            clock.set_output(Signal::High);
+            graph.run();
+            let high: Vec<bool> = phases.iter().map(|p| p.sig().is_high()).collect();
+            let mut expected = [false; 3];
+            if let Some(phase) = phase {
+                expected[phase] = true;
+            }
+            assert_eq!(high, expected, "phase {:?}", phase);
+
+            clock.set_output(Signal::Low);
+            graph.run();
+            assert!(phases.iter().all(|p| p.sig() == Signal::Low));
+        };
+        for cycle in 0..6 {
+            check_cycle(&mut graph, &mut clock, Some(cycle % 3));
+        }
+
+        // Skipping still steps through the phases
+        skip.set_output(Signal::High);
+        graph.run();
+        for cycle in 0..3 {
+            check_cycle(&mut graph, &mut clock, Some(cycle));
+        }
+        skip.set_output(Signal::Low);
+
+        // Holding masks the clock, then carries on from the same phase
+        check_cycle(&mut graph, &mut clock, Some(0));
+        hold.set_output(Signal::High);
+        graph.run();
+        for _ in 0..2 {
+            check_cycle(&mut graph, &mut clock, None);
+        }
+        hold.set_output(Signal::Low);
+        graph.run();
+        check_cycle(&mut graph, &mut clock, Some(1));
+        check_cycle(&mut graph, &mut clock, Some(2));
+    }
+
+    #[test]
+    fn test_data_block() {
+        let mut graph = Graph::new();
+        let control = ControlBlock::new(&mut graph, "control");
+        let d_block = DataBlock::new(&mut graph, "data");
+        control.connect_data_block(&mut graph, &d_block);
+
+        let mut clock = drive_pin(&mut graph, control.clock(), Signal::Low);
+        let mut reset = drive_pin(&mut graph, control.reset(), Signal::High);
+        graph.connect(&reset, d_block.reset());
+        drive_pin(&mut graph, d_block.clear(), Signal::Low);
+        let mut skip = drive_pin(&mut graph, control.skip(), Signal::Low);
+        let mut hold = drive_pin(&mut graph, control.hold(), Signal::Low);
+        let mut instruction = drive(&mut graph, control.instruction());
+
+        graph.run();
+        reset.set_output(Signal::Low);
+        graph.run();
+
+        // The RAM only sees the low address bits of the pointer
+        let start = d_block.addr().iter().val().val;
+        let cell = |offset| (start + offset) % IcCY7C199::NUM_WORDS;
+        d_block.tape().poke(cell(1), 10);
+
+        // Skipped instructions are fetched but do nothing, and held ones wait for the hold to end
+        let encoding = OpcodeEncoding::default();
+        for (op, skipped, held) in [
+            ('+', false, false),
+            ('+', false, true),
+            ('>', false, false),
+            ('+', true, false),
+            ('+', false, false),
+            ('+', false, true),
+            ('-', true, false),
+            ('+', false, false),
+            ('<', false, false),
+            ('>', true, false),
+            ('-', false, false),
+        ] {
+            let code = encoding.encode(Opcode::from_char(op).unwrap());
+            set_bus(&mut instruction, code as usize);
+            skip.set_output(Signal::from(skipped));
+            hold.set_output(Signal::from(held));
+            graph.run();
+            for _ in 0..3 {
+                graph.pulse_output(&mut clock);
+            }
+            if held {
+                hold.set_output(Signal::Low);
+                graph.run();
+                for _ in 0..3 {
+                    graph.pulse_output(&mut clock);
+                }
+            }
+        }
+
+        let tape = d_block.tape();
+        assert_eq!(d_block.addr().iter().val().val, start);
+        assert_eq!(tape.peek(cell(0)), BusValue::new_val(1));
+        assert_eq!(tape.peek(cell(1)), BusValue::new_val(13));
+        assert_eq!(d_block.data().iter().val(), BusValue::new_val(1));
+    }
+}
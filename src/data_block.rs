@@ -6,6 +6,7 @@ use std::iter::zip;
 pub struct DataBlock {
     d_ce: Pin,
     p_ce: Pin,
+    i_ce: Pin,
     up: Pin,
     down: Pin,
     count: Pin,
@@ -14,6 +15,7 @@ pub struct DataBlock {
     clear: Pin,
     bus: BusBuffer,
     ptr: Counter16Bit,
+    tape: Memory,
 }
 
 impl Debug for DataBlock {
@@ -32,6 +34,14 @@ impl DataBlock {
     pub fn p_ce(&self) -> &Pin {
         &self.p_ce
     }
+
+    /// Input enable
+    ///
+    /// While high, neither the RAM nor the register drive the bus, and both take the value on the
+    /// bus during the store phase
+    pub fn i_ce(&self) -> &Pin {
+        &self.i_ce
+    }
     pub fn up(&self) -> &Pin {
         &self.up
     }
@@ -52,13 +62,23 @@ impl DataBlock {
     }
 
     pub fn data(&self) -> &[Pin] {
-        &self.bus.output()
+        self.bus.output()
+    }
+
+    /// The main data bus itself, for parts that drive it such as I/O
+    pub fn bus(&self) -> &[Pin] {
+        self.bus.input()
     }
 
     pub fn addr(&self) -> [&Pin; 16] {
         self.ptr.output()
     }
 
+    /// The contents of the data RAM, i.e. the brainfuck tape
+    pub fn tape(&self) -> &Memory {
+        &self.tape
+    }
+
     pub fn new(graph: &mut Graph, name: &str) -> Self {
         let make_name = |n: &str| format!("{}.{}", name, n);
 
@@ -91,7 +111,7 @@ impl DataBlock {
                 reg.input()[i],
                 &reg_interface.output()[i],
             ]);
-            graph.connect(&reg.output()[i], &reg_interface.input()[i]);
+            graph.connect(reg.output()[i], &reg_interface.input()[i]);
         }
 
         let up = graph.new_input(&make_name("up"));
@@ -103,6 +123,7 @@ impl DataBlock {
 
         let ptr_count_en = graph.new_input(&make_name("ptr_count_en"));
         let data_count_en = graph.new_input(&make_name("data_count_en"));
+        let input_en = graph.new_input(&make_name("input_en"));
 
         let reset = graph.new_input(&make_name("reset"));
 
@@ -119,17 +140,19 @@ impl DataBlock {
         //
         // During normal operation, the register output is enabled when data
         // count is enabled (i.e. we want to see the result of `+` and `-`).
-        // Otherwise ram output is enabled. TODO: need to disable ram out when
-        // doing an input `,` operation
+        // Otherwise ram output is enabled, except during an input `,` operation
+        // when the bus is driven from outside
         //
         // During reset, the register is outputting zero, so we want the RAM
         // to read that while cycling through the address space. This will
-        // clear the contents of RAM (NYI)
+        // clear the contents of RAM
         let reg_not_ram = &reset | &data_count_en;
 
         // Since RAM OE is inverted but the tristate enable is not, we can
-        // just connect them all to the same control signal
-        graph.connect_all(&[&reg_not_ram, reg_interface.en(), ram.oe_inv()]);
+        // mostly connect them to the same control signal
+        graph.connect(&reg_not_ram, reg_interface.en());
+        let ram_oe_inv = &reg_not_ram | &input_en;
+        graph.connect(&ram_oe_inv, ram.oe_inv());
 
         // Count the data register up or down on the count clock when enabled
         let reg_count = &count_clock & &data_count_en;
@@ -153,17 +176,24 @@ impl DataBlock {
         graph.connect(ptr_up.output(), ptr.up());
         graph.connect(ptr_down.output(), ptr.down());
 
+        // The clear clock sweeps the pointer through the RAM during reset. The RAM doesn't see the
+        // top bit, so clearing the pointer when it's set wraps it back to 0 after a full sweep.
+        // Sweeping a multiple of the RAM size leaves the pointer at 0 when reset ends
+        let ptr_wrap = &reset & ptr.output()[15];
+        graph.connect(&ptr_wrap, ptr.clear());
+
         // Load the reg from RAM on the store clock when ptr count is enabled (i.e. after ptr
-        // crements)
+        // crements), or from the bus when doing input
         let reg_load = nand_gate(graph, "reg_load");
+        let reg_load_en = &ptr_count_en | &input_en;
         graph.connect(&store_clock, reg_load.input_a());
-        graph.connect(&ptr_count_en, reg_load.input_b());
+        graph.connect(&reg_load_en, reg_load.input_b());
         graph.connect(reg_load.output(), reg.load_inv());
 
         // Write from the bus to RAM on the store clock when data count is enabled (i.e. after reg
-        // crements).
+        // crements) or when doing input
         let ram_we = nor_gate(graph, "ram_we");
-        let write = &store_clock & &data_count_en;
+        let write = &store_clock & &(&data_count_en | &input_en);
         graph.connect(&reset, ram_we.input_a());
         graph.connect(&write, ram_we.input_b());
         graph.connect(ram_we.output(), ram.we_inv());
@@ -172,6 +202,7 @@ impl DataBlock {
             bus,
             d_ce: data_count_en,
             p_ce: ptr_count_en,
+            i_ce: input_en,
             up,
             ptr,
             down,
@@ -179,6 +210,7 @@ impl DataBlock {
             store: store_clock,
             reset,
             clear: clear_clock,
+            tape: ram.memory().clone(),
         }
     }
 }
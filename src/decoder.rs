@@ -0,0 +1,540 @@
+use crate::*;
+
+// Off inputs count as Low, as for the storage primitives. Errors spread the way they would through
+// the chips' gates, so an Error select only spoils outputs that depend on it.
+
+/// Logical And where a Low input wins over an Error, like a real gate
+fn and(a: Signal, b: Signal) -> Signal {
+    if a == Signal::Low || b == Signal::Low {
+        Signal::Low
+    } else {
+        a & b
+    }
+}
+
+/// High when the select pins hold `n`
+fn matches(select: &[PinState], n: usize) -> Signal {
+    select
+        .iter()
+        .enumerate()
+        .map(|(bit, pin)| {
+            let sig = level(*pin);
+            if n & (1 << bit) != 0 {
+                sig
+            } else {
+                !sig
+            }
+        })
+        .fold(Signal::High, and)
+}
+
+/// Drives active-Low decoder outputs from the enable and select pins
+fn decode(enable: Signal, select: &[PinState], outputs_inv: &mut [PinState]) {
+    for (n, pin) in outputs_inv.iter_mut().enumerate() {
+        *pin = PinState::Output(!and(enable, matches(select, n)));
+    }
+}
+
+/// Picks one of the inputs using the select pins, least-significant first
+fn mux(select: &[PinState], inputs: &[PinState]) -> Signal {
+    match select.split_last() {
+        None => level(inputs[0]),
+        Some((top, rest)) => {
+            let (low, high) = inputs.split_at(inputs.len() / 2);
+            choose(level(*top), mux(rest, high), mux(rest, low))
+        }
+    }
+}
+
+/// 3-to-8 line decoder, 74138
+///
+/// While enabled, the output selected by A0-A2 is Low and the rest are High. Enabled needs E̅1 and
+/// E̅2 Low and E3 High. All outputs are High otherwise.
+#[derive(Debug)]
+pub struct Ic74138 {
+    pins: Vec<Pin>,
+}
+
+impl Ic74138 {
+    /// Select pin starting index
+    pub(crate) const SELECT_START: usize = 0;
+
+    /// Select pin ending index
+    pub(crate) const SELECT_END: usize = Self::SELECT_START + 3;
+
+    /// First inverse enable pin index
+    pub(crate) const E1_INV: usize = Self::SELECT_END;
+
+    /// Second inverse enable pin index
+    pub(crate) const E2_INV: usize = Self::E1_INV + 1;
+
+    /// Third enable pin index
+    pub(crate) const E3: usize = Self::E2_INV + 1;
+
+    /// Inverse output pin starting index
+    pub(crate) const OUTPUT_START: usize = Self::E3 + 1;
+
+    /// Inverse output pin ending index
+    pub(crate) const OUTPUT_END: usize = Self::OUTPUT_START + 8;
+
+    /// Creates a 74138 decoder
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let mut states = [PinState::INPUT; Self::OUTPUT_END];
+        states[Self::OUTPUT_START..].fill(PinState::OUTPUT);
+        let pins = graph.new_part(name, &states, |pins| {
+            let enable = and(
+                and(!level(pins[Self::E1_INV]), !level(pins[Self::E2_INV])),
+                level(pins[Self::E3]),
+            );
+            let (inputs, outputs) = pins.split_at_mut(Self::OUTPUT_START);
+            decode(
+                enable,
+                &inputs[Self::SELECT_START..Self::SELECT_END],
+                outputs,
+            );
+        });
+        Self { pins }
+    }
+
+    /// Select inputs (A0-A2), least-significant first
+    pub fn select(&self) -> &[Pin] {
+        &self.pins[Self::SELECT_START..Self::SELECT_END]
+    }
+
+    /// First inverse enable (E̅1)
+    pub fn e1_inv(&self) -> &Pin {
+        &self.pins[Self::E1_INV]
+    }
+
+    /// Second inverse enable (E̅2)
+    pub fn e2_inv(&self) -> &Pin {
+        &self.pins[Self::E2_INV]
+    }
+
+    /// Third enable (E3), active High
+    pub fn e3(&self) -> &Pin {
+        &self.pins[Self::E3]
+    }
+
+    /// Inverse outputs (Y̅0-Y̅7)
+    pub fn output_inv(&self) -> &[Pin] {
+        &self.pins[Self::OUTPUT_START..Self::OUTPUT_END]
+    }
+}
+
+/// One half of a 74139
+///
+/// While E̅ is Low, the output selected by A0-A1 is Low and the rest are High. All outputs are High
+/// otherwise.
+#[derive(Debug)]
+pub struct Ic74139Half {
+    pins: Vec<Pin>,
+}
+
+impl Ic74139Half {
+    /// Inverse enable pin index
+    pub(crate) const ENABLE_INV: usize = 0;
+
+    /// Select pin starting index
+    pub(crate) const SELECT_START: usize = 1;
+
+    /// Select pin ending index
+    pub(crate) const SELECT_END: usize = Self::SELECT_START + 2;
+
+    /// Inverse output pin starting index
+    pub(crate) const OUTPUT_START: usize = Self::SELECT_END;
+
+    /// Inverse output pin ending index
+    pub(crate) const OUTPUT_END: usize = Self::OUTPUT_START + 4;
+
+    fn new(graph: &mut Graph, name: &str) -> Self {
+        let mut states = [PinState::INPUT; Self::OUTPUT_END];
+        states[Self::OUTPUT_START..].fill(PinState::OUTPUT);
+        let pins = graph.new_part(name, &states, |pins| {
+            let enable = !level(pins[Self::ENABLE_INV]);
+            let (inputs, outputs) = pins.split_at_mut(Self::OUTPUT_START);
+            decode(
+                enable,
+                &inputs[Self::SELECT_START..Self::SELECT_END],
+                outputs,
+            );
+        });
+        Self { pins }
+    }
+
+    /// Inverse enable (E̅)
+    pub fn enable_inv(&self) -> &Pin {
+        &self.pins[Self::ENABLE_INV]
+    }
+
+    /// Select inputs (A0-A1), least-significant first
+    pub fn select(&self) -> &[Pin] {
+        &self.pins[Self::SELECT_START..Self::SELECT_END]
+    }
+
+    /// Inverse outputs (Y̅0-Y̅3)
+    pub fn output_inv(&self) -> &[Pin] {
+        &self.pins[Self::OUTPUT_START..Self::OUTPUT_END]
+    }
+}
+
+/// Dual 2-to-4 line decoder, 74139
+#[derive(Debug)]
+pub struct Ic74139 {
+    half1: Ic74139Half,
+    half2: Ic74139Half,
+}
+
+impl Ic74139 {
+    /// Creates a 74139 with two independent decoders
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let make_name = |n| format!("{}.{}", name, n);
+        Self {
+            half1: Ic74139Half::new(graph, &make_name("half1")),
+            half2: Ic74139Half::new(graph, &make_name("half2")),
+        }
+    }
+
+    /// First decoder (1E̅, 1A0-1A1, 1Y̅0-1Y̅3)
+    pub fn half1(&self) -> &Ic74139Half {
+        &self.half1
+    }
+
+    /// Second decoder (2E̅, 2A0-2A1, 2Y̅0-2Y̅3)
+    pub fn half2(&self) -> &Ic74139Half {
+        &self.half2
+    }
+}
+
+/// 8-to-1 line multiplexer, 74151
+///
+/// While E̅ is Low, Y follows the input selected by S0-S2 and Y̅ is its inverse. Otherwise Y is Low
+/// and Y̅ is High.
+#[derive(Debug)]
+pub struct Ic74151 {
+    pins: Vec<Pin>,
+}
+
+impl Ic74151 {
+    /// Data input starting index
+    pub(crate) const INPUT_START: usize = 0;
+
+    /// Data input ending index
+    pub(crate) const INPUT_END: usize = Self::INPUT_START + 8;
+
+    /// Select pin starting index
+    pub(crate) const SELECT_START: usize = Self::INPUT_END;
+
+    /// Select pin ending index
+    pub(crate) const SELECT_END: usize = Self::SELECT_START + 3;
+
+    /// Inverse enable pin index
+    pub(crate) const ENABLE_INV: usize = Self::SELECT_END;
+
+    /// Output pin index
+    pub(crate) const OUTPUT: usize = Self::ENABLE_INV + 1;
+
+    /// Inverse output pin index
+    pub(crate) const OUTPUT_INV: usize = Self::OUTPUT + 1;
+
+    /// Creates a 74151 multiplexer
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let mut states = [PinState::INPUT; Self::OUTPUT_INV + 1];
+        states[Self::OUTPUT] = PinState::OUTPUT;
+        states[Self::OUTPUT_INV] = PinState::OUTPUT;
+        let pins = graph.new_part(name, &states, |pins| {
+            let selected = mux(
+                &pins[Self::SELECT_START..Self::SELECT_END],
+                &pins[Self::INPUT_START..Self::INPUT_END],
+            );
+            let output = and(!level(pins[Self::ENABLE_INV]), selected);
+            pins[Self::OUTPUT] = PinState::Output(output);
+            pins[Self::OUTPUT_INV] = PinState::Output(!output);
+        });
+        Self { pins }
+    }
+
+    /// Data inputs (I0-I7)
+    pub fn input(&self) -> &[Pin] {
+        &self.pins[Self::INPUT_START..Self::INPUT_END]
+    }
+
+    /// Select inputs (S0-S2), least-significant first
+    pub fn select(&self) -> &[Pin] {
+        &self.pins[Self::SELECT_START..Self::SELECT_END]
+    }
+
+    /// Inverse enable (E̅)
+    pub fn enable_inv(&self) -> &Pin {
+        &self.pins[Self::ENABLE_INV]
+    }
+
+    /// Output (Y)
+    pub fn output(&self) -> &Pin {
+        &self.pins[Self::OUTPUT]
+    }
+
+    /// Inverse output (Y̅)
+    pub fn output_inv(&self) -> &Pin {
+        &self.pins[Self::OUTPUT_INV]
+    }
+}
+
+/// Quad 2-to-1 line multiplexer, 74157
+///
+/// While E̅ is Low, each output follows its I0 input when S is Low and its I1 input when S is High.
+/// Otherwise the outputs are Low.
+#[derive(Debug)]
+pub struct Ic74157 {
+    pins: Vec<Pin>,
+}
+
+impl Ic74157 {
+    /// Select pin index
+    pub(crate) const SELECT: usize = 0;
+
+    /// Inverse enable pin index
+    pub(crate) const ENABLE_INV: usize = 1;
+
+    /// I0 input starting index
+    pub(crate) const INPUT0_START: usize = 2;
+
+    /// I1 input starting index
+    pub(crate) const INPUT1_START: usize = Self::INPUT0_START + 4;
+
+    /// Output pin starting index
+    pub(crate) const OUTPUT_START: usize = Self::INPUT1_START + 4;
+
+    /// Output pin ending index
+    pub(crate) const OUTPUT_END: usize = Self::OUTPUT_START + 4;
+
+    /// Creates a 74157 multiplexer
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let mut states = [PinState::INPUT; Self::OUTPUT_END];
+        states[Self::OUTPUT_START..].fill(PinState::OUTPUT);
+        let pins = graph.new_part(name, &states, |pins| {
+            let enable = !level(pins[Self::ENABLE_INV]);
+            for i in 0..4 {
+                let inputs = [pins[Self::INPUT0_START + i], pins[Self::INPUT1_START + i]];
+                let selected = mux(&pins[Self::SELECT..=Self::SELECT], &inputs);
+                pins[Self::OUTPUT_START + i] = PinState::Output(and(enable, selected));
+            }
+        });
+        Self { pins }
+    }
+
+    /// Select input (S)
+    pub fn select(&self) -> &Pin {
+        &self.pins[Self::SELECT]
+    }
+
+    /// Inverse enable (E̅)
+    pub fn enable_inv(&self) -> &Pin {
+        &self.pins[Self::ENABLE_INV]
+    }
+
+    /// Inputs selected when S is Low (I0a-I0d)
+    pub fn input0(&self) -> &[Pin] {
+        &self.pins[Self::INPUT0_START..Self::INPUT1_START]
+    }
+
+    /// Inputs selected when S is High (I1a-I1d)
+    pub fn input1(&self) -> &[Pin] {
+        &self.pins[Self::INPUT1_START..Self::OUTPUT_START]
+    }
+
+    /// Outputs (Ya-Yd)
+    pub fn output(&self) -> &[Pin] {
+        &self.pins[Self::OUTPUT_START..Self::OUTPUT_END]
+    }
+}
+
+#[cfg(test)]
+mod test_decoder {
+    use crate::test_util::*;
+    use crate::*;
+
+    /// Checks a part against a datasheet function table
+    ///
+    /// Each row lists the inputs, a `|`, then the outputs, as H, L or X for don't care. Spaces are
+    /// ignored. An output can also name an input by its position, counting up from `0` through the
+    /// characters after `9`, to follow it, or `!` and a position to follow its inverse. Every
+    /// combination of the X inputs is tried.
+    fn check_table(inputs: &[&Pin], outputs: &[&Pin], table: &[&str]) {
+        let mut graph = inputs[0].graph();
+        let mut drivers = drive(&mut graph, inputs.iter().copied());
+
+        for row in table {
+            let row = row.replace(' ', "");
+            let (ins, outs) = row.split_once('|').unwrap();
+            let ins: Vec<char> = ins.chars().collect();
+            let outs: Vec<(bool, char)> = outs
+                .split_inclusive(|c| c != '!')
+                .map(|out| (out.starts_with('!'), out.chars().last().unwrap()))
+                .collect();
+            assert_eq!(ins.len(), inputs.len(), "{}", row);
+            assert_eq!(outs.len(), outputs.len(), "{}", row);
+
+            let dont_care: Vec<usize> = (0..ins.len()).filter(|i| ins[*i] == 'X').collect();
+            for combo in 0..1 << dont_care.len() {
+                let mut sigs: Vec<Signal> = ins.iter().map(|c| Signal::from(*c == 'H')).collect();
+                for (bit, i) in dont_care.iter().enumerate() {
+                    sigs[*i] = Signal::from(combo & (1 << bit) != 0);
+                }
+                set_signals(&mut drivers, &sigs);
+                graph.run();
+
+                for (pin, (invert, c)) in outputs.iter().zip(outs.iter()) {
+                    let expected = match c {
+                        'H' => Signal::High,
+                        'L' => Signal::Low,
+                        n => sigs[*n as usize - '0' as usize],
+                    };
+                    let expected = if *invert { !expected } else { expected };
+                    assert_eq!(pin.sig(), expected, "{} with inputs {:?}", row, sigs);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_74138() {
+        let mut graph = Graph::new();
+        let ic = Ic74138::new(&mut graph, "ic");
+        let [a0, a1, a2] = [0, 1, 2].map(|i| &ic.select()[i]);
+        let outputs: Vec<&Pin> = ic.output_inv().iter().collect();
+        #[rustfmt::skip]
+        check_table(
+            &[ic.e1_inv(), ic.e2_inv(), ic.e3(), a2, a1, a0],
+            &outputs,
+            &[
+                // E̅1 E̅2 E3 A2 A1 A0 | Y̅0-Y̅7
+                "H X X XXX | HHHHHHHH",
+                "X H X XXX | HHHHHHHH",
+                "X X L XXX | HHHHHHHH",
+                "L L H LLL | LHHHHHHH",
+                "L L H LLH | HLHHHHHH",
+                "L L H LHL | HHLHHHHH",
+                "L L H LHH | HHHLHHHH",
+                "L L H HLL | HHHHLHHH",
+                "L L H HLH | HHHHHLHH",
+                "L L H HHL | HHHHHHLH",
+                "L L H HHH | HHHHHHHL",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_74139() {
+        let mut graph = Graph::new();
+        let ic = Ic74139::new(&mut graph, "ic");
+        for half in [ic.half1(), ic.half2()] {
+            let outputs: Vec<&Pin> = half.output_inv().iter().collect();
+            check_table(
+                &[half.enable_inv(), &half.select()[1], &half.select()[0]],
+                &outputs,
+                &[
+                    // E̅ A1 A0 | Y̅0-Y̅3
+                    "H XX | HHHH",
+                    "L LL | LHHH",
+                    "L LH | HLHH",
+                    "L HL | HHLH",
+                    "L HH | HHHL",
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn test_74151() {
+        let mut graph = Graph::new();
+        let ic = Ic74151::new(&mut graph, "ic");
+        let mut inputs = vec![
+            ic.enable_inv(),
+            &ic.select()[2],
+            &ic.select()[1],
+            &ic.select()[0],
+        ];
+        inputs.extend(ic.input().iter());
+        check_table(
+            &inputs,
+            &[ic.output(), ic.output_inv()],
+            &[
+                // E̅ S2 S1 S0 I0-I7 | Y Y̅, where I0-I7 are inputs 4 to ;
+                "H XXX XXXXXXXX | L H",
+                "L LLL XXXXXXXX | 4 !4",
+                "L LLH XXXXXXXX | 5 !5",
+                "L LHL XXXXXXXX | 6 !6",
+                "L LHH XXXXXXXX | 7 !7",
+                "L HLL XXXXXXXX | 8 !8",
+                "L HLH XXXXXXXX | 9 !9",
+                "L HHL XXXXXXXX | : !:",
+                "L HHH XXXXXXXX | ; !;",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_74157() {
+        for i in 0..4 {
+            // Each channel gets its own chip, so the shared pins only have one driver each
+            let mut graph = Graph::new();
+            let ic = Ic74157::new(&mut graph, "ic");
+            check_table(
+                &[
+                    ic.enable_inv(),
+                    ic.select(),
+                    &ic.input0()[i],
+                    &ic.input1()[i],
+                ],
+                &[&ic.output()[i]],
+                &[
+                    // E̅ S I0 I1 | Y
+                    "H X XX | L",
+                    "L L LX | L",
+                    "L L HX | H",
+                    "L H XL | L",
+                    "L H XH | H",
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        // An Error select only matters if the selected inputs or outputs differ
+        let mut graph = Graph::new();
+        let mux = Ic74157::new(&mut graph, "mux");
+        let decoder = Ic74138::new(&mut graph, "decoder");
+        let mut select = graph.new_output("select", Signal::Low);
+        let low = graph.new_output("low", Signal::Low);
+        let high = graph.new_output("high", Signal::High);
+        graph.connect_all(&[&select, mux.select(), &decoder.select()[0]]);
+        graph.connect_all(&[
+            &low,
+            mux.enable_inv(),
+            &mux.input0()[0],
+            &mux.input1()[0],
+            &mux.input0()[2],
+        ]);
+        graph.connect_all(&[&high, &mux.input0()[1], &mux.input1()[1], &mux.input1()[2]]);
+        graph.connect_all(&[
+            &low,
+            decoder.e1_inv(),
+            decoder.e2_inv(),
+            &decoder.select()[1],
+            &decoder.select()[2],
+        ]);
+        graph.connect(&high, decoder.e3());
+        graph.run();
+        select.set_output(Signal::Error);
+        graph.run();
+
+        assert_eq!(mux.output()[0].sig(), Signal::Low);
+        assert_eq!(mux.output()[1].sig(), Signal::High);
+        assert_eq!(mux.output()[2].sig(), Signal::Error);
+        let outputs: Vec<Signal> = decoder.output_inv().iter().map(|pin| pin.sig()).collect();
+        assert_eq!(outputs[..2], [Signal::Error, Signal::Error]);
+        assert!(outputs[2..].iter().all(|sig| *sig == Signal::High));
+    }
+}
@@ -0,0 +1,280 @@
+use crate::*;
+use std::collections::{BTreeMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+/// Propagation delay from a part's pins to their nodes
+///
+/// The rise delay applies to changes to High, and the fall delay to everything else. Delays are at
+/// least one tick, which is how long every part takes without one.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Delay {
+    /// Delays in ticks
+    Ticks { rise: usize, fall: usize },
+
+    /// Delays in nanoseconds, rounded up to whole ticks using `Graph::tick_ns`
+    Ns { rise: f64, fall: f64 },
+}
+
+impl Delay {
+    /// Typical delays of a 74LS00 NAND gate
+    pub const LS_GATE: Delay = Delay::Ns {
+        rise: 9.0,
+        fall: 10.0,
+    };
+
+    /// Typical delays of a 74HC00 NAND gate at 5V
+    pub const HC_GATE: Delay = Delay::Ns {
+        rise: 8.0,
+        fall: 8.0,
+    };
+
+    /// Creates a delay in ticks
+    pub fn ticks(rise: usize, fall: usize) -> Self {
+        Delay::Ticks { rise, fall }
+    }
+
+    /// Creates a delay in nanoseconds
+    pub fn ns(rise: f64, fall: f64) -> Self {
+        Delay::Ns { rise, fall }
+    }
+
+    /// Number of ticks before a change to the given state reaches the node
+    pub fn to_ticks(&self, state: PinState, tick_ns: f64) -> usize {
+        let rising = state.sig() == Signal::High;
+        let ticks = match *self {
+            Delay::Ticks { rise, fall } => {
+                if rising {
+                    rise
+                } else {
+                    fall
+                }
+            }
+            Delay::Ns { rise, fall } => {
+                let ns = if rising { rise } else { fall };
+                (ns / tick_ns).ceil() as usize
+            }
+        };
+        ticks.max(1)
+    }
+}
+
+/// Pin changes waiting for their delays to pass
+///
+/// A ring of slots indexed by tick, each listing the pins with a change due then. Changes follow
+/// transport delay rules: a new change for a pin cancels any of its changes due at the same time or
+/// later, so pulses shorter than the delay still get through, like real glitches.
+#[derive(Default)]
+pub(crate) struct EventWheel {
+    /// Pins with changes due, indexed by tick modulo the ring size
+    slots: Vec<Vec<PinId>>,
+
+    /// Each pin's changes in the order they are due
+    pending: BTreeMap<PinId, VecDeque<(usize, PinState)>>,
+}
+
+impl EventWheel {
+    /// Whether no changes are waiting
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Drops every waiting change
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(Vec::clear);
+        self.pending.clear();
+    }
+
+    /// Cancels the pin's changes due at or after `time`
+    pub fn cancel(&mut self, pin_id: PinId, time: usize) {
+        if let Some(events) = self.pending.get_mut(&pin_id) {
+            while events.back().is_some_and(|(due, _)| *due >= time) {
+                events.pop_back();
+            }
+            if events.is_empty() {
+                self.pending.remove(&pin_id);
+            }
+        }
+    }
+
+    /// Schedules a change to the pin's state at tick `time`, `time - now` ticks from now
+    pub fn schedule(&mut self, now: usize, pin_id: PinId, time: usize, state: PinState) {
+        if time - now >= self.slots.len() {
+            self.grow(time - now + 1);
+        }
+        self.cancel(pin_id, time);
+        self.pending
+            .entry(pin_id)
+            .or_default()
+            .push_back((time, state));
+        let len = self.slots.len();
+        self.slots[time % len].push(pin_id);
+    }
+
+    /// Removes the changes due at tick `time`
+    pub fn take(&mut self, time: usize) -> Vec<(PinId, PinState)> {
+        let mut due = Vec::new();
+        if self.pending.is_empty() {
+            return due;
+        }
+        let len = self.slots.len();
+        for pin_id in std::mem::take(&mut self.slots[time % len]) {
+            let Some(events) = self.pending.get_mut(&pin_id) else {
+                continue;
+            };
+            // Canceled changes leave their pins behind in the slots
+            if events.front().is_some_and(|(t, _)| *t == time) {
+                let (_, state) = events.pop_front().unwrap();
+                due.push((pin_id, state));
+            }
+            if events.is_empty() {
+                self.pending.remove(&pin_id);
+            }
+        }
+        due
+    }
+
+    /// The tick the next change is due, if any
+    pub fn next_due(&self) -> Option<usize> {
+        self.pending
+            .values()
+            .filter_map(|events| events.front())
+            .map(|(time, _)| *time)
+            .min()
+    }
+
+    /// Hashes the waiting changes relative to the current tick, for cycle detection
+    pub fn hash_from<H: Hasher>(&self, now: usize, state: &mut H) {
+        for (pin_id, events) in self.pending.iter() {
+            pin_id.hash(state);
+            for (time, pin_state) in events {
+                (time - now).hash(state);
+                pin_state.hash(state);
+            }
+        }
+    }
+
+    /// Resizes the ring to hold at least `len` ticks
+    fn grow(&mut self, len: usize) {
+        let len = len.next_power_of_two();
+        self.slots = vec![Vec::new(); len];
+        for (pin_id, events) in self.pending.iter() {
+            for (time, _) in events {
+                self.slots[time % len].push(*pin_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_delay {
+    use crate::*;
+
+    /// Ticks the graph, recording whether the pin is High after every tick
+    fn trace(graph: &mut Graph, pin: &Pin, ticks: usize) -> String {
+        (0..ticks)
+            .map(|_| {
+                graph.tick();
+                if pin.sig() == Signal::High {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_to_ticks() {
+        let high = PinState::Output(Signal::High);
+        let low = PinState::Output(Signal::Low);
+        assert_eq!(Delay::ticks(3, 0).to_ticks(high, 1.0), 3);
+        assert_eq!(Delay::ticks(3, 0).to_ticks(low, 1.0), 1);
+        assert_eq!(Delay::LS_GATE.to_ticks(high, 5.0), 2);
+        assert_eq!(Delay::LS_GATE.to_ticks(low, 5.0), 2);
+        assert_eq!(Delay::LS_GATE.to_ticks(low, 2.5), 4);
+    }
+
+    #[test]
+    fn test_rise_fall() {
+        let mut graph = Graph::new();
+        let mut input = graph.new_output("input", Signal::Low);
+        let buffer = buffer(&mut graph, "buffer");
+        graph.connect(&input, buffer.input());
+        graph.set_delay(buffer.output(), Delay::ticks(3, 1));
+        graph.run();
+
+        // The first tick gets the input to the buffer
+        input.set_output(Signal::High);
+        let other = graph.new_input("other");
+        graph.connect(buffer.output(), &other);
+        assert_eq!(trace(&mut graph, &other, 5), "00011");
+        input.set_output(Signal::Low);
+        assert_eq!(trace(&mut graph, &other, 3), "100");
+
+        // Ns delays use the graph's tick length
+        graph.set_tick_ns(4.0);
+        graph.set_delay(buffer.output(), Delay::ns(10.0, 10.0));
+        input.set_output(Signal::High);
+        assert_eq!(trace(&mut graph, &other, 5), "00011");
+    }
+
+    #[test]
+    fn test_glitch() {
+        // a & !a is never High in theory, but the inverter's delay lets a pulse through
+        let glitch_width = |delay| {
+            let mut graph = Graph::new();
+            let mut a = graph.new_output("a", Signal::Low);
+            let not = not_gate(&mut graph, "not");
+            let and = and_gate(&mut graph, "and");
+            graph.connect_all(&[&a, not.input(), and.input_a()]);
+            graph.connect(not.output(), and.input_b());
+            graph.set_delay(not.output(), delay);
+            graph.run();
+            assert_eq!(and.output().sig(), Signal::Low);
+
+            a.set_output(Signal::High);
+            let trace = trace(&mut graph, and.output(), 20);
+            trace.chars().filter(|c| *c == '1').count()
+        };
+        assert_eq!(glitch_width(Delay::ticks(1, 1)), 1);
+        assert_eq!(glitch_width(Delay::ticks(1, 5)), 5);
+    }
+
+    #[test]
+    fn test_transport() {
+        // A pulse shorter than the delay still gets through, just later
+        let mut graph = Graph::new();
+        let mut input = graph.new_output("input", Signal::Low);
+        let buffer = buffer(&mut graph, "buffer");
+        let other = graph.new_input("other");
+        graph.connect(&input, buffer.input());
+        graph.connect(buffer.output(), &other);
+        graph.set_delay(buffer.output(), Delay::ticks(4, 4));
+        graph.run();
+
+        input.set_output(Signal::High);
+        graph.tick();
+        input.set_output(Signal::Low);
+        assert_eq!(trace(&mut graph, &other, 8), "00010000");
+    }
+
+    #[test]
+    fn test_run() {
+        // `run` waits for delayed changes, even when no nodes change in between
+        let mut graph = Graph::new();
+        let mut input = graph.new_output("input", Signal::Low);
+        let buffer = buffer(&mut graph, "buffer");
+        let other = graph.new_input("other");
+        graph.connect(&input, buffer.input());
+        graph.connect(buffer.output(), &other);
+        graph.set_default_delay(Some(Delay::ticks(50, 50)));
+        graph.run();
+        assert_eq!(other.sig(), Signal::Low);
+
+        input.set_output(Signal::High);
+        let stats = graph.run();
+        assert_eq!(other.sig(), Signal::High);
+        assert_eq!(stats.cycle, 0);
+        assert!(stats.ticks > 50);
+    }
+}
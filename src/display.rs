@@ -0,0 +1,532 @@
+use crate::*;
+
+// Displays are bare input pins with no part, since they don't drive anything. Their looks are
+// worked out from the pins when asked.
+
+/// How a light on a display looks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Light {
+    Dark,
+    Lit,
+
+    /// Driven with an Error, so it could be either
+    Error,
+}
+
+impl Light {
+    /// How a pin looks when `on` lights it. Off pins are dark
+    fn of(pin: &Pin, on: Signal) -> Self {
+        match pin.sig() {
+            Signal::Error => Light::Error,
+            sig if sig == on => Light::Lit,
+            _ => Light::Dark,
+        }
+    }
+
+    /// Picks the character for the light
+    fn glyph(self, lit: char, error: char) -> char {
+        match self {
+            Light::Dark => ' ',
+            Light::Lit => lit,
+            Light::Error => error,
+        }
+    }
+}
+
+/// Characters to render displays with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderStyle {
+    #[default]
+    Unicode,
+    Ascii,
+}
+
+impl RenderStyle {
+    /// Characters for a lit LED, a dark one and an Error
+    fn leds(self) -> (char, char, char) {
+        match self {
+            RenderStyle::Unicode => ('●', '○', '◌'),
+            RenderStyle::Ascii => ('*', '.', '?'),
+        }
+    }
+}
+
+/// Places multi-line renders next to each other, one space apart
+pub fn side_by_side(renders: &[String]) -> String {
+    let blocks: Vec<Vec<&str>> = renders.iter().map(|r| r.lines().collect()).collect();
+    let height = blocks.iter().map(Vec::len).max().unwrap_or(0);
+    (0..height)
+        .map(|row| {
+            let line = blocks
+                .iter()
+                .map(|block| {
+                    let width = block.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+                    format!("{:width$}", block.get(row).unwrap_or(&""), width = width)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            line.trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A single LED
+///
+/// Lights while its pin is High, or Low for `Led::new_inv`
+#[derive(Debug)]
+pub struct Led {
+    pin: Pin,
+    on: Signal,
+}
+
+impl Led {
+    /// The input pin
+    pub fn input(&self) -> &Pin {
+        &self.pin
+    }
+
+    /// How the LED looks
+    pub fn light(&self) -> Light {
+        Light::of(&self.pin, self.on)
+    }
+
+    /// The LED as one character
+    pub fn render(&self, style: RenderStyle) -> String {
+        let (lit, dark, error) = style.leds();
+        match self.light() {
+            Light::Dark => dark,
+            Light::Lit => lit,
+            Light::Error => error,
+        }
+        .to_string()
+    }
+
+    /// Creates an LED that lights on High
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        Self {
+            pin: graph.new_input(name),
+            on: Signal::High,
+        }
+    }
+
+    /// Creates an LED that lights on Low, like one wired from Vcc
+    pub fn new_inv(graph: &mut Graph, name: &str) -> Self {
+        Self {
+            pin: graph.new_input(name),
+            on: Signal::Low,
+        }
+    }
+}
+
+/// A row of LEDs, e.g. to show a bus
+///
+/// Pins are least-significant first, but render most-significant first so the bar reads like a
+/// binary number
+#[derive(Debug)]
+pub struct LedBar {
+    pins: Vec<Pin>,
+    on: Signal,
+}
+
+impl LedBar {
+    /// Input pins, least-significant first
+    pub fn input(&self) -> &[Pin] {
+        &self.pins
+    }
+
+    /// How each LED looks, least-significant first
+    pub fn lights(&self) -> Vec<Light> {
+        self.pins
+            .iter()
+            .map(|pin| Light::of(pin, self.on))
+            .collect()
+    }
+
+    /// The bar as one line, most-significant first
+    pub fn render(&self, style: RenderStyle) -> String {
+        let (lit, dark, error) = style.leds();
+        self.lights()
+            .iter()
+            .rev()
+            .map(|light| match light {
+                Light::Dark => dark,
+                Light::Lit => lit,
+                Light::Error => error,
+            })
+            .collect()
+    }
+
+    /// Creates a bar of LEDs that light on High
+    pub fn new(graph: &mut Graph, name: &str, count: usize) -> Self {
+        Self {
+            pins: graph.new_pins(name, &vec![PinState::INPUT; count]),
+            on: Signal::High,
+        }
+    }
+
+    /// Creates a bar of LEDs that light on Low
+    pub fn new_inv(graph: &mut Graph, name: &str, count: usize) -> Self {
+        Self {
+            pins: graph.new_pins(name, &vec![PinState::INPUT; count]),
+            on: Signal::Low,
+        }
+    }
+}
+
+/// Segments a-g of each hex digit, with segment a in bit 0
+pub const HEX_SEGMENTS: [usize; 16] = [
+    0x3f, 0x06, 0x5b, 0x4f, 0x66, 0x6d, 0x7d, 0x07, 0x7f, 0x6f, 0x77, 0x7c, 0x39, 0x5e, 0x79, 0x71,
+];
+
+/// Renders segments a-g and an optional decimal point as three lines
+fn render_segments(segments: &[Light], style: RenderStyle) -> String {
+    let (horizontal, vertical, dp, error) = match style {
+        RenderStyle::Unicode => ('━', '┃', '•', '░'),
+        RenderStyle::Ascii => ('_', '|', '.', '?'),
+    };
+    let h = |i: usize| segments[i].glyph(horizontal, error);
+    let v = |i: usize| segments[i].glyph(vertical, error);
+    let dp = segments.get(7).map_or(' ', |light| light.glyph(dp, error));
+    format!(
+        " {}  \n{}{}{} \n{}{}{}{}",
+        h(0),
+        v(5),
+        h(6),
+        v(1),
+        v(4),
+        h(3),
+        v(2),
+        dp
+    )
+}
+
+/// Which side of a 7-segment display's LEDs is shared
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Common {
+    /// Segments light on Low
+    Anode,
+
+    /// Segments light on High
+    Cathode,
+}
+
+/// A 7-segment digit with a decimal point
+#[derive(Debug)]
+pub struct SevenSegment {
+    pins: Vec<Pin>,
+    common: Common,
+}
+
+impl SevenSegment {
+    /// Decimal point pin index
+    pub(crate) const DP: usize = 7;
+
+    /// Segment inputs a-g
+    pub fn segment(&self) -> &[Pin] {
+        &self.pins[..Self::DP]
+    }
+
+    /// Decimal point input
+    pub fn dp(&self) -> &Pin {
+        &self.pins[Self::DP]
+    }
+
+    /// Which side of the LEDs is shared
+    pub fn common(&self) -> Common {
+        self.common
+    }
+
+    /// How segments a-g and the decimal point look
+    pub fn segments(&self) -> [Light; 8] {
+        let on = match self.common {
+            Common::Anode => Signal::Low,
+            Common::Cathode => Signal::High,
+        };
+        std::array::from_fn(|i| Light::of(&self.pins[i], on))
+    }
+
+    /// The digit as three lines of four characters
+    pub fn render(&self, style: RenderStyle) -> String {
+        render_segments(&self.segments(), style)
+    }
+
+    /// Creates a 7-segment digit
+    pub fn new(graph: &mut Graph, name: &str, common: Common) -> Self {
+        Self {
+            pins: graph.new_pins(name, &[PinState::INPUT; 8]),
+            common,
+        }
+    }
+}
+
+/// A hex digit with its own decoder, like a TIL311
+///
+/// Off inputs count as Low. Segments that depend on Error inputs show as Error.
+#[derive(Debug)]
+pub struct HexDisplay {
+    pins: Vec<Pin>,
+}
+
+impl HexDisplay {
+    /// Data inputs, least-significant first
+    pub fn input(&self) -> &[Pin] {
+        &self.pins
+    }
+
+    /// The value on the inputs
+    pub fn value(&self) -> BusValue {
+        self.pins.iter().val()
+    }
+
+    /// How segments a-g look
+    pub fn segments(&self) -> [Light; 7] {
+        let value = self.value();
+        let pattern = HEX_SEGMENTS[value.val];
+        // Segments that differ for any combination of the Error bits
+        let mut differ = 0;
+        let mut sub = value.error;
+        while sub != 0 {
+            differ |= pattern ^ HEX_SEGMENTS[value.val | sub];
+            sub = (sub - 1) & value.error;
+        }
+        std::array::from_fn(|i| match (differ >> i & 1, pattern >> i & 1) {
+            (1, _) => Light::Error,
+            (_, 1) => Light::Lit,
+            _ => Light::Dark,
+        })
+    }
+
+    /// The digit as three lines of four characters
+    pub fn render(&self, style: RenderStyle) -> String {
+        render_segments(&self.segments(), style)
+    }
+
+    /// Creates a hex display
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        Self {
+            pins: graph.new_pins(name, &[PinState::INPUT; 4]),
+        }
+    }
+}
+
+/// Front panel for a `DataBlock`: the address as four hex digits, and the data bus as two hex
+/// digits over a bar of LEDs
+#[derive(Debug)]
+pub struct DataPanel {
+    addr: Vec<HexDisplay>,
+    data: Vec<HexDisplay>,
+    leds: LedBar,
+}
+
+impl DataPanel {
+    /// Address digits, least-significant first
+    pub fn addr(&self) -> &[HexDisplay] {
+        &self.addr
+    }
+
+    /// Data digits, least-significant first
+    pub fn data(&self) -> &[HexDisplay] {
+        &self.data
+    }
+
+    /// Data LEDs
+    pub fn leds(&self) -> &LedBar {
+        &self.leds
+    }
+
+    /// The panel as a few lines of text
+    pub fn render(&self, style: RenderStyle) -> String {
+        let digits = |displays: &[HexDisplay]| {
+            let renders: Vec<String> = displays.iter().rev().map(|d| d.render(style)).collect();
+            side_by_side(&renders)
+        };
+        side_by_side(&[
+            format!("addr\n{}", digits(&self.addr)),
+            format!("data\n{}\n{}", digits(&self.data), self.leds.render(style)),
+        ])
+    }
+
+    /// Creates the displays and connects them to the data block
+    pub fn new(graph: &mut Graph, name: &str, data_block: &DataBlock) -> Self {
+        let make_name = |n: String| format!("{}.{}", name, n);
+        let addr: Vec<HexDisplay> = (0..4)
+            .map(|i| HexDisplay::new(graph, &make_name(format!("addr{}", i))))
+            .collect();
+        let data: Vec<HexDisplay> = (0..2)
+            .map(|i| HexDisplay::new(graph, &make_name(format!("data{}", i))))
+            .collect();
+        let leds = LedBar::new(graph, &make_name("leds".to_string()), 8);
+
+        let addr_pins = addr.iter().flat_map(|d| d.input());
+        for (pin, display) in data_block.addr().into_iter().zip(addr_pins) {
+            graph.connect(pin, display);
+        }
+        let data_pins = data.iter().flat_map(|d| d.input());
+        for ((pin, display), led) in data_block.data().iter().zip(data_pins).zip(leds.input()) {
+            graph.connect_all(&[pin, display, led]);
+        }
+
+        Self { addr, data, leds }
+    }
+}
+
+#[cfg(test)]
+mod test_display {
+    use crate::test_util::*;
+    use crate::*;
+
+    #[test]
+    fn test_leds() {
+        let mut graph = Graph::new();
+        let led = Led::new(&mut graph, "led");
+        let led_inv = Led::new_inv(&mut graph, "led_inv");
+        let bar = LedBar::new(&mut graph, "bar", 4);
+        let mut driver = graph.new_output("driver", Signal::High);
+        let mut bits = drive(&mut graph, bar.input());
+        set_bus(&mut bits, 0b1001);
+        graph.connect_all(&[&driver, led.input(), led_inv.input()]);
+        graph.run();
+
+        assert_eq!(led.light(), Light::Lit);
+        assert_eq!(led_inv.light(), Light::Dark);
+        assert_eq!(led.render(RenderStyle::Ascii), "*");
+        assert_eq!(led_inv.render(RenderStyle::Unicode), "○");
+        assert_eq!(bar.render(RenderStyle::Ascii), "*..*");
+        assert_eq!(
+            bar.lights(),
+            vec![Light::Lit, Light::Dark, Light::Dark, Light::Lit]
+        );
+
+        driver.set_output(Signal::Error);
+        bits[1].set_output(Signal::High);
+        bits[2].set_output(Signal::Error);
+        graph.run();
+        assert_eq!(led.light(), Light::Error);
+        assert_eq!(led_inv.light(), Light::Error);
+        assert_eq!(bar.render(RenderStyle::Ascii), "*?**");
+        assert_eq!(bar.render(RenderStyle::Unicode), "●◌●●");
+    }
+
+    #[test]
+    fn test_seven_segment() {
+        let mut graph = Graph::new();
+        let cathode = SevenSegment::new(&mut graph, "cathode", Common::Cathode);
+        let anode = SevenSegment::new(&mut graph, "anode", Common::Anode);
+        let mut drivers = drive(&mut graph, cathode.segment().iter().chain([cathode.dp()]));
+        for (driver, pin) in drivers
+            .iter()
+            .zip(anode.segment().iter().chain([anode.dp()]))
+        {
+            graph.connect(driver, pin);
+        }
+        // 7 with a decimal point
+        set_bus(&mut drivers, 0b1000_0111);
+        graph.run();
+
+        assert_eq!(cathode.render(RenderStyle::Ascii), " _  \n  | \n  |.");
+        assert_eq!(anode.render(RenderStyle::Ascii), "    \n|_  \n|_  ");
+        assert_eq!(cathode.render(RenderStyle::Unicode), " ━  \n  ┃ \n  ┃•");
+        assert_eq!(anode.segments()[0], Light::Dark);
+
+        drivers[6].set_output(Signal::Error);
+        graph.run();
+        assert_eq!(cathode.render(RenderStyle::Ascii), " _  \n ?| \n  |.");
+    }
+
+    #[test]
+    fn test_hex_display() {
+        let mut graph = Graph::new();
+        let hex = HexDisplay::new(&mut graph, "hex");
+        let mut drivers = drive(&mut graph, hex.input());
+
+        let digits: Vec<String> = (0..16)
+            .map(|n| {
+                set_bus(&mut drivers, n);
+                graph.run();
+                assert_eq!(hex.value().unwrap(), n);
+                hex.render(RenderStyle::Ascii)
+            })
+            .collect();
+        assert_eq!(
+            side_by_side(&digits),
+            [
+                " _         _    _         _    _    _    _    _    _         _         _    _",
+                "| |    |   _|   _|  |_|  |_   |_     |  |_|  |_|  |_|  |_   |     _|  |_   |_",
+                "|_|    |  |_    _|    |   _|  |_|    |  |_|   _|  | |  |_|  |_   |_|  |_   |",
+            ]
+            .join("\n")
+        );
+
+        // 8 or 9 only differ in segment e
+        drivers[0].set_output(Signal::Error);
+        drivers[1].set_output(Signal::Low);
+        drivers[2].set_output(Signal::Low);
+        graph.run();
+        let segments = hex.segments();
+        assert_eq!(segments[4], Light::Error);
+        assert!(segments.iter().filter(|s| **s == Light::Lit).count() == 6);
+    }
+
+    #[test]
+    fn test_data_panel() {
+        let mut graph = Graph::new();
+        let data_block = DataBlock::new(&mut graph, "data");
+        let panel = DataPanel::new(&mut graph, "panel", &data_block);
+        assert_eq!(panel.addr().len(), 4);
+        assert_eq!(panel.data().len(), 2);
+        assert_eq!(panel.leds().input().len(), 8);
+
+        let mut d = drive(
+            &mut graph,
+            [
+                data_block.d_ce(),
+                data_block.p_ce(),
+                data_block.down(),
+                data_block.reset(),
+            ],
+        );
+        drive(&mut graph, [data_block.i_ce(), data_block.up()]);
+        drive(&mut graph, [data_block.store(), data_block.clear()]);
+        let mut count = drive_pin(&mut graph, data_block.count(), Signal::Low);
+        let [d_ce, p_ce, down, reset] = &mut d[..] else {
+            unreachable!()
+        };
+
+        // Reset clears the register, and the pointer too while its top bit is set
+        reset.set_output(Signal::High);
+        graph.run();
+        reset.set_output(Signal::Low);
+        graph.run();
+        assert_eq!(data_block.addr().iter().val().val, 0);
+
+        // Count the pointer down to 0xfffe and the register down to 0xfd
+        down.set_output(Signal::High);
+        p_ce.set_output(Signal::High);
+        for _ in 0..2 {
+            graph.pulse_output(&mut count);
+        }
+        p_ce.set_output(Signal::Low);
+        d_ce.set_output(Signal::High);
+        for _ in 0..3 {
+            graph.pulse_output(&mut count);
+        }
+
+        let values = |displays: &[HexDisplay]| -> Vec<usize> {
+            displays.iter().map(|d| d.value().unwrap()).collect()
+        };
+        assert_eq!(values(panel.addr()), [0xe, 0xf, 0xf, 0xf]);
+        assert_eq!(values(panel.data()), [0xd, 0xf]);
+        assert_eq!(
+            panel.render(RenderStyle::Ascii),
+            [
+                "addr              data",
+                " _    _    _    _  _",
+                "|_   |_   |_   |_ |_    _|",
+                "|    |    |    |_ |    |_|",
+                "                  ******.*",
+            ]
+            .join("\n")
+        );
+    }
+}
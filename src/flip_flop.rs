@@ -0,0 +1,625 @@
+use crate::*;
+
+// Shared rules for the storage primitives:
+//
+// * Off inputs count as Low, so an unconnected set or reset never fires
+// * An Error input that could change the state makes Q and Q̅ Error. If the state would end up the
+//   same either way, e.g. an Error clock when D already equals Q, nothing changes
+// * Once Q is Error, only a known set, reset or load gets it back
+
+/// The level of an input, with Off counting as Low
+pub(crate) fn level(pin: PinState) -> Signal {
+    match pin.sig() {
+        Signal::Off => Signal::Low,
+        sig => sig,
+    }
+}
+
+/// `then` if the condition is High, `other` if it's Low, and Error if it's Error and they differ
+pub(crate) fn choose(cond: Signal, then: Signal, other: Signal) -> Signal {
+    match cond {
+        Signal::High => then,
+        Signal::Error if then != other => Signal::Error,
+        Signal::Error => then,
+        _ => other,
+    }
+}
+
+/// High on a rising edge, Low otherwise, or Error if an Error clock leaves it unknown
+pub(crate) fn rising_edge(clock: PinState, prev: PinState) -> Signal {
+    match (level(prev), level(clock)) {
+        (_, Signal::Error) | (Signal::Error, Signal::High) => Signal::Error,
+        (Signal::Low, Signal::High) => Signal::High,
+        _ => Signal::Low,
+    }
+}
+
+/// Drives a Q/Q̅ pair
+fn set_outputs(pins: &mut [PinState], q: usize, q_inv: usize, sig: Signal) {
+    pins[q] = PinState::Output(sig);
+    pins[q_inv] = PinState::Output(!sig);
+}
+
+/// Puts a Q/Q̅ pair into the power-on state
+fn power_on_outputs(power_on: &mut PowerOn, pins: &mut [PinState], q: usize, q_inv: usize) {
+    assert!(q < q_inv);
+    let (q_pins, q_inv_pins) = pins.split_at_mut(q_inv);
+    power_on.outputs(&mut q_pins[q], &mut q_inv_pins[0]);
+}
+
+/// Edge-triggered D flip-flop with asynchronous set and reset, like half a 74HC74
+///
+/// Q takes the value of D on the rising edge of the clock. Set and reset are active High and act
+/// straight away. Reset wins when both are High, like `TFlipFlop`.
+#[derive(Debug)]
+pub struct DFlipFlop {
+    d: Pin,
+    clock: Pin,
+    set: Pin,
+    reset: Pin,
+    q: Pin,
+    q_inv: Pin,
+}
+
+impl DFlipFlop {
+    /// Data input
+    pub fn d(&self) -> &Pin {
+        &self.d
+    }
+
+    /// Clock input. D is loaded on the rising edge
+    pub fn clock(&self) -> &Pin {
+        &self.clock
+    }
+
+    /// Asynchronous set input
+    pub fn set(&self) -> &Pin {
+        &self.set
+    }
+
+    /// Asynchronous reset input
+    pub fn reset(&self) -> &Pin {
+        &self.reset
+    }
+
+    /// Q output
+    pub fn q(&self) -> &Pin {
+        &self.q
+    }
+
+    /// Inverted Q output
+    pub fn q_inv(&self) -> &Pin {
+        &self.q_inv
+    }
+
+    /// D pin index
+    const D: usize = 0;
+
+    /// Clock pin index
+    const CLOCK: usize = 1;
+
+    /// Set pin index
+    const SET: usize = 2;
+
+    /// Reset pin index
+    const RESET: usize = 3;
+
+    /// Q pin index
+    const Q: usize = 4;
+
+    /// Inverted Q pin index
+    const Q_INV: usize = 5;
+
+    // internal for edge detection
+    /// Previous clock state index
+    const CLOCK_PREV: usize = 6;
+
+    /// Creates a D flip-flop
+    ///
+    /// Q starts Low, or in the graph's power-on state after `Graph::power_on`
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let pins = graph.new_stateful_part(
+            name,
+            &[
+                PinState::INPUT,
+                PinState::INPUT,
+                PinState::INPUT,
+                PinState::INPUT,
+                PinState::Output(Signal::Low),
+                PinState::Output(Signal::High),
+                PinState::INPUT,
+            ],
+            |pins| {
+                let q = level(pins[Self::Q]);
+                let edge = rising_edge(pins[Self::CLOCK], pins[Self::CLOCK_PREV]);
+                let clocked = choose(edge, level(pins[Self::D]), q);
+                let set = choose(level(pins[Self::SET]), Signal::High, clocked);
+                let new_q = choose(level(pins[Self::RESET]), Signal::Low, set);
+                set_outputs(pins, Self::Q, Self::Q_INV, new_q);
+                pins[Self::CLOCK_PREV] = pins[Self::CLOCK];
+            },
+            |power_on, pins| power_on_outputs(power_on, pins, Self::Q, Self::Q_INV),
+        );
+        Self {
+            d: pins[Self::D].clone(),
+            clock: pins[Self::CLOCK].clone(),
+            set: pins[Self::SET].clone(),
+            reset: pins[Self::RESET].clone(),
+            q: pins[Self::Q].clone(),
+            q_inv: pins[Self::Q_INV].clone(),
+        }
+    }
+}
+
+/// Edge-triggered JK flip-flop with asynchronous set and reset
+///
+/// On the rising edge of the clock: J and K Low holds, J High sets, K High resets, and both High
+/// toggles. Set and reset are active High and act straight away, with reset winning. Setting or
+/// resetting with J and K also clears an Error Q.
+#[derive(Debug)]
+pub struct JkFlipFlop {
+    j: Pin,
+    k: Pin,
+    clock: Pin,
+    set: Pin,
+    reset: Pin,
+    q: Pin,
+    q_inv: Pin,
+}
+
+impl JkFlipFlop {
+    /// J input, sets on the clock edge
+    pub fn j(&self) -> &Pin {
+        &self.j
+    }
+
+    /// K input, resets on the clock edge
+    pub fn k(&self) -> &Pin {
+        &self.k
+    }
+
+    /// Clock input. J and K act on the rising edge
+    pub fn clock(&self) -> &Pin {
+        &self.clock
+    }
+
+    /// Asynchronous set input
+    pub fn set(&self) -> &Pin {
+        &self.set
+    }
+
+    /// Asynchronous reset input
+    pub fn reset(&self) -> &Pin {
+        &self.reset
+    }
+
+    /// Q output
+    pub fn q(&self) -> &Pin {
+        &self.q
+    }
+
+    /// Inverted Q output
+    pub fn q_inv(&self) -> &Pin {
+        &self.q_inv
+    }
+
+    /// J pin index
+    const J: usize = 0;
+
+    /// K pin index
+    const K: usize = 1;
+
+    /// Clock pin index
+    const CLOCK: usize = 2;
+
+    /// Set pin index
+    const SET: usize = 3;
+
+    /// Reset pin index
+    const RESET: usize = 4;
+
+    /// Q pin index
+    const Q: usize = 5;
+
+    /// Inverted Q pin index
+    const Q_INV: usize = 6;
+
+    // internal for edge detection
+    /// Previous clock state index
+    const CLOCK_PREV: usize = 7;
+
+    /// Creates a JK flip-flop
+    ///
+    /// Q starts Low, or in the graph's power-on state after `Graph::power_on`
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let pins = graph.new_stateful_part(
+            name,
+            &[
+                PinState::INPUT,
+                PinState::INPUT,
+                PinState::INPUT,
+                PinState::INPUT,
+                PinState::INPUT,
+                PinState::Output(Signal::Low),
+                PinState::Output(Signal::High),
+                PinState::INPUT,
+            ],
+            |pins| {
+                let q = level(pins[Self::Q]);
+                let (j, k) = (level(pins[Self::J]), level(pins[Self::K]));
+                // J and K that differ load Q whatever it was. Otherwise a High Q only cares about
+                // K, and a Low Q only about J
+                let next = match (j, k, q) {
+                    (Signal::High, Signal::Low, _) => Signal::High,
+                    (Signal::Low, Signal::High, _) => Signal::Low,
+                    (_, _, Signal::High) => choose(k, Signal::Low, Signal::High),
+                    (_, _, Signal::Low) => choose(j, Signal::High, Signal::Low),
+                    _ => Signal::Error,
+                };
+                let edge = rising_edge(pins[Self::CLOCK], pins[Self::CLOCK_PREV]);
+                let clocked = choose(edge, next, q);
+                let set = choose(level(pins[Self::SET]), Signal::High, clocked);
+                let new_q = choose(level(pins[Self::RESET]), Signal::Low, set);
+                set_outputs(pins, Self::Q, Self::Q_INV, new_q);
+                pins[Self::CLOCK_PREV] = pins[Self::CLOCK];
+            },
+            |power_on, pins| power_on_outputs(power_on, pins, Self::Q, Self::Q_INV),
+        );
+        Self {
+            j: pins[Self::J].clone(),
+            k: pins[Self::K].clone(),
+            clock: pins[Self::CLOCK].clone(),
+            set: pins[Self::SET].clone(),
+            reset: pins[Self::RESET].clone(),
+            q: pins[Self::Q].clone(),
+            q_inv: pins[Self::Q_INV].clone(),
+        }
+    }
+}
+
+/// Set-reset latch, like a pair of cross-coupled NOR gates
+///
+/// Set High makes Q High, reset High makes Q Low, and both Low holds. Both High is the forbidden
+/// state, so Q and Q̅ are Error until one of them is released.
+#[derive(Debug)]
+pub struct SrLatch {
+    set: Pin,
+    reset: Pin,
+    q: Pin,
+    q_inv: Pin,
+}
+
+impl SrLatch {
+    /// Set input
+    pub fn set(&self) -> &Pin {
+        &self.set
+    }
+
+    /// Reset input
+    pub fn reset(&self) -> &Pin {
+        &self.reset
+    }
+
+    /// Q output
+    pub fn q(&self) -> &Pin {
+        &self.q
+    }
+
+    /// Inverted Q output
+    pub fn q_inv(&self) -> &Pin {
+        &self.q_inv
+    }
+
+    /// Set pin index
+    const SET: usize = 0;
+
+    /// Reset pin index
+    const RESET: usize = 1;
+
+    /// Q pin index
+    const Q: usize = 2;
+
+    /// Inverted Q pin index
+    const Q_INV: usize = 3;
+
+    /// Creates an SR latch
+    ///
+    /// Q starts Low, or in the graph's power-on state after `Graph::power_on`
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let pins = graph.new_stateful_part(
+            name,
+            &[
+                PinState::INPUT,
+                PinState::INPUT,
+                PinState::Output(Signal::Low),
+                PinState::Output(Signal::High),
+            ],
+            |pins| {
+                let set = level(pins[Self::SET]);
+                let reset = level(pins[Self::RESET]);
+                let new_q = if set != Signal::Low && reset != Signal::Low {
+                    Signal::Error
+                } else {
+                    let q = level(pins[Self::Q]);
+                    choose(reset, Signal::Low, choose(set, Signal::High, q))
+                };
+                set_outputs(pins, Self::Q, Self::Q_INV, new_q);
+            },
+            |power_on, pins| power_on_outputs(power_on, pins, Self::Q, Self::Q_INV),
+        );
+        Self {
+            set: pins[Self::SET].clone(),
+            reset: pins[Self::RESET].clone(),
+            q: pins[Self::Q].clone(),
+            q_inv: pins[Self::Q_INV].clone(),
+        }
+    }
+}
+
+/// Transparent D latch, like one bit of a 74HC573
+///
+/// Q follows D while enable is High, and holds its last value while enable is Low.
+#[derive(Debug)]
+pub struct DLatch {
+    d: Pin,
+    enable: Pin,
+    q: Pin,
+    q_inv: Pin,
+}
+
+impl DLatch {
+    /// Data input
+    pub fn d(&self) -> &Pin {
+        &self.d
+    }
+
+    /// Latch enable input. Q follows D while High
+    pub fn enable(&self) -> &Pin {
+        &self.enable
+    }
+
+    /// Q output
+    pub fn q(&self) -> &Pin {
+        &self.q
+    }
+
+    /// Inverted Q output
+    pub fn q_inv(&self) -> &Pin {
+        &self.q_inv
+    }
+
+    /// D pin index
+    const D: usize = 0;
+
+    /// Enable pin index
+    const ENABLE: usize = 1;
+
+    /// Q pin index
+    const Q: usize = 2;
+
+    /// Inverted Q pin index
+    const Q_INV: usize = 3;
+
+    /// Creates a D latch
+    ///
+    /// Q starts Low, or in the graph's power-on state after `Graph::power_on`
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let pins = graph.new_stateful_part(
+            name,
+            &[
+                PinState::INPUT,
+                PinState::INPUT,
+                PinState::Output(Signal::Low),
+                PinState::Output(Signal::High),
+            ],
+            |pins| {
+                let q = level(pins[Self::Q]);
+                let new_q = choose(level(pins[Self::ENABLE]), level(pins[Self::D]), q);
+                set_outputs(pins, Self::Q, Self::Q_INV, new_q);
+            },
+            |power_on, pins| power_on_outputs(power_on, pins, Self::Q, Self::Q_INV),
+        );
+        Self {
+            d: pins[Self::D].clone(),
+            enable: pins[Self::ENABLE].clone(),
+            q: pins[Self::Q].clone(),
+            q_inv: pins[Self::Q_INV].clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_flip_flop {
+    use crate::test_util::*;
+    use crate::*;
+
+    /// Sets the driven signals and runs
+    fn set(graph: &mut Graph, pins: &mut [Pin], sigs: &[Signal]) {
+        set_signals(pins, sigs);
+        graph.run();
+    }
+
+    /// Q and Q̅
+    fn outputs(q: &Pin, q_inv: &Pin) -> (Signal, Signal) {
+        (q.sig(), q_inv.sig())
+    }
+
+    use Signal::{Error, High, Low, Off};
+
+    #[test]
+    fn test_d_flip_flop() {
+        let mut graph = Graph::new();
+        let ff = DFlipFlop::new(&mut graph, "ff");
+        let mut pins = drive(&mut graph, [ff.d(), ff.clock(), ff.set(), ff.reset()]);
+        graph.run();
+        let q = || outputs(ff.q(), ff.q_inv());
+        assert_eq!(q(), (Low, High));
+
+        // D only loads on the rising edge
+        set(&mut graph, &mut pins, &[High, Low, Low, Low]);
+        assert_eq!(q(), (Low, High));
+        set(&mut graph, &mut pins, &[High, High, Low, Low]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[Low, High, Low, Low]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[Low, Low, Low, Low]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[Low, High, Low, Low]);
+        assert_eq!(q(), (Low, High));
+
+        // Asynchronous set and reset, with reset winning
+        set(&mut graph, &mut pins, &[Low, High, High, Low]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[Low, High, High, High]);
+        assert_eq!(q(), (Low, High));
+        set(&mut graph, &mut pins, &[Low, High, Low, Low]);
+        assert_eq!(q(), (Low, High));
+
+        // An Error clock only matters if D would change Q
+        set(&mut graph, &mut pins, &[Low, Error, Low, Low]);
+        assert_eq!(q(), (Low, High));
+        set(&mut graph, &mut pins, &[High, Error, Low, Low]);
+        assert_eq!(q(), (Error, Error));
+
+        // A known load recovers, and Off counts as Low
+        set(&mut graph, &mut pins, &[High, Low, Low, Low]);
+        set(&mut graph, &mut pins, &[Off, High, Low, Low]);
+        assert_eq!(q(), (Low, High));
+    }
+
+    #[test]
+    fn test_jk_flip_flop() {
+        let mut graph = Graph::new();
+        let ff = JkFlipFlop::new(&mut graph, "ff");
+        let mut pins = drive(
+            &mut graph,
+            [ff.j(), ff.k(), ff.clock(), ff.set(), ff.reset()],
+        );
+        graph.run();
+
+        // J, K, Q before the edge, Q after the edge
+        let table = [
+            (Low, Low, Low, Low),
+            (Low, Low, High, High),
+            (High, Low, Low, High),
+            (High, Low, High, High),
+            (Low, High, Low, Low),
+            (Low, High, High, Low),
+            (High, High, Low, High),
+            (High, High, High, Low),
+            (Error, Low, High, High),
+            (Error, Low, Low, Error),
+        ];
+        for (j, k, before, after) in table {
+            // Get to the starting state with set or reset
+            set(&mut graph, &mut pins, &[Low, Low, Low, before, !before]);
+            set(&mut graph, &mut pins, &[j, k, Low, Low, Low]);
+            assert_eq!(ff.q().sig(), before);
+            set(&mut graph, &mut pins, &[j, k, High, Low, Low]);
+            assert_eq!(
+                outputs(ff.q(), ff.q_inv()),
+                (after, !after),
+                "J={:?} K={:?} Q={:?}",
+                j,
+                k,
+                before
+            );
+        }
+
+        // An Error Q holds or toggles to Error, until J and K that differ load it
+        for (j, k, after) in [
+            (Low, Low, Error),
+            (High, High, Error),
+            (High, Low, High),
+            (Low, High, Low),
+        ] {
+            set(&mut graph, &mut pins, &[Low, Low, Low, Low, High]);
+            set(&mut graph, &mut pins, &[Error, Low, Low, Low, Low]);
+            set(&mut graph, &mut pins, &[Error, Low, High, Low, Low]);
+            assert_eq!(ff.q().sig(), Error);
+            set(&mut graph, &mut pins, &[j, k, Low, Low, Low]);
+            set(&mut graph, &mut pins, &[j, k, High, Low, Low]);
+            assert_eq!(
+                outputs(ff.q(), ff.q_inv()),
+                (after, !after),
+                "J={:?} K={:?} Q=Error",
+                j,
+                k
+            );
+        }
+    }
+
+    #[test]
+    fn test_sr_latch() {
+        let mut graph = Graph::new();
+        let latch = SrLatch::new(&mut graph, "latch");
+        let mut pins = drive(&mut graph, [latch.set(), latch.reset()]);
+        graph.run();
+        let q = || outputs(latch.q(), latch.q_inv());
+        assert_eq!(q(), (Low, High));
+
+        set(&mut graph, &mut pins, &[High, Low]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[Low, Low]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[Low, High]);
+        assert_eq!(q(), (Low, High));
+        set(&mut graph, &mut pins, &[Off, Off]);
+        assert_eq!(q(), (Low, High));
+
+        // Forbidden and unknown inputs
+        set(&mut graph, &mut pins, &[High, High]);
+        assert_eq!(q(), (Error, Error));
+        set(&mut graph, &mut pins, &[Low, High]);
+        assert_eq!(q(), (Low, High));
+        set(&mut graph, &mut pins, &[Error, Low]);
+        assert_eq!(q(), (Error, Error));
+        set(&mut graph, &mut pins, &[High, Low]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[Error, Low]);
+        assert_eq!(q(), (High, Low));
+    }
+
+    #[test]
+    fn test_d_latch() {
+        let mut graph = Graph::new();
+        let latch = DLatch::new(&mut graph, "latch");
+        let mut pins = drive(&mut graph, [latch.d(), latch.enable()]);
+        graph.run();
+        let q = || outputs(latch.q(), latch.q_inv());
+
+        // Transparent while enabled
+        set(&mut graph, &mut pins, &[High, High]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[Low, High]);
+        assert_eq!(q(), (Low, High));
+        set(&mut graph, &mut pins, &[Error, High]);
+        assert_eq!(q(), (Error, Error));
+        set(&mut graph, &mut pins, &[High, High]);
+        assert_eq!(q(), (High, Low));
+
+        // Holds while disabled
+        set(&mut graph, &mut pins, &[High, Low]);
+        set(&mut graph, &mut pins, &[Low, Low]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[Low, Off]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[High, Error]);
+        assert_eq!(q(), (High, Low));
+        set(&mut graph, &mut pins, &[Low, Error]);
+        assert_eq!(q(), (Error, Error));
+    }
+
+    #[test]
+    fn test_power_on() {
+        let mut graph = Graph::new();
+        let ff = DFlipFlop::new(&mut graph, "ff");
+        let latch = SrLatch::new(&mut graph, "latch");
+        drive(&mut graph, [ff.d(), ff.clock(), ff.set(), ff.reset()]);
+        drive(&mut graph, [latch.set(), latch.reset()]);
+        graph.power_on(PowerOnState::Ones);
+        graph.run();
+        assert_eq!(outputs(ff.q(), ff.q_inv()), (High, Low));
+        assert_eq!(outputs(latch.q(), latch.q_inv()), (High, Low));
+    }
+}
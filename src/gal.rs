@@ -0,0 +1,983 @@
+use crate::*;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+/// A problem with a JEDEC file, or a fuse map that doesn't fit a device
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JedecError {
+    /// What went wrong
+    pub message: String,
+}
+
+impl Display for JedecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for JedecError {}
+
+/// Creates a `JedecError`
+fn jedec_error<T>(message: impl Into<String>) -> Result<T, JedecError> {
+    Err(JedecError {
+        message: message.into(),
+    })
+}
+
+/// A fuse map from a JEDEC (`.jed`) file, as written by GALasm, WinCUPL and friends
+///
+/// In the file and here, `false` is an intact fuse. An intact fuse connects its input to the
+/// product term.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Jedec {
+    /// Fuses by number
+    pub fuses: Vec<bool>,
+}
+
+impl Jedec {
+    /// Parses a JEDEC file
+    ///
+    /// Uses the fuse count (`QF`), default fuse state (`F`) and fuse lists (`L`), and checks the fuse
+    /// checksum (`C`) if there is one. Other fields and the transmission checksum are ignored.
+    pub fn parse(text: &str) -> Result<Self, JedecError> {
+        // Everything between STX and ETX, if they're there
+        let text = text.split_once('\x02').map_or(text, |(_, rest)| rest);
+        let text = text.split_once('\x03').map_or(text, |(body, _)| body);
+
+        let mut count = None;
+        let mut default = false;
+        let mut lists = Vec::new();
+        let mut checksum = None;
+
+        // The first field is the free-form design specification
+        for field in text.split('*').skip(1).map(str::trim_start) {
+            let mut chars = field.chars();
+            match chars.next() {
+                Some('Q') if field.starts_with("QF") => {
+                    let Ok(n) = field[2..].trim().parse() else {
+                        return jedec_error(format!("bad fuse count '{}'", field));
+                    };
+                    count = Some(n);
+                }
+                Some('F') => default = Self::parse_bits(&field[1..])? == [true],
+                Some('L') => {
+                    let digits = field[1..].trim_start();
+                    let end = digits
+                        .find(|c: char| !c.is_ascii_digit())
+                        .unwrap_or(digits.len());
+                    let Ok(address) = digits[..end].parse::<usize>() else {
+                        return jedec_error(format!("bad fuse address in '{}'", field));
+                    };
+                    lists.push((address, Self::parse_bits(&digits[end..])?));
+                }
+                Some('C') => {
+                    let Ok(sum) = u16::from_str_radix(field[1..].trim(), 16) else {
+                        return jedec_error(format!("bad checksum '{}'", field));
+                    };
+                    checksum = Some(sum);
+                }
+                _ => (),
+            }
+        }
+
+        let Some(count) = count else {
+            return jedec_error("missing fuse count (QF)");
+        };
+        let mut fuses = vec![default; count];
+        for (address, bits) in lists {
+            if address + bits.len() > count {
+                let message = format!("fuses {} to {} are past the end", address, count);
+                return jedec_error(message);
+            }
+            fuses[address..address + bits.len()].copy_from_slice(&bits);
+        }
+
+        let jedec = Self { fuses };
+        if let Some(expected) = checksum {
+            if jedec.checksum() != expected {
+                let message = format!(
+                    "fuse checksum is {:04X} but the file says {:04X}",
+                    jedec.checksum(),
+                    expected
+                );
+                return jedec_error(message);
+            }
+        }
+        Ok(jedec)
+    }
+
+    /// Parses 0s and 1s, skipping whitespace
+    fn parse_bits(text: &str) -> Result<Vec<bool>, JedecError> {
+        text.chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                _ => jedec_error(format!("unexpected '{}' in fuses", c)),
+            })
+            .collect()
+    }
+
+    /// Fuse checksum: the 16-bit sum of the fuses packed into bytes, least-significant first
+    pub fn checksum(&self) -> u16 {
+        self.fuses
+            .chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .enumerate()
+                    .map(|(i, fuse)| (*fuse as u16) << i)
+                    .sum::<u16>()
+            })
+            .fold(0, u16::wrapping_add)
+    }
+}
+
+impl Display for Jedec {
+    /// Writes a JEDEC file that `parse` and device programmers can read
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut body = format!("\x02\n*QF{}*F0*\n", self.fuses.len());
+        for (i, line) in self.fuses.chunks(32).enumerate() {
+            if line.iter().any(|fuse| *fuse) {
+                let bits: String = line
+                    .iter()
+                    .map(|fuse| if *fuse { '1' } else { '0' })
+                    .collect();
+                body += &format!("L{:05} {}*\n", i * 32, bits);
+            }
+        }
+        body += &format!("C{:04X}*\n\x03", self.checksum());
+        let sum = body.bytes().fold(0u16, |sum, b| sum.wrapping_add(b as u16));
+        writeln!(f, "{}{:04X}", body, sum)
+    }
+}
+
+// The two devices share one model. The AND array has a true and an inverse column for each signal,
+// and each row is a product term. Macrocells OR some of the rows into a register or straight to the
+// pin. Off inputs count as Low. A Low literal wins over an Error in a product term, and a High term
+// wins over an Error in a sum.
+
+/// Where a pair of array columns gets its signal
+#[derive(Clone, Copy, Debug)]
+enum Column {
+    /// A pin, by part pin index
+    Pin(usize),
+
+    /// Q̅ of a macrocell's register
+    Register(usize),
+}
+
+/// When a macrocell drives its pin
+#[derive(Clone, Copy, Debug)]
+enum OutputEnable {
+    Always,
+    Never,
+
+    /// While a row is High
+    Term(usize),
+
+    /// While a pin is Low, by part pin index
+    PinLow(usize),
+}
+
+/// An output logic macrocell (OLMC)
+#[derive(Clone, Debug)]
+struct Macrocell {
+    /// Part pin index
+    pin: usize,
+
+    /// Rows summed into the output
+    terms: Range<usize>,
+    oe: OutputEnable,
+    registered: bool,
+    active_high: bool,
+}
+
+/// A programmed device
+#[derive(Clone, Debug)]
+struct GalLogic {
+    columns: Vec<Column>,
+
+    /// The connected literals of each row, as (column pair, true if not inverted), or None for rows
+    /// that are always Low
+    rows: Vec<Option<Vec<(usize, bool)>>>,
+    macrocells: Vec<Macrocell>,
+
+    /// Clock pin for the registers, by part pin index
+    clock: Option<usize>,
+
+    /// Asynchronous reset row
+    reset: Option<usize>,
+
+    /// Synchronous preset row
+    preset: Option<usize>,
+}
+
+impl GalLogic {
+    /// Reads the array's rows out of the fuses
+    fn rows(
+        fuses: &[bool],
+        rows: usize,
+        columns: usize,
+        enabled: impl Fn(usize) -> bool,
+    ) -> Vec<Option<Vec<(usize, bool)>>> {
+        (0..rows)
+            .map(|row| {
+                let fuses = &fuses[row * columns * 2..(row + 1) * columns * 2];
+                // A row with both columns of a pair connected can never be High
+                let contradiction = fuses.chunks(2).any(|pair| pair == [false, false]);
+                if !enabled(row) || contradiction {
+                    return None;
+                }
+                let literals = fuses
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, fuse)| !**fuse)
+                    .map(|(col, _)| (col / 2, col % 2 == 0))
+                    .collect();
+                Some(literals)
+            })
+            .collect()
+    }
+
+    /// Part updater. `state` is where the hidden pins start: the previous clock, then each
+    /// macrocell's register
+    fn update(&self, pins: &mut [PinState], state: usize) {
+        let signals: Vec<Signal> = self
+            .columns
+            .iter()
+            .map(|column| match column {
+                Column::Pin(pin) => level(pins[*pin]),
+                Column::Register(m) => !level(pins[state + 1 + m]),
+            })
+            .collect();
+        let term = |row: usize| match &self.rows[row] {
+            None => Signal::Low,
+            Some(literals) => literals
+                .iter()
+                .map(|(col, positive)| {
+                    let sig = signals[*col];
+                    if *positive {
+                        sig
+                    } else {
+                        !sig
+                    }
+                })
+                .fold(Signal::High, |a, b| {
+                    if a == Signal::Low || b == Signal::Low {
+                        Signal::Low
+                    } else {
+                        a & b
+                    }
+                }),
+        };
+        let sum = |rows: Range<usize>| {
+            rows.map(term).fold(Signal::Low, |a, b| {
+                if a == Signal::High || b == Signal::High {
+                    Signal::High
+                } else {
+                    a | b
+                }
+            })
+        };
+
+        let edge = match self.clock {
+            Some(clock) => {
+                let edge = rising_edge(pins[clock], pins[state]);
+                pins[state] = pins[clock];
+                edge
+            }
+            None => Signal::Low,
+        };
+        let reset = self.reset.map_or(Signal::Low, term);
+        let preset = self.preset.map_or(Signal::Low, term);
+
+        for (m, cell) in self.macrocells.iter().enumerate() {
+            let value = sum(cell.terms.clone());
+            let value = if cell.registered {
+                let q = level(pins[state + 1 + m]);
+                let q = choose(edge, choose(preset, Signal::High, value), q);
+                let q = choose(reset, Signal::Low, q);
+                pins[state + 1 + m] = PinState::Input(q);
+                q
+            } else {
+                value
+            };
+            let value = if cell.active_high { value } else { !value };
+
+            let oe = match cell.oe {
+                OutputEnable::Always => Signal::High,
+                OutputEnable::Never => Signal::Low,
+                OutputEnable::Term(row) => term(row),
+                OutputEnable::PinLow(pin) => !level(pins[pin]),
+            };
+            match oe {
+                Signal::High => pins[cell.pin] = PinState::Output(value),
+                Signal::Error => pins[cell.pin] = PinState::Output(Signal::Error),
+                // Keep what the node wrote if it's already an input
+                _ if matches!(pins[cell.pin], PinState::Output(_)) => {
+                    pins[cell.pin] = PinState::INPUT
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Creates the part, with `num_pins` logic pins
+    ///
+    /// The registers start reset, like the real devices at power-up. `Graph::power_on` resets them
+    /// again whatever the power-on state, and a device with registers counts as sequential for
+    /// `Graph::timing`, its combinational outputs included
+    fn build(self, graph: &mut Graph, name: &str, num_pins: usize) -> Vec<Pin> {
+        let mut states = vec![PinState::INPUT; num_pins];
+        states.resize(
+            num_pins + 1 + self.macrocells.len(),
+            PinState::Input(Signal::Low),
+        );
+        if !self.macrocells.iter().any(|cell| cell.registered) {
+            return graph.new_part(name, &states, move |pins| self.update(pins, num_pins));
+        }
+        graph.new_stateful_part(
+            name,
+            &states,
+            move |pins| self.update(pins, num_pins),
+            move |_, pins| pins[num_pins + 1..].fill(PinState::Input(Signal::Low)),
+        )
+    }
+}
+
+/// Checks that a fuse map is the right size for a device
+fn check_fuses(jedec: &Jedec, count: usize, device: &str) -> Result<(), JedecError> {
+    if jedec.fuses.len() != count {
+        let message = format!("{} has {} fuses, not {}", device, count, jedec.fuses.len());
+        return jedec_error(message);
+    }
+    Ok(())
+}
+
+/// Generic array logic with 10 macrocells, GAL22V10
+///
+/// Pin 1 is the clock for every register and also an input to the array, along with pins 2-11 and
+/// 13. Pins 14-23 are the outputs, each with its own output enable term. A registered output drives
+/// its pin from Q, inverted if it's active Low, and feeds Q̅ back to the array. A combinational
+/// output feeds back its pin, so a disabled output is another input. The asynchronous reset term
+/// clears every register straight away. The synchronous preset term sets them on the clock edge.
+#[derive(Debug)]
+pub struct IcGAL22V10 {
+    pins: Vec<Pin>,
+}
+
+impl IcGAL22V10 {
+    /// Number of fuses
+    pub const FUSES: usize = 5892;
+
+    /// Product terms of each macrocell, from pin 23 down to 14
+    const TERMS: [usize; 10] = [8, 10, 12, 14, 16, 16, 14, 12, 10, 8];
+
+    /// Input pins in the order of their array columns
+    const INPUT_PINS: [usize; 12] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 13];
+
+    /// Pin indexes by DIP pin number, starting from pin 1
+    const DIP: [Option<usize>; 24] = [
+        Some(0),
+        Some(1),
+        Some(2),
+        Some(3),
+        Some(4),
+        Some(5),
+        Some(6),
+        Some(7),
+        Some(8),
+        Some(9),
+        Some(10),
+        None,
+        Some(11),
+        Some(12),
+        Some(13),
+        Some(14),
+        Some(15),
+        Some(16),
+        Some(17),
+        Some(18),
+        Some(19),
+        Some(20),
+        Some(21),
+        None,
+    ];
+
+    /// Part pin index of a DIP pin
+    fn index(number: usize) -> usize {
+        Self::DIP[number - 1].unwrap()
+    }
+
+    /// Creates a GAL22V10 programmed with a fuse map
+    pub fn new(graph: &mut Graph, name: &str, jedec: &Jedec) -> Result<Self, JedecError> {
+        check_fuses(jedec, Self::FUSES, "GAL22V10")?;
+        let fuses = &jedec.fuses;
+
+        let mut macrocells = Vec::new();
+        let mut row = 1;
+        for (m, terms) in Self::TERMS.iter().enumerate() {
+            macrocells.push(Macrocell {
+                pin: Self::index(23 - m),
+                terms: row + 1..row + 1 + terms,
+                oe: OutputEnable::Term(row),
+                registered: !fuses[5808 + 2 * m + 1],
+                active_high: fuses[5808 + 2 * m],
+            });
+            row += 1 + terms;
+        }
+
+        // Inputs and feedback alternate, then the last two inputs
+        let columns = (0..22)
+            .map(|i| match (i % 2, i / 2) {
+                _ if i >= 20 => Column::Pin(Self::index(Self::INPUT_PINS[i - 10])),
+                (0, n) => Column::Pin(Self::index(Self::INPUT_PINS[n])),
+                (_, m) if macrocells[m].registered => Column::Register(m),
+                (_, m) => Column::Pin(macrocells[m].pin),
+            })
+            .collect();
+
+        let logic = GalLogic {
+            columns,
+            rows: GalLogic::rows(fuses, 132, 22, |_| true),
+            macrocells,
+            clock: Some(Self::index(1)),
+            reset: Some(0),
+            preset: Some(131),
+        };
+        let pins = logic.build(graph, name, 22);
+        Ok(Self { pins })
+    }
+
+    /// Creates a GAL22V10 from the text of a JEDEC file
+    pub fn from_jed(graph: &mut Graph, name: &str, text: &str) -> Result<Self, JedecError> {
+        Self::new(graph, name, &Jedec::parse(text)?)
+    }
+
+    /// The pin with the given DIP pin number
+    ///
+    /// Panics for the power pins, 12 and 24
+    pub fn dip(&self, number: usize) -> &Pin {
+        dip_pin(&self.pins, &Self::DIP, number)
+    }
+}
+
+/// Generic array logic with 8 macrocells, GAL16V8
+///
+/// Pins 2-9 are inputs and pins 12-19 are outputs with 8 product terms each. The mode fuses pick one
+/// of three layouts:
+///
+/// * Registered: pin 1 is the clock and pin 11 enables the registered outputs while it's Low. Each
+///   output is registered, with Q̅ feeding back, or combinational with its first term as its output
+///   enable.
+/// * Complex: pins 1 and 11 are inputs. Every output is combinational with its first term as its
+///   output enable. Pins 12 and 19 don't feed back.
+/// * Simple: as complex, but outputs are always enabled and use all 8 terms, or are disabled to
+///   act as inputs. Pins 15 and 16 don't feed back, but 12, 13 and 19 do.
+///
+/// Outputs are inverted when they're active Low. Disabled product terms are always Low.
+#[derive(Debug)]
+pub struct IcGAL16V8 {
+    pins: Vec<Pin>,
+}
+
+impl IcGAL16V8 {
+    /// Number of fuses
+    pub const FUSES: usize = 2194;
+
+    /// Output polarity fuses, from pin 19 down to 12
+    const XOR: usize = 2048;
+
+    /// Macrocell configuration fuses, from pin 19 down to 12
+    const AC1: usize = 2120;
+
+    /// Product term disable fuses, one per row
+    const PTD: usize = 2128;
+
+    /// Mode fuse, Low for registered mode
+    const SYN: usize = 2192;
+
+    /// Mode fuse, Low for simple mode
+    const AC0: usize = 2193;
+
+    /// Pin indexes by DIP pin number, starting from pin 1
+    const DIP: [Option<usize>; 20] = [
+        Some(0),
+        Some(1),
+        Some(2),
+        Some(3),
+        Some(4),
+        Some(5),
+        Some(6),
+        Some(7),
+        Some(8),
+        None,
+        Some(9),
+        Some(10),
+        Some(11),
+        Some(12),
+        Some(13),
+        Some(14),
+        Some(15),
+        Some(16),
+        Some(17),
+        None,
+    ];
+
+    /// Part pin index of a DIP pin
+    fn index(number: usize) -> usize {
+        Self::DIP[number - 1].unwrap()
+    }
+
+    /// Creates a GAL16V8 programmed with a fuse map
+    pub fn new(graph: &mut Graph, name: &str, jedec: &Jedec) -> Result<Self, JedecError> {
+        check_fuses(jedec, Self::FUSES, "GAL16V8")?;
+        let fuses = &jedec.fuses;
+        let registered_mode = match (fuses[Self::SYN], fuses[Self::AC0]) {
+            (false, true) => true,
+            (true, _) => false,
+            (false, false) => return jedec_error("GAL16V8 mode fuses SYN and AC0 are both 0"),
+        };
+        let simple_mode = fuses[Self::SYN] && !fuses[Self::AC0];
+
+        let macrocells: Vec<Macrocell> = (0..8)
+            .map(|m| {
+                let pin = Self::index(19 - m);
+                let rows = 8 * m..8 * m + 8;
+                let ac1 = fuses[Self::AC1 + m];
+                let (terms, oe, registered) = match (simple_mode, registered_mode, ac1) {
+                    (true, _, false) => (rows, OutputEnable::Always, false),
+                    (true, _, true) => (rows, OutputEnable::Never, false),
+                    (_, true, false) => (rows, OutputEnable::PinLow(Self::index(11)), true),
+                    _ => (
+                        rows.start + 1..rows.end,
+                        OutputEnable::Term(rows.start),
+                        false,
+                    ),
+                };
+                Macrocell {
+                    pin,
+                    terms,
+                    oe,
+                    registered,
+                    active_high: fuses[Self::XOR + m],
+                }
+            })
+            .collect();
+
+        // Inputs and feedback alternate. In simple mode, pins 15 and 16 don't feed back and the
+        // feedback columns move over to the neighbouring pins
+        let columns = (0..16)
+            .map(|i| match (i % 2, i / 2) {
+                (0, n) => Column::Pin(Self::index(n + 2)),
+                (_, 0) if !registered_mode => Column::Pin(Self::index(1)),
+                (_, 7) if !registered_mode => Column::Pin(Self::index(11)),
+                (_, m @ 1..=3) if simple_mode => Column::Pin(Self::index(20 - m)),
+                (_, m) if simple_mode => Column::Pin(Self::index(18 - m)),
+                (_, m) if macrocells[m].registered => Column::Register(m),
+                (_, m) => Column::Pin(macrocells[m].pin),
+            })
+            .collect();
+
+        let logic = GalLogic {
+            columns,
+            rows: GalLogic::rows(fuses, 64, 16, |row| fuses[Self::PTD + row]),
+            macrocells,
+            clock: registered_mode.then(|| Self::index(1)),
+            reset: None,
+            preset: None,
+        };
+        let pins = logic.build(graph, name, 18);
+        Ok(Self { pins })
+    }
+
+    /// Creates a GAL16V8 from the text of a JEDEC file
+    pub fn from_jed(graph: &mut Graph, name: &str, text: &str) -> Result<Self, JedecError> {
+        Self::new(graph, name, &Jedec::parse(text)?)
+    }
+
+    /// The pin with the given DIP pin number
+    ///
+    /// Panics for the power pins, 10 and 20
+    pub fn dip(&self, number: usize) -> &Pin {
+        dip_pin(&self.pins, &Self::DIP, number)
+    }
+}
+
+#[cfg(test)]
+mod test_gal {
+    use crate::test_util::*;
+    use crate::*;
+
+    /// Programs a row as the product of the literals, as (column pair, true if not inverted)
+    fn term(fuses: &mut [bool], columns: usize, row: usize, literals: &[(usize, bool)]) {
+        let fuses = &mut fuses[row * columns * 2..(row + 1) * columns * 2];
+        fuses.fill(true);
+        for (col, positive) in literals {
+            fuses[col * 2 + !positive as usize] = false;
+        }
+    }
+
+    /// Checks that a file survives writing and parsing, and returns it
+    fn round_trip(fuses: Vec<bool>) -> String {
+        let jedec = Jedec { fuses };
+        let text = jedec.to_string();
+        assert_eq!(Jedec::parse(&text).unwrap(), jedec);
+        text
+    }
+
+    #[test]
+    fn test_parse() {
+        let text =
+            "\x02GAL16V8-ish test\nQP4*\n*QF20*  QP4*F1* N a note *\nL0004 0000\n 00*L0016 0*\
+                    C0119*\x030000";
+        let jedec = Jedec::parse(text).unwrap();
+        let mut fuses = vec![true; 20];
+        fuses[4..10].fill(false);
+        fuses[16] = false;
+        assert_eq!(jedec.fuses, fuses);
+
+        let bad_checksum = text.replace("C0119", "C0118");
+        assert_eq!(
+            Jedec::parse(&bad_checksum).unwrap_err().to_string(),
+            "fuse checksum is 0119 but the file says 0118"
+        );
+        assert!(Jedec::parse("*F0*L0 1*").is_err());
+        assert!(Jedec::parse("*QF4*L2 111*").is_err());
+        assert!(Jedec::parse("*QF4*L0 12*").is_err());
+        assert!(IcGAL16V8::new(&mut Graph::new(), "gal", &jedec).is_err());
+    }
+
+    #[test]
+    fn test_22v10() {
+        // Column pairs alternate inputs 1-10 with feedback from pin 23 down to 14, then pins 11 and 13
+        let input = |pin: usize| match pin {
+            11 => 20,
+            13 => 21,
+            _ => (pin - 1) * 2,
+        };
+        let feedback = |pin: usize| (23 - pin) * 2 + 1;
+        let mut fuses = vec![false; IcGAL22V10::FUSES];
+        let olmc = |fuses: &mut [bool], pin: usize, active_high: bool, combinational: bool| {
+            fuses[5808 + (23 - pin) * 2] = active_high;
+            fuses[5808 + (23 - pin) * 2 + 1] = combinational;
+        };
+
+        // Pin 23 = pin 2 & pin 3
+        term(&mut fuses, 22, 1, &[]);
+        term(&mut fuses, 22, 2, &[(input(2), true), (input(3), true)]);
+        olmc(&mut fuses, 23, true, true);
+        // Pin 22 toggles on each clock. Q̅ feeds back
+        term(&mut fuses, 22, 10, &[]);
+        term(&mut fuses, 22, 11, &[(feedback(22), true)]);
+        olmc(&mut fuses, 22, true, false);
+        // Pin 21 = !pin 5, while pin 4 is High
+        term(&mut fuses, 22, 21, &[(input(4), true)]);
+        term(&mut fuses, 22, 22, &[(input(5), true)]);
+        olmc(&mut fuses, 21, false, true);
+        // Pin 14 is an input, passed through to pin 20
+        olmc(&mut fuses, 14, true, true);
+        term(&mut fuses, 22, 34, &[]);
+        term(&mut fuses, 22, 35, &[(feedback(14), true)]);
+        olmc(&mut fuses, 20, true, true);
+        // Pin 6 resets, pin 7 presets
+        term(&mut fuses, 22, 0, &[(input(6), true)]);
+        term(&mut fuses, 22, 131, &[(input(7), true)]);
+
+        let text = round_trip(fuses);
+        let mut graph = Graph::new();
+        let gal = IcGAL22V10::from_jed(&mut graph, "gal", &text).unwrap();
+        let mut d = drive(&mut graph, [1, 2, 3, 4, 5, 6, 7, 14].map(|n| gal.dip(n)));
+        let [clock, a, b, oe, inv, reset, preset, pass] = &mut d[..] else {
+            unreachable!()
+        };
+        graph.run();
+
+        assert_eq!(gal.dip(23).sig(), Signal::Low);
+        a.set_output(Signal::High);
+        b.set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(23).sig(), Signal::High);
+
+        assert_eq!(gal.dip(22).sig(), Signal::Low);
+        for expected in [Signal::High, Signal::Low, Signal::High] {
+            graph.pulse_output(clock);
+            assert_eq!(gal.dip(22).sig(), expected);
+        }
+        reset.set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(22).sig(), Signal::Low);
+        reset.set_output(Signal::Low);
+        preset.set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(22).sig(), Signal::Low);
+        graph.pulse_output(clock);
+        assert_eq!(gal.dip(22).sig(), Signal::High);
+        graph.pulse_output(clock);
+        assert_eq!(gal.dip(22).sig(), Signal::High);
+
+        assert_eq!(gal.dip(21).state(), PinState::INPUT);
+        oe.set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(21).state(), PinState::Output(Signal::High));
+        inv.set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(21).sig(), Signal::Low);
+
+        assert_eq!(gal.dip(14).state(), PinState::Input(Signal::Low));
+        pass.set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(20).sig(), Signal::High);
+    }
+
+    /// A 16V8 in registered mode with a 2-bit counter on pins 19 and 18, and pin 17 = pin 2 ^ pin 3
+    /// enabled by pin 4
+    fn counter_16v8() -> Vec<bool> {
+        let input = |pin: usize| (pin - 2) * 2;
+        let feedback = |pin: usize| (19 - pin) * 2 + 1;
+        let mut fuses = vec![false; IcGAL16V8::FUSES];
+        fuses[2128..2192].fill(true);
+        fuses[2193] = true;
+
+        // Q0 toggles, Q1 toggles when Q0 is 1. Both active High
+        term(&mut fuses, 16, 0, &[(feedback(19), true)]);
+        term(
+            &mut fuses,
+            16,
+            8,
+            &[(feedback(18), true), (feedback(19), false)],
+        );
+        term(
+            &mut fuses,
+            16,
+            9,
+            &[(feedback(18), false), (feedback(19), true)],
+        );
+        fuses[2048] = true;
+        fuses[2049] = true;
+
+        // Combinational, active Low xnor
+        fuses[2120 + 2] = true;
+        term(&mut fuses, 16, 16, &[(input(4), true)]);
+        term(&mut fuses, 16, 17, &[(input(2), true), (input(3), true)]);
+        term(&mut fuses, 16, 18, &[(input(2), false), (input(3), false)]);
+        fuses[2128 + 19] = false;
+        term(&mut fuses, 16, 19, &[]);
+        fuses
+    }
+
+    #[test]
+    fn test_16v8_registered() {
+        let text = round_trip(counter_16v8());
+        let mut graph = Graph::new();
+        let gal = IcGAL16V8::from_jed(&mut graph, "gal", &text).unwrap();
+        let mut d = drive(&mut graph, [1, 11, 2, 3, 4].map(|n| gal.dip(n)));
+        let [clock, oe_inv, a, b, enable] = &mut d[..] else {
+            unreachable!()
+        };
+        graph.run();
+
+        let count = |gal: &IcGAL16V8| [gal.dip(19), gal.dip(18)].into_iter().val().unwrap();
+        assert_eq!(count(&gal), 0);
+        for expected in [1, 2, 3, 0, 1] {
+            graph.pulse_output(clock);
+            assert_eq!(count(&gal), expected);
+        }
+        oe_inv.set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(19).state(), PinState::INPUT);
+        // The registers still count with their outputs disabled
+        graph.pulse_output(clock);
+        oe_inv.set_output(Signal::Low);
+        graph.run();
+        assert_eq!(count(&gal), 2);
+
+        assert_eq!(gal.dip(17).state(), PinState::INPUT);
+        enable.set_output(Signal::High);
+        for (x, y, expected) in [(0, 0, 0), (0, 1, 1), (1, 0, 1), (1, 1, 0)] {
+            a.set_output(Signal::from(x == 1));
+            b.set_output(Signal::from(y == 1));
+            graph.run();
+            assert_eq!(gal.dip(17).sig(), Signal::from(expected == 1));
+        }
+    }
+
+    #[test]
+    fn test_16v8_combinational() {
+        // Complex mode: pin 19 = pin 1 & pin 11, enabled by pin 2
+        let mut fuses = vec![false; IcGAL16V8::FUSES];
+        fuses[2128..2192].fill(true);
+        fuses[2192] = true;
+        fuses[2193] = true;
+        fuses[2048..2056].fill(true);
+        fuses[2120..2128].fill(true);
+        term(&mut fuses, 16, 0, &[(0, true)]);
+        term(&mut fuses, 16, 1, &[(1, true), (15, true)]);
+
+        let mut graph = Graph::new();
+        let gal = IcGAL16V8::new(&mut graph, "gal", &Jedec { fuses }).unwrap();
+        let mut d = drive(&mut graph, [gal.dip(1), gal.dip(11), gal.dip(2)]);
+        graph.run();
+        assert_eq!(gal.dip(19).state(), PinState::INPUT);
+        d[2].set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(19).sig(), Signal::Low);
+        d[0].set_output(Signal::High);
+        d[1].set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(19).sig(), Signal::High);
+        d[2].set_output(Signal::Error);
+        graph.run();
+        assert_eq!(gal.dip(19).sig(), Signal::Error);
+
+        // Simple mode: pin 13 is an input and pin 14 is always !pin 13
+        let mut fuses = vec![false; IcGAL16V8::FUSES];
+        fuses[2128..2192].fill(true);
+        fuses[2192] = true;
+        fuses[2120 + 6] = true;
+        term(&mut fuses, 16, 40, &[(11, true)]);
+
+        let mut graph = Graph::new();
+        let gal = IcGAL16V8::new(&mut graph, "gal", &Jedec { fuses }).unwrap();
+        let mut d = drive(&mut graph, [gal.dip(13)]);
+        graph.run();
+        assert_eq!(gal.dip(14).sig(), Signal::High);
+        d[0].set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(14).sig(), Signal::Low);
+    }
+
+    /// An enabled output
+    fn out(high: bool) -> PinState {
+        PinState::Output(Signal::from(high))
+    }
+
+    /// Sets the inputs to every combination of bits, least significant first, and checks the
+    /// outputs against `expected`
+    fn truth_table<const N: usize>(
+        graph: &mut Graph,
+        inputs: &mut [Pin],
+        outputs: [&Pin; N],
+        expected: impl Fn(&dyn Fn(usize) -> bool) -> [PinState; N],
+    ) {
+        for bits in 0..1 << inputs.len() {
+            set_bus(inputs, bits);
+            graph.run();
+            let states = outputs.map(|pin| pin.state());
+            assert_eq!(
+                states,
+                expected(&|i| bits & (1 << i) != 0),
+                "inputs {:b}",
+                bits
+            );
+        }
+    }
+
+    // The fixtures are GALasm sources with the files assembled from them
+
+    #[test]
+    fn test_22v10_fixture() {
+        let text = include_str!("../fixtures/gal/mixed_22v10.jed");
+        let mut graph = Graph::new();
+        let gal = IcGAL22V10::from_jed(&mut graph, "gal", text).unwrap();
+        let mut d = drive(&mut graph, [2, 3, 4, 5, 11, 13, 14].map(|n| gal.dip(n)));
+        let outputs = [23, 21, 20, 19].map(|n| gal.dip(n));
+        truth_table(&mut graph, &mut d, outputs, |bit| {
+            let [a, b, c, e, i11, i13, p14] = [0, 1, 2, 3, 4, 5, 6].map(bit);
+            let o21 = if c { out(!e) } else { PinState::INPUT };
+            [out(a && b), o21, out(p14), out(i11 && i13)]
+        });
+
+        let mut d = drive(&mut graph, [1, 6, 7].map(|n| gal.dip(n)));
+        let [clock, reset, preset] = &mut d[..] else {
+            unreachable!()
+        };
+        graph.run();
+        assert_eq!(gal.dip(22).sig(), Signal::Low);
+        for expected in [Signal::High, Signal::Low, Signal::High] {
+            graph.pulse_output(clock);
+            assert_eq!(gal.dip(22).sig(), expected);
+        }
+        reset.set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(22).sig(), Signal::Low);
+        reset.set_output(Signal::Low);
+        preset.set_output(Signal::High);
+        for _ in 0..2 {
+            graph.pulse_output(clock);
+            assert_eq!(gal.dip(22).sig(), Signal::High);
+        }
+    }
+
+    #[test]
+    fn test_16v8_registered_fixture() {
+        let text = include_str!("../fixtures/gal/counter_16v8.jed");
+        let mut graph = Graph::new();
+        let gal = IcGAL16V8::from_jed(&mut graph, "gal", text).unwrap();
+        let mut d = drive(&mut graph, [2, 3, 4].map(|n| gal.dip(n)));
+        truth_table(&mut graph, &mut d, [gal.dip(17)], |bit| {
+            let [a, b, enable] = [0, 1, 2].map(bit);
+            [if enable { out(a != b) } else { PinState::INPUT }]
+        });
+
+        let mut d = drive(&mut graph, [1, 11].map(|n| gal.dip(n)));
+        graph.run();
+        let count = |gal: &IcGAL16V8| [gal.dip(19), gal.dip(18)].into_iter().val().unwrap();
+        for expected in [1, 2, 3, 0, 1, 2] {
+            graph.pulse_output(&mut d[0]);
+            assert_eq!(count(&gal), expected);
+        }
+        d[1].set_output(Signal::High);
+        graph.run();
+        assert_eq!(gal.dip(19).state(), PinState::INPUT);
+        assert_eq!(gal.dip(18).state(), PinState::INPUT);
+    }
+
+    #[test]
+    fn test_16v8_complex_fixture() {
+        let text = include_str!("../fixtures/gal/complex_16v8.jed");
+        let mut graph = Graph::new();
+        let gal = IcGAL16V8::from_jed(&mut graph, "gal", text).unwrap();
+        let mut d = drive(&mut graph, [1, 2, 3, 4, 5, 11, 16].map(|n| gal.dip(n)));
+        let outputs = [19, 18, 17, 12].map(|n| gal.dip(n));
+        truth_table(&mut graph, &mut d, outputs, |bit| {
+            let [i1, i2, i3, i4, i5, i11, p16] = [0, 1, 2, 3, 4, 5, 6].map(bit);
+            let o19 = if i2 { out(i1 && i11) } else { PinState::INPUT };
+            [o19, out(!(i3 && i4)), out(p16 || i5), PinState::INPUT]
+        });
+    }
+
+    #[test]
+    fn test_16v8_simple_fixture() {
+        let text = include_str!("../fixtures/gal/simple_16v8.jed");
+        let mut graph = Graph::new();
+        let gal = IcGAL16V8::from_jed(&mut graph, "gal", text).unwrap();
+        let mut d = drive(&mut graph, [1, 2, 11, 12, 13, 19].map(|n| gal.dip(n)));
+        let outputs = [14, 15, 16].map(|n| gal.dip(n));
+        truth_table(&mut graph, &mut d, outputs, |bit| {
+            let [_, i2, i11, p12, p13, p19] = [0, 1, 2, 3, 4, 5].map(bit);
+            [out(!p13), out(p12 && i11), out(!(i2 || p19))]
+        });
+    }
+
+    #[test]
+    fn test_power_on() {
+        let text = include_str!("../fixtures/gal/counter_16v8.jed");
+        let mut graph = Graph::new();
+        let gal = IcGAL16V8::from_jed(&mut graph, "gal", text).unwrap();
+        let mut d = drive(&mut graph, [gal.dip(1)]);
+        graph.run();
+        let count = |gal: &IcGAL16V8| [gal.dip(19), gal.dip(18)].into_iter().val().unwrap();
+        for _ in 0..2 {
+            graph.pulse_output(&mut d[0]);
+        }
+        assert_eq!(count(&gal), 2);
+
+        // The power-up reset wins over the power-on state
+        graph.power_on(PowerOnState::Ones);
+        graph.run();
+        assert_eq!(count(&gal), 0);
+        graph.pulse_output(&mut d[0]);
+        assert_eq!(count(&gal), 1);
+    }
+}
@@ -56,6 +56,7 @@ pub fn buffer(graph: &mut Graph, name: &str) -> UnaryGate {
 }
 
 /// Represents single-bit, two-input logic gate
+#[derive(Debug)]
 pub struct BinaryGate {
     input_a: Pin,
     input_b: Pin,
@@ -163,7 +164,7 @@ impl Not for &Pin {
         let mut graph = self.graph();
         let name = format!("not({})", self.name());
         let gate = not_gate(&mut graph, &name);
-        graph.connect(self, &gate.input());
+        graph.connect(self, gate.input());
         gate.output().clone()
     }
 }
@@ -404,7 +405,7 @@ impl BusTristate {
 
     /// Get the enable pin
     pub fn en(&self) -> &Pin {
-        &self.0.last().unwrap()
+        self.0.last().unwrap()
     }
 
     /// Get the bit width of the buffer
@@ -460,6 +461,7 @@ macro_rules! assert_high {
 #[cfg(test)]
 mod test_gates {
     use crate::*;
+    use std::iter::zip;
 
     #[test]
     fn test_not_gate() {
@@ -571,8 +573,8 @@ mod test_gates {
         let buffer = BusBuffer::new(&mut graph, "buffer", 5);
 
         let mut inputs = graph.new_pins("inputs", &[PinState::Output(Signal::Low); 5]);
-        for i in 0..5 {
-            graph.connect(&inputs[i], &buffer.input()[i]);
+        for (input, buffer_in) in zip(&inputs, buffer.input()) {
+            graph.connect(input, buffer_in);
         }
 
         graph.run();
@@ -600,8 +602,8 @@ mod test_gates {
         let buffer = BusTristate::new(&mut graph, "buffer", 5);
 
         let mut inputs = graph.new_pins("inputs", &[PinState::Output(Signal::Low); 5]);
-        for i in 0..5 {
-            graph.connect(&inputs[i], &buffer.input()[i]);
+        for (input, buffer_in) in zip(&inputs, buffer.input()) {
+            graph.connect(input, buffer_in);
         }
 
         let mut enable = graph.new_output("enable", Signal::Low);
@@ -1,5 +1,5 @@
 use crate::*;
-use std::cell::{RefCell, RefMut};
+use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Debug, Formatter};
@@ -89,22 +89,22 @@ impl Debug for Pin {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let name = format!("[{}]:{}", self.id, self.name);
         f.debug_tuple(&name)
-            .field(&self.graph().get_state(&self))
+            .field(&self.graph().get_state(self))
             .finish()
     }
 }
 
 /// A set of mutually connected pins
 #[derive(Debug, Default, Hash, PartialEq, PartialOrd)]
-struct Node {
-    pin_ids: BTreeSet<PinId>,
+pub(crate) struct Node {
+    pin_ids: Vec<PinId>,
     signal: Signal,
 }
 
 impl Node {
     fn new(pin_id: PinId) -> Node {
         Node {
-            pin_ids: BTreeSet::from([pin_id]),
+            pin_ids: vec![pin_id],
             ..Default::default()
         }
     }
@@ -112,8 +112,56 @@ impl Node {
 
 /// A set of pins with an update function
 ///
-/// The update function is called each tick with the latest states of the pins
-type Part = Box<dyn FnMut(&mut [PinState])>;
+/// The update function is called with the latest states of the pins, but only on ticks where one
+/// of the part's input nodes changed, an alarm it set went off, or `all_parts` is set (the first
+/// tick of a run, `update_parts`, power-on)
+type Part = Box<dyn FnMut(&mut [PinState], &mut Timer)>;
+
+/// A part's view of time, given to the updaters of timed parts
+///
+/// Parts are only updated when their pins change, so parts that keep time themselves (clocks, write
+/// cycles, busy flags, etc.) use this to be updated again at a later tick
+pub struct Timer<'a> {
+    part_id: usize,
+    now: usize,
+    tick_ns: f64,
+    alarms: &'a mut BTreeSet<(usize, usize)>,
+}
+
+impl Timer<'_> {
+    /// The current tick, counting from the creation of the graph
+    pub fn now(&self) -> usize {
+        self.now
+    }
+
+    /// Length of a tick in nanoseconds
+    pub fn tick_ns(&self) -> f64 {
+        self.tick_ns
+    }
+
+    /// Number of whole ticks that last at least `ns` nanoseconds
+    pub fn ticks(&self, ns: f64) -> usize {
+        (ns / self.tick_ns).ceil() as usize
+    }
+
+    /// Updates the part again at the given tick. Ticks that have already started are ignored
+    pub fn wake_at(&mut self, tick: usize) {
+        if tick > self.now {
+            self.alarms.insert((tick, self.part_id));
+        }
+    }
+
+    /// Updates the part again after the given number of ticks
+    pub fn wake_in(&mut self, ticks: usize) {
+        self.wake_at(self.now + ticks);
+    }
+}
+
+/// Puts a part's state into its power-on condition
+///
+/// Called by `Graph::power_on` with the part's pin states. State kept outside the pins (e.g. RAM
+/// contents) is captured by the closure.
+type Initializer = Box<dyn FnMut(&mut PowerOn, &mut [PinState])>;
 
 /// The interface to the befrust compute graph
 ///
@@ -123,7 +171,7 @@ pub struct Graph(Rc<RefCell<GraphImpl>>);
 
 /// Internal data structures for the compute graph
 #[derive(Default)]
-struct GraphImpl {
+pub(crate) struct GraphImpl {
     /// Current state of all pins in the graph
     pub pin_states: Vec<PinState>,
     pub pin_names: Vec<String>,
@@ -142,10 +190,64 @@ struct GraphImpl {
 
     /// Parts for updating output pins
     pub parts: Vec<(Part, Range<usize>)>,
+
+    /// Power-on initializers for parts that hold state
+    pub initializers: Vec<(Initializer, Range<usize>)>,
+
+    /// Number of times the parts have been updated. Shared with parts that report when things happen
+    pub tick_count: Rc<Cell<usize>>,
+
+    /// Nodes with pins that changed since the last node update
+    pub dirty_nodes: Vec<usize>,
+
+    /// Whether each node is in `dirty_nodes`, indexed by node id
+    pub node_dirty: Vec<bool>,
+
+    /// Set when pins change outside of part updates (new pins, connections, etc.) so the next node
+    /// update checks every node
+    pub all_dirty: bool,
+
+    /// Pin states of a part from before its last update, for spotting changes
+    pub prev_states: Vec<PinState>,
+
+    /// The part each pin belongs to, if any
+    pub pin_parts: Vec<Option<usize>>,
+
+    /// Parts whose pins changed since their last update
+    pub scheduled_parts: Vec<usize>,
+
+    /// Whether each part is in `scheduled_parts`
+    pub part_scheduled: Vec<bool>,
+
+    /// Set when parts may need updating for reasons the graph can't see (new parts, the host
+    /// changing RAM contents, etc.) so the next part update calls every part
+    pub all_parts: bool,
+
+    /// Pin states as seen by their nodes. Differs from `pin_states` while a part's change is delayed
+    pub node_view: Vec<PinState>,
+
+    /// Propagation delays set for individual parts
+    pub part_delays: Vec<Option<Delay>>,
+
+    /// Whether each part is sequential, so timing paths start and end at it instead of passing
+    /// through
+    pub part_sequential: Vec<bool>,
+
+    /// Propagation delay for parts without their own
+    pub default_delay: Option<Delay>,
+
+    /// Length of a tick in nanoseconds, for `Delay::Ns`
+    pub tick_ns: f64,
+
+    /// Delayed pin changes on their way to the nodes
+    pub wheel: EventWheel,
+
+    /// Ticks at which timed parts asked to be updated, with their part ids
+    pub alarms: BTreeSet<(usize, usize)>,
 }
 
 /// Update and cycle information for a run of the graph
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default)]
 pub struct RunStats {
     /// Number of ticks to reach steady state
     pub ticks: usize,
@@ -173,14 +275,18 @@ impl GraphImpl {
     fn new_pin(&mut self, state: PinState, name: String) -> PinId {
         let id = self.pin_states.len();
         self.pin_states.push(state);
+        self.node_view.push(state);
         self.pin_names.push(name);
 
         let node_id = self.next_node;
         self.next_node += 1;
         let insertion = self.nodes.insert(node_id, Node::new(id));
-        assert!(matches!(insertion, None), "Node id collision");
+        assert!(insertion.is_none(), "Node id collision");
+        self.node_dirty.push(false);
+        self.all_dirty = true;
 
         self.pin_nodes.push(node_id);
+        self.pin_parts.push(None);
 
         assert_eq!(self.pin_states.len(), self.pin_names.len());
         assert_eq!(self.pin_states.len(), self.pin_nodes.len());
@@ -198,66 +304,230 @@ impl GraphImpl {
         let b_node = self.nodes.remove(&b_node_id).expect("Missing node");
         {
             let a_node = self.nodes.get_mut(&a_node_id).expect("Missing_node");
-            for b_pin in b_node.pin_ids.iter() {
-                a_node.pin_ids.insert(b_pin.clone());
-            }
+            a_node.pin_ids.extend(&b_node.pin_ids);
         }
         for b_pin in b_node.pin_ids.iter() {
-            self.pin_nodes[*b_pin] = a_node_id.clone();
+            self.pin_nodes[*b_pin] = a_node_id;
         }
+        self.all_dirty = true;
+    }
+
+    /// Sets a pin from outside of a part update, dropping any delayed changes it has
+    fn set_pin(&mut self, pin_id: PinId, state: PinState) {
+        self.pin_states[pin_id] = state;
+        self.node_view[pin_id] = state;
+        self.wheel.cancel(pin_id, 0);
+        self.mark_dirty(pin_id);
     }
 
+    /// Marks the node connected to a pin as needing an update
+    fn mark_dirty(&mut self, pin_id: PinId) {
+        let node_id = self.pin_nodes[pin_id];
+        if !self.node_dirty[node_id] {
+            self.node_dirty[node_id] = true;
+            self.dirty_nodes.push(node_id);
+        }
+    }
+
+    /// Updates the nodes whose pins changed since the last call, or every node if pins changed
+    /// outside of a part update
+    ///
+    /// Parts with inputs on updated nodes are scheduled for the next part update
     pub fn update_nodes(&mut self) -> usize {
-        //#![allow(unused_assignments, unused_variables)]
         let mut update_count = 0;
-        for (_node_id, node) in self.nodes.iter_mut() {
-            let mut new_signal = node.signal;
-            let mut out_count = 0;
-            //let mut output_pins = vec![];
-            //let mut out_id = usize::MAX;
-            for pin in node.pin_ids.iter() {
-                match self.pin_states[*pin] {
-                    PinState::HiZ | PinState::Input(_) => continue,
-                    PinState::Output(signal) => {
-                        //output_pins.push(&self.pin_names[*pin]);
-                        if out_count > 0 {
-                            new_signal = Signal::Error;
-                            out_count += 1;
-                        } else {
-                            out_count += 1;
-                            //out_id = *pin;
-                            new_signal = signal;
-                        }
+        let node_ids = if self.all_dirty {
+            self.all_dirty = false;
+            self.dirty_nodes.clear();
+            self.node_dirty.fill(false);
+            self.nodes.keys().copied().collect()
+        } else {
+            std::mem::take(&mut self.dirty_nodes)
+        };
+
+        for node_id in node_ids {
+            self.node_dirty[node_id] = false;
+            let node = self.nodes.get_mut(&node_id).expect("Missing node");
+            if !Self::update_node(node, &self.node_view, &mut self.pin_states) {
+                continue;
+            }
+            update_count += 1;
+            for pin_id in node.pin_ids.iter() {
+                if !matches!(self.pin_states[*pin_id], PinState::Input(_)) {
+                    continue;
+                }
+                if let Some(part_id) = self.pin_parts[*pin_id] {
+                    if !self.part_scheduled[part_id] {
+                        self.part_scheduled[part_id] = true;
+                        self.scheduled_parts.push(part_id);
                     }
                 }
             }
+        }
+
+        update_count
+    }
 
-            if new_signal != node.signal {
-                //println!("Update node {} from {:?} to {:?} from pin:", _node_id, node.signal, new_signal);
-                //println!("    [{}]:{}", out_id, self.pin_names[out_id]);
-                //if out_count > 1 {
-                //    println!("{:?}", output_names);
-                //}
-                update_count += 1;
-                node.signal = new_signal;
-                for pin_id in node.pin_ids.iter() {
-                    let state = &mut self.pin_states[*pin_id];
-                    if matches!(state, PinState::Input(_)) {
-                        *state = PinState::Input(new_signal);
+    /// Propagates signals from the node's output pins to its input pins
+    ///
+    /// Outputs are read from `node_view` so that delayed changes aren't seen early. Returns whether
+    /// the node was updated to a new signal
+    fn update_node(node: &mut Node, node_view: &[PinState], pin_states: &mut [PinState]) -> bool {
+        let mut new_signal = node.signal;
+        let mut out_count = 0;
+        for pin in node.pin_ids.iter() {
+            match node_view[*pin] {
+                PinState::HiZ | PinState::Input(_) => continue,
+                PinState::Output(signal) => {
+                    if out_count > 0 {
+                        new_signal = Signal::Error;
+                    } else {
+                        new_signal = signal;
                     }
+                    out_count += 1;
                 }
             }
         }
 
-        update_count
+        if new_signal == node.signal {
+            return false;
+        }
+
+        node.signal = new_signal;
+        for pin_id in node.pin_ids.iter() {
+            let state = &mut pin_states[*pin_id];
+            if matches!(state, PinState::Input(_)) {
+                *state = PinState::Input(new_signal);
+            }
+        }
+        true
     }
 
+    /// Updates the scheduled parts, or every part if `all_parts` is set
+    ///
+    /// Delayed changes that are due reach their nodes first
     pub fn update_parts(&mut self) {
-        for (part, pin_range) in self.parts.iter_mut() {
+        let now = self.tick_count.get() + 1;
+        self.tick_count.set(now);
+        for (pin_id, state) in self.wheel.take(now) {
+            self.node_view[pin_id] = state;
+            self.mark_dirty(pin_id);
+        }
+        while let Some(&(tick, part_id)) = self.alarms.first() {
+            if tick > now {
+                break;
+            }
+            self.alarms.pop_first();
+            if !self.part_scheduled[part_id] {
+                self.part_scheduled[part_id] = true;
+                self.scheduled_parts.push(part_id);
+            }
+        }
+
+        let part_ids = if self.all_parts {
+            self.all_parts = false;
+            self.scheduled_parts.clear();
+            self.part_scheduled.fill(false);
+            (0..self.parts.len()).collect()
+        } else {
+            std::mem::take(&mut self.scheduled_parts)
+        };
+
+        for part_id in part_ids {
+            self.part_scheduled[part_id] = false;
+            self.update_part(part_id);
+        }
+    }
+
+    /// Calls a part's updater, marking the nodes of any pins it changes as dirty
+    ///
+    /// A part that changed its own pins is scheduled again, since it may depend on them. The part
+    /// sees its changes straight away, but if it has a delay they only reach the nodes once the
+    /// delay has passed.
+    fn update_part(&mut self, part_id: usize) {
+        let (part, pin_range) = &mut self.parts[part_id];
+        let pin_range = pin_range.clone();
+        self.prev_states.clear();
+        self.prev_states
+            .extend_from_slice(&self.pin_states[pin_range.clone()]);
+        let now = self.tick_count.get();
+        let mut timer = Timer {
+            part_id,
+            now,
+            tick_ns: self.tick_ns,
+            alarms: &mut self.alarms,
+        };
+        part(&mut self.pin_states[pin_range.clone()], &mut timer);
+
+        let delay = self.part_delays[part_id].or(self.default_delay);
+        let mut changed = false;
+        for pin_id in pin_range.clone() {
+            let state = self.pin_states[pin_id];
+            if state == self.prev_states[pin_id - pin_range.start] {
+                continue;
+            }
+            changed = true;
+            match delay.map_or(1, |delay| delay.to_ticks(state, self.tick_ns)) {
+                1 => {
+                    self.wheel.cancel(pin_id, now);
+                    self.node_view[pin_id] = state;
+                    self.mark_dirty(pin_id);
+                }
+                ticks => self.wheel.schedule(now, pin_id, now + ticks - 1, state),
+            }
+        }
+        if changed && !self.part_scheduled[part_id] {
+            self.part_scheduled[part_id] = true;
+            self.scheduled_parts.push(part_id);
+        }
+    }
+
+    /// Updates the scheduled parts, then the nodes they changed
+    fn tick(&mut self) -> usize {
+        self.update_parts();
+        self.update_nodes()
+    }
+
+    /// Whether no delayed changes or alarms are waiting
+    fn is_quiet(&self) -> bool {
+        self.wheel.is_empty() && self.alarms.is_empty()
+    }
+
+    /// Skips the ticks before the next delayed change or alarm while nothing else needs updating,
+    /// up to `limit` ticks
+    ///
+    /// Returns the number of ticks skipped
+    fn skip_idle(&mut self, limit: usize) -> usize {
+        if self.all_parts || self.all_dirty {
+            return 0;
+        }
+        if !self.scheduled_parts.is_empty() || !self.dirty_nodes.is_empty() {
+            return 0;
+        }
+        let next = self.wheel.next_due().into_iter();
+        let Some(next) = next.chain(self.alarms.first().map(|(tick, _)| *tick)).min() else {
+            return 0;
+        };
+        let now = self.tick_count.get();
+        let skipped = next.saturating_sub(now + 1).min(limit);
+        self.tick_count.set(now + skipped);
+        skipped
+    }
+
+    pub fn power_on(&mut self, state: PowerOnState) {
+        // Bring the inputs up to date so initializers see what the parts are connected to
+        self.all_dirty = true;
+        self.update_nodes();
+        self.all_dirty = true;
+        self.all_parts = true;
+        let mut power_on = PowerOn::new(state);
+        for (init, pin_range) in self.initializers.iter_mut() {
             let start = pin_range.start;
             let end = pin_range.end;
-            part(&mut self.pin_states[start..end]);
+            init(&mut power_on, &mut self.pin_states[start..end]);
         }
+        self.node_view.clone_from(&self.pin_states);
+        self.wheel.clear();
+        self.alarms.clear();
     }
 
     pub fn print_nodes(&self) {
@@ -273,7 +543,7 @@ impl GraphImpl {
     pub fn print_orphans(&self) {
         for (node_id, node) in &self.nodes {
             if node.pin_ids.len() < 2 {
-                let pin_id = *node.pin_ids.iter().next().unwrap();
+                let pin_id = node.pin_ids[0];
                 let name = &self.pin_names[pin_id];
                 println!("Node {} has orphaned pin: {:?} {}", node_id, pin_id, name);
             }
@@ -281,17 +551,31 @@ impl GraphImpl {
     }
 }
 
+impl Default for Graph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Graph {
     /// Creates a new compute graph
     pub fn new() -> Self {
-        Self(Rc::new(RefCell::new(GraphImpl::default())))
+        Self(Rc::new(RefCell::new(GraphImpl {
+            tick_ns: 1.0,
+            ..Default::default()
+        })))
     }
 
     /// Retrieves the underlying implementation
-    fn g(&self) -> RefMut<GraphImpl> {
+    fn g(&self) -> RefMut<'_, GraphImpl> {
         (*self.0).borrow_mut()
     }
 
+    /// Read-only view of the underlying implementation, for analyses like `timing`
+    pub(crate) fn inner(&self) -> Ref<'_, GraphImpl> {
+        self.0.borrow()
+    }
+
     /// Creates a new pin with the given name and state
     pub fn new_pin(&mut self, name: String, state: PinState) -> Pin {
         Pin {
@@ -345,26 +629,129 @@ impl Graph {
 
     /// Creates a part
     ///
-    /// A "part" is a set of pins with an associated update function. Each tick, the associated
-    /// update function produces a new set of PinStates given the existing states.
-    pub fn new_part<F>(&mut self, name: &str, new_states: &[PinState], updater: F) -> Vec<Pin>
+    /// A "part" is a set of pins with an associated update function. The update function produces a
+    /// new set of PinStates given the existing states. It runs only when one of the part's input
+    /// nodes changes, when every part is updated (the first tick of a run, `update_parts`,
+    /// power-on), or, for timed parts, when an alarm goes off.
+    pub fn new_part<F>(&mut self, name: &str, new_states: &[PinState], mut updater: F) -> Vec<Pin>
+    where
+        F: 'static + FnMut(&mut [PinState]),
+    {
+        self.new_timed_part(name, new_states, move |pins, _| updater(pins))
+    }
+
+    /// Creates a part that keeps time
+    ///
+    /// Like `new_part`, but the updater also gets a `Timer` for reading the current tick and asking
+    /// to be updated again later
+    pub fn new_timed_part<F>(&mut self, name: &str, new_states: &[PinState], updater: F) -> Vec<Pin>
+    where
+        F: 'static + FnMut(&mut [PinState], &mut Timer),
+    {
+        let start = self.g().pin_states.len();
+        let end = start + new_states.len();
+        let part_id = {
+            let mut g = self.g();
+            g.parts.push((Box::new(updater), Range { start, end }));
+            g.part_scheduled.push(false);
+            g.part_delays.push(None);
+            g.part_sequential.push(false);
+            g.all_parts = true;
+            g.parts.len() - 1
+        };
+
+        let pins = self.new_pins(name, new_states);
+        self.g().pin_parts[start..end].fill(Some(part_id));
+        pins
+    }
+
+    /// Creates a part that holds state
+    ///
+    /// Like `new_part`, but `initializer` is also registered to put the part into its power-on
+    /// state whenever `power_on` is called
+    pub fn new_stateful_part<F, I>(
+        &mut self,
+        name: &str,
+        new_states: &[PinState],
+        updater: F,
+        initializer: I,
+    ) -> Vec<Pin>
     where
         F: 'static + FnMut(&mut [PinState]),
+        I: 'static + FnMut(&mut PowerOn, &mut [PinState]),
     {
         let start = self.g().pin_states.len();
         let end = start + new_states.len();
         self.g()
-            .parts
-            .push((Box::new(updater), Range { start, end }));
+            .initializers
+            .push((Box::new(initializer), Range { start, end }));
 
-        self.new_pins(name, new_states)
+        let pins = self.new_part(name, new_states, updater);
+        if let Some(pin) = pins.first() {
+            self.mark_sequential(pin);
+        }
+        pins
+    }
+
+    /// Puts every stateful part (RAM, flip-flops, etc.) into the given power-on state
+    ///
+    /// Can be called again at any time to re-initialize the graph without rebuilding it. Node
+    /// signals catch up on the next `tick` or `run`
+    pub fn power_on(&mut self, state: PowerOnState) {
+        self.g().power_on(state);
+    }
+
+    /// Sets the propagation delay of the part that owns the pin
+    ///
+    /// Without a delay, or the default delay, a part's changes reach its nodes in the same tick
+    pub fn set_delay(&mut self, pin: &Pin, delay: Delay) {
+        let mut g = self.g();
+        let part_id = g.pin_parts[pin.id].expect("Pin doesn't belong to a part");
+        g.part_delays[part_id] = Some(delay);
+    }
+
+    /// Marks the part that owns the pin as sequential for `timing`
+    ///
+    /// Sequential parts are clocked or hold state, so signals don't pass straight through them.
+    /// Parts made with `new_stateful_part` are already marked
+    pub fn mark_sequential(&mut self, pin: &Pin) {
+        let mut g = self.g();
+        let part_id = g.pin_parts[pin.id].expect("Pin doesn't belong to a part");
+        g.part_sequential[part_id] = true;
+    }
+
+    /// Sets the propagation delay for every part without its own, e.g. `Delay::LS_GATE`
+    pub fn set_default_delay(&mut self, delay: Option<Delay>) {
+        self.g().default_delay = delay;
+    }
+
+    /// Sets the length of a tick in nanoseconds, for delays given with `Delay::ns`. 1 by default
+    pub fn set_tick_ns(&mut self, tick_ns: f64) {
+        assert!(tick_ns > 0.0, "Ticks must take some time");
+        self.g().tick_ns = tick_ns;
+    }
+
+    /// Length of a tick in nanoseconds
+    pub fn tick_ns(&self) -> f64 {
+        self.0.borrow().tick_ns
     }
 
     /// Calls the updaters for all parts with their current pin states
     pub fn update_parts(&mut self) {
+        self.g().all_parts = true;
         self.g().update_parts();
     }
 
+    /// Total number of ticks (part updates) since the graph was created
+    pub fn tick_count(&self) -> usize {
+        self.0.borrow().tick_count.get()
+    }
+
+    /// A shared view of `tick_count` that parts can read from their updaters
+    pub(crate) fn tick_counter(&self) -> Rc<Cell<usize>> {
+        self.0.borrow().tick_count.clone()
+    }
+
     /// Propagates signals from output pins to Nodes, and from nodes to input pins
     ///
     /// Returns the number of nodes that were updated to a new signal
@@ -383,11 +770,16 @@ impl Graph {
     /// Ticks the compute graph until reaching steady state
     ///
     /// Steady state is either:
-    ///     0 nodes updated with a new signal, or
+    ///     0 nodes updated with a new signal and no delayed changes waiting, or
     ///     A cycle is detected
     ///
-    /// Note: this is pretty slow since it hashes all the pin states each tick and keeps a hashset
-    /// of the results for detecting cycles.
+    /// Every part is updated on the first tick, so changes made by the host outside the graph
+    /// (e.g. RAM contents) are picked up. After that only parts whose pins changed or whose timers
+    /// are due are updated. Ticks where nothing happens while waiting for a delayed change or timer
+    /// are skipped, but still count.
+    ///
+    /// Note: cycle detection hashes all the pin states each tick and keeps a set of the results, so
+    /// it only starts after `CYCLE_CHECK_TICKS` ticks. Most runs settle well before that.
     pub fn run(&mut self) -> RunStats {
         let mut stats = RunStats {
             ticks: 1,
@@ -398,18 +790,31 @@ impl Graph {
         // for cycle detection
         let mut state_hashes = BTreeMap::new();
 
+        self.g().all_parts = true;
         loop {
-            match self.tick() {
-                0 => break,
+            let skipped = self.g().skip_idle(usize::MAX);
+            let updates = self.g().tick();
+            match updates {
+                0 if self.g().is_quiet() => break,
                 n => {
-                    stats.ticks += 1;
+                    stats.ticks += 1 + skipped;
                     stats.updates += n;
                 }
             }
+            if stats.ticks < Self::CYCLE_CHECK_TICKS {
+                continue;
+            }
 
             let mut hash = DefaultHasher::new();
-            self.g().pin_states.hash(&mut hash);
-            self.g().nodes.hash(&mut hash);
+            let g = self.g();
+            g.pin_states.hash(&mut hash);
+            g.nodes.hash(&mut hash);
+            let now = g.tick_count.get();
+            g.wheel.hash_from(now, &mut hash);
+            for (tick, part_id) in g.alarms.iter() {
+                (tick - now, part_id).hash(&mut hash);
+            }
+            drop(g);
 
             if let Some(tick) = state_hashes.insert(hash.finish(), stats.ticks) {
                 stats.cycle = stats.ticks - tick - 1;
@@ -420,6 +825,33 @@ impl Graph {
         stats
     }
 
+    /// Number of ticks `run` waits before looking for cycles
+    const CYCLE_CHECK_TICKS: usize = 100;
+
+    /// Ticks the compute graph until 0 nodes are updated with no delayed changes or timers waiting,
+    /// or `max_ticks` have passed
+    ///
+    /// Much faster than `run` since there is no cycle detection. A graph that hasn't settled after
+    /// `max_ticks` is left as it is.
+    pub fn run_for(&mut self, max_ticks: usize) -> RunStats {
+        self.g().all_parts = true;
+        self.settle(max_ticks)
+    }
+
+    /// `run_for` without the initial update of every part
+    fn settle(&mut self, max_ticks: usize) -> RunStats {
+        let mut stats = RunStats::default();
+        while stats.ticks < max_ticks {
+            stats.ticks += 1 + self.g().skip_idle(max_ticks - stats.ticks - 1);
+            let updates = self.g().tick();
+            match updates {
+                0 if self.g().is_quiet() => break,
+                n => stats.updates += n,
+            }
+        }
+        stats
+    }
+
     /// Get the state of the pin
     pub fn get_state(&self, pin: &Pin) -> PinState {
         self.0.borrow().pin_states[pin.id]
@@ -435,14 +867,14 @@ impl Graph {
         let state = self.g().pin_states[pin.id];
         assert!(matches!(state, PinState::Output(_)));
 
-        self.g().pin_states[pin.id] = PinState::Output(signal);
+        self.g().set_pin(pin.id, PinState::Output(signal));
     }
 
     /// Change the output pin to its logical inverse
     pub fn flip_output(&mut self, pin: &mut Pin) {
         let state = self.g().pin_states[pin.id];
         assert!(matches!(state, PinState::Output(_)));
-        self.g().pin_states[pin.id] = PinState::Output(!state);
+        self.g().set_pin(pin.id, PinState::Output(!state));
     }
 
     /// Flips the state of the given output pin for one tick
@@ -464,6 +896,23 @@ impl Graph {
         stats + self.run()
     }
 
+    /// Pulses the output `count` times, settling with `run_for` after each edge
+    ///
+    /// A fast clock burst for long sequences such as clearing RAM, where the cycle detection in
+    /// `pulse_output` would take most of the time
+    pub fn burst_output(&mut self, pin: &mut Pin, count: usize, max_ticks: usize) -> RunStats {
+        // Only the pin changes between edges, so every part is updated just once
+        let mut stats = RunStats::default();
+        self.g().all_parts = true;
+        for _ in 0..count {
+            self.flip_output(pin);
+            stats = stats + self.settle(max_ticks);
+            self.flip_output(pin);
+            stats = stats + self.settle(max_ticks);
+        }
+        stats
+    }
+
     /// Prints all the pins that are not connected to any others
     pub fn print_orphans(&self) {
         self.g().print_orphans()
@@ -478,6 +927,8 @@ impl Graph {
 #[cfg(test)]
 mod test_graph {
     use crate::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
 
     #[test]
     fn create_graph() {
@@ -496,4 +947,95 @@ mod test_graph {
 
         assert_eq!(graph.get_signal(&pins[1]), Signal::Low);
     }
+
+    #[test]
+    fn test_burst() {
+        let mut graph = Graph::new();
+        let mut clock = graph.new_output("clock", Signal::Low);
+
+        // Counts rising edges, keeping the previous clock on a hidden pin
+        let edges = Rc::new(Cell::new(0));
+        let counter = edges.clone();
+        let pins = graph.new_part("edges", &[PinState::INPUT, PinState::OUTPUT], move |pins| {
+            if pins[0] == PinState::Input(Signal::High) && pins[1] == PinState::Output(Signal::Low)
+            {
+                counter.set(counter.get() + 1);
+            }
+            pins[1] = PinState::Output(pins[0].into());
+        });
+        graph.connect(&clock, &pins[0]);
+        graph.run();
+
+        let stats = graph.burst_output(&mut clock, 1000, 10);
+        assert_eq!(edges.get(), 1000);
+        assert_eq!(graph.get_signal(&clock), Signal::Low);
+        assert!(stats.ticks <= 2000 * 10);
+    }
+
+    #[test]
+    fn test_timer() {
+        let mut graph = Graph::new();
+        let mut start = graph.new_output("start", Signal::Low);
+
+        // Raises its output 1000 ticks after its input rises
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        let mut due = None;
+        let pins = graph.new_timed_part(
+            "timer",
+            &[PinState::INPUT, PinState::Output(Signal::Low)],
+            move |pins, timer| {
+                counter.set(counter.get() + 1);
+                if pins[0].sig() != Signal::High {
+                    return;
+                }
+                let due = *due.get_or_insert_with(|| {
+                    timer.wake_in(1000);
+                    timer.now() + 1000
+                });
+                if timer.now() >= due {
+                    pins[1] = PinState::Output(Signal::High);
+                }
+            },
+        );
+        graph.connect(&start, &pins[0]);
+        graph.run();
+
+        start.set_output(Signal::High);
+        let before = calls.get();
+        let stats = graph.run_for(500);
+        assert_eq!(stats.ticks, 500);
+        assert_eq!(pins[1].sig(), Signal::Low);
+
+        // The idle ticks are skipped, but the part still wakes up
+        let stats = graph.run();
+        assert!(stats.ticks > 500);
+        assert_eq!(pins[1].sig(), Signal::High);
+        assert!(calls.get() - before < 10);
+    }
+
+    #[test]
+    fn test_power_on_clears_alarms() {
+        let mut graph = Graph::new();
+        let mut start = graph.new_output("start", Signal::Low);
+
+        // Asks to be woken 1000 ticks after its input rises
+        let pins = graph.new_timed_part("timer", &[PinState::INPUT], |pins, timer| {
+            if pins[0].sig() == Signal::High {
+                timer.wake_in(1000);
+            }
+        });
+        graph.connect(&start, &pins[0]);
+        graph.run();
+
+        start.set_output(Signal::High);
+        graph.run_for(10);
+        start.set_output(Signal::Low);
+        graph.power_on(PowerOnState::Zeros);
+
+        // Nothing is left waiting for the alarm set before power-on
+        let before = graph.tick_count();
+        graph.run();
+        assert!(graph.tick_count() - before < 10);
+    }
 }
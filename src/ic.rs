@@ -64,8 +64,10 @@ impl TFlipFlop {
     const TOGGLE_PREV: usize = 5;
 
     /// Create a T-flip flop
+    ///
+    /// The output starts Low, or in the graph's power-on state after `Graph::power_on`
     pub fn new(graph: &mut Graph, name: &str) -> Self {
-        let pins = graph.new_part(
+        let pins = graph.new_stateful_part(
             name,
             &[
                 PinState::INPUT,
@@ -89,6 +91,12 @@ impl TFlipFlop {
                 pins[Self::OUT_INV] = PinState::Output(!new_q);
                 pins[Self::TOGGLE_PREV] = pins[Self::TOGGLE];
             },
+            |power_on, pins| {
+                let (q, q_inv) = pins.split_at_mut(Self::OUT_INV);
+                power_on.outputs(&mut q[Self::OUTPUT], &mut q_inv[0]);
+                // A toggle that is already High isn't an edge
+                pins[Self::TOGGLE_PREV] = pins[Self::TOGGLE];
+            },
         );
         Self {
             toggle: pins[Self::TOGGLE].clone(),
@@ -204,7 +212,7 @@ impl FullAdder {
 
     /// Input pin used for loading data
     pub fn input(&self) -> &Pin {
-        &self.half_adder.input()
+        self.half_adder.input()
     }
 
     /// Clear pin resets the bit to 0
@@ -329,6 +337,7 @@ impl Ic74193 {
     /// Borrow output
     ///
     /// Normally high. If counter is 0, transitions to low, then High, following the down signal
+    #[allow(clippy::should_implement_trait)]
     pub fn borrow(&self) -> &Pin {
         &self.borrow
     }
@@ -394,8 +403,8 @@ impl Ic74193 {
 
         let up_cond2 = adder1.output();
         let down_cond2 = adder1.out_inv();
-        graph.connect(&up_cond2, adder2.up_cond());
-        graph.connect(&down_cond2, adder2.down_cond());
+        graph.connect(up_cond2, adder2.up_cond());
+        graph.connect(down_cond2, adder2.down_cond());
 
         let up_cond3 = up_cond2 & adder2.output();
         let down_cond3 = down_cond2 & adder2.out_inv();
@@ -501,6 +510,7 @@ impl Counter8Bit {
     }
 
     /// Output borrow
+    #[allow(clippy::should_implement_trait)]
     pub fn borrow(&self) -> &Pin {
         self.counter2.borrow()
     }
@@ -534,22 +544,22 @@ impl Counter16Bit {
     /// Input bits for loading data
     pub fn input(&self) -> [&Pin; 16] {
         [
-            &self.counter1.input()[0],
-            &self.counter1.input()[1],
-            &self.counter1.input()[2],
-            &self.counter1.input()[3],
-            &self.counter1.input()[4],
-            &self.counter1.input()[5],
-            &self.counter1.input()[6],
-            &self.counter1.input()[7],
-            &self.counter2.input()[0],
-            &self.counter2.input()[1],
-            &self.counter2.input()[2],
-            &self.counter2.input()[3],
-            &self.counter2.input()[4],
-            &self.counter2.input()[5],
-            &self.counter2.input()[6],
-            &self.counter2.input()[7],
+            self.counter1.input()[0],
+            self.counter1.input()[1],
+            self.counter1.input()[2],
+            self.counter1.input()[3],
+            self.counter1.input()[4],
+            self.counter1.input()[5],
+            self.counter1.input()[6],
+            self.counter1.input()[7],
+            self.counter2.input()[0],
+            self.counter2.input()[1],
+            self.counter2.input()[2],
+            self.counter2.input()[3],
+            self.counter2.input()[4],
+            self.counter2.input()[5],
+            self.counter2.input()[6],
+            self.counter2.input()[7],
         ]
     }
 
@@ -576,22 +586,22 @@ impl Counter16Bit {
     /// Output pins
     pub fn output(&self) -> [&Pin; 16] {
         [
-            &self.counter1.output()[0],
-            &self.counter1.output()[1],
-            &self.counter1.output()[2],
-            &self.counter1.output()[3],
-            &self.counter1.output()[4],
-            &self.counter1.output()[5],
-            &self.counter1.output()[6],
-            &self.counter1.output()[7],
-            &self.counter2.output()[0],
-            &self.counter2.output()[1],
-            &self.counter2.output()[2],
-            &self.counter2.output()[3],
-            &self.counter2.output()[4],
-            &self.counter2.output()[5],
-            &self.counter2.output()[6],
-            &self.counter2.output()[7],
+            self.counter1.output()[0],
+            self.counter1.output()[1],
+            self.counter1.output()[2],
+            self.counter1.output()[3],
+            self.counter1.output()[4],
+            self.counter1.output()[5],
+            self.counter1.output()[6],
+            self.counter1.output()[7],
+            self.counter2.output()[0],
+            self.counter2.output()[1],
+            self.counter2.output()[2],
+            self.counter2.output()[3],
+            self.counter2.output()[4],
+            self.counter2.output()[5],
+            self.counter2.output()[6],
+            self.counter2.output()[7],
         ]
     }
 
@@ -601,6 +611,7 @@ impl Counter16Bit {
     }
 
     /// Output borrow
+    #[allow(clippy::should_implement_trait)]
     pub fn borrow(&self) -> &Pin {
         self.counter2.borrow()
     }
@@ -622,99 +633,87 @@ impl Counter16Bit {
 }
 
 /// 32k x 8bit RAM modeled after the CY7C199
-pub struct IcCY7C199(Vec<Pin>);
+pub struct IcCY7C199 {
+    pins: Vec<Pin>,
+    memory: Memory,
+    access_log: AccessLog,
+}
 
 impl IcCY7C199 {
     /// Inverse chip enable pin index
-    const CE_INV: usize = 0;
+    pub(crate) const CE_INV: usize = 0;
 
     /// Inverse output enable pin index
-    const OE_INV: usize = 1;
+    pub(crate) const OE_INV: usize = 1;
 
     /// Inverse write enable pin index
-    const WE_INV: usize = 2;
+    pub(crate) const WE_INV: usize = 2;
 
     /// IO pin starting index
-    const IO_START: usize = 3;
+    pub(crate) const IO_START: usize = 3;
 
     /// Size of word (number of IO pins)
-    const WORD_SIZE: usize = 8;
+    pub(crate) const WORD_SIZE: usize = 8;
 
     /// IO pin ending index
-    const IO_END: usize = Self::IO_START + Self::WORD_SIZE;
+    pub(crate) const IO_END: usize = Self::IO_START + Self::WORD_SIZE;
 
     /// Address pin starting index
-    const ADDR_START: usize = Self::IO_END;
+    pub(crate) const ADDR_START: usize = Self::IO_END;
 
     /// Address width
-    const ADDR_SIZE: usize = 15;
+    pub(crate) const ADDR_SIZE: usize = 15;
 
     /// Address pin ending index
-    const ADDR_END: usize = Self::ADDR_START + Self::ADDR_SIZE;
+    pub(crate) const ADDR_END: usize = Self::ADDR_START + Self::ADDR_SIZE;
 
     /// Total number of pins in part
-    const NUM_PINS: usize = Self::ADDR_END;
+    pub(crate) const NUM_PINS: usize = Self::ADDR_END;
 
     /// Total number of words in RAM
-    const NUM_WORDS: usize = 1 << Self::ADDR_SIZE;
-
-    /// Inverted chip enable
-    pub fn ce_inv(&self) -> &Pin {
-        &self.0[Self::CE_INV]
-    }
-
-    /// Inverted output enable
-    pub fn oe_inv(&self) -> &Pin {
-        &self.0[Self::OE_INV]
-    }
-
-    /// Inverted write enable
-    pub fn we_inv(&self) -> &Pin {
-        &self.0[Self::WE_INV]
-    }
-
-    /// I/O pins
-    ///
-    /// Pins are set to HiZ if:
-    ///     `ce_inv` is High, or
-    ///     `oe_inv` and `we_inv` are both Low
-    ///
-    /// Otherwise, pins are set to output the contents of ram at the current address if `oe_inv` is
-    ///     Low
-    ///
-    /// Otherwise, pins are set to input and their value is written into ram if `we_inv` is Low
-    pub fn io(&self) -> &[Pin] {
-        &self.0[Self::IO_START..Self::IO_END]
-    }
-
-    /// Address pins
-    pub fn addr(&self) -> &[Pin] {
-        &self.0[Self::ADDR_START..Self::ADDR_END]
-    }
+    pub const NUM_WORDS: usize = 1 << Self::ADDR_SIZE;
 
     /// Create a new RAM part
+    ///
+    /// The contents start as all ones, or in the graph's power-on state after `Graph::power_on`
     pub fn new(graph: &mut Graph, name: &str) -> Self {
         let mut states = [PinState::INPUT; Self::NUM_PINS];
         Self::set_io(&mut states, PinState::HiZ);
 
-        // TODO: Need a way to examine this vector
-        // TODO: Randomize the contents
-        let mut ram = vec![0xff; Self::NUM_WORDS];
+        let memory = Memory::new(Self::NUM_WORDS, Self::WORD_SIZE, 0xff);
+        let access_log = AccessLog::new();
+
+        let ram = memory.clone();
+        let init_ram = memory.clone();
+        let mut monitor = AccessMonitor::new(graph, &access_log, 0);
+        let pins = graph.new_stateful_part(
+            name,
+            &states,
+            move |pins| {
+                Self::update(&mut ram.words_mut(), &mut monitor, pins);
+            },
+            move |power_on, _| power_on.fill(&init_ram),
+        );
 
-        let pins = graph.new_part(name, &states, move |pins| {
-            Self::update(&mut ram, pins);
-        });
+        Self {
+            pins,
+            memory,
+            access_log,
+        }
+    }
 
-        Self(pins)
+    /// Reads, writes and write problems seen by the RAM
+    pub fn access_log(&self) -> &AccessLog {
+        &self.access_log
     }
 
     /// Sets the IO pins to a given state
-    fn set_io(states: &mut [PinState], val: PinState) {
+    pub(crate) fn set_io(states: &mut [PinState], val: PinState) {
         states[Self::IO_START..Self::IO_END].fill(val);
     }
 
     /// Part updater
-    fn update(ram: &mut Vec<u8>, pins: &mut [PinState]) {
+    fn update(ram: &mut [BusValue], monitor: &mut AccessMonitor, pins: &mut [PinState]) {
         let ce = !pins[Self::CE_INV];
         let oe = !pins[Self::OE_INV];
         let we = !pins[Self::WE_INV];
@@ -722,43 +721,61 @@ impl IcCY7C199 {
         let (_, output_pins) = pins.split_at_mut(Self::IO_START);
         let (io_pins, addr_pins) = output_pins.split_at_mut(Self::WORD_SIZE);
 
-        let data = io_pins.iter().val().unwrap();
-        let addr = addr_pins.iter().val().unwrap();
-
         if ce.is_lowish() {
+            monitor.idle();
             Self::set_io(pins, PinState::HiZ);
         } else if oe.is_high() {
-            let data = ram[addr];
-            Self::set_output(io_pins, data as usize);
+            let addr = addr_pins.iter().val();
+            let data = if addr.error == 0 {
+                ram[addr.val]
+            } else {
+                BusValue::new_error((1 << Self::WORD_SIZE) - 1)
+            };
+            monitor.read(driven_val(addr_pins), data);
+            drive_bus(io_pins, data);
         } else {
-            Self::set_input(io_pins, data);
+            release_bus(io_pins);
 
             if we.is_high() {
-                //println!("writing {} to {}", data, addr);
-                ram[addr] = data as u8;
+                if let Some((addr, data)) = monitor.write(addr_pins, io_pins) {
+                    ram[addr] = data;
+                }
+            } else {
+                monitor.idle();
             }
         }
     }
+}
 
-    /// Sets the pins to output the given value
-    fn set_output(pins: &mut [PinState], val: usize) {
-        let bus_val = BusValue::new_val(val);
-        for (i, state) in pins.iter_mut().enumerate() {
-            *state = PinState::Output(bus_val.sig(i));
-        }
+impl MemoryChip for IcCY7C199 {
+    fn ce_inv(&self) -> &Pin {
+        &self.pins[Self::CE_INV]
     }
 
-    /// Changes the pins to inputs and sets them to the given value
-    fn set_input(pins: &mut [PinState], val: usize) {
-        let bus_val = BusValue::new_val(val);
-        for (i, state) in pins.iter_mut().enumerate() {
-            *state = PinState::Input(bus_val.sig(i));
-        }
+    fn oe_inv(&self) -> &Pin {
+        &self.pins[Self::OE_INV]
+    }
+
+    fn we_inv(&self) -> &Pin {
+        &self.pins[Self::WE_INV]
+    }
+
+    fn io(&self) -> &[Pin] {
+        &self.pins[Self::IO_START..Self::IO_END]
+    }
+
+    fn addr(&self) -> &[Pin] {
+        &self.pins[Self::ADDR_START..Self::ADDR_END]
+    }
+
+    fn memory(&self) -> &Memory {
+        &self.memory
     }
 }
 
 #[cfg(test)]
 mod test_counter {
+    use crate::test_util::*;
     use crate::*;
     use std::iter::zip;
 
@@ -766,15 +783,6 @@ mod test_counter {
     pub fn test_load() {
         let mut graph = Graph::new();
 
-        let d = graph.new_pins(
-            "d",
-            &[
-                PinState::Output(Signal::High),
-                PinState::Output(Signal::Low),
-                PinState::Output(Signal::Low),
-                PinState::Output(Signal::High),
-            ],
-        );
         let mut load_inv = graph.new_output("load_inv", Signal::High);
         let mut clear = graph.new_output("clear", Signal::High);
         let up = graph.new_output("up", Signal::High);
@@ -787,18 +795,11 @@ mod test_counter {
         graph.connect(&load_inv, counter.load_inv());
         graph.connect(&clear, counter.clear());
 
-        let connect_many = |seq: &[(&Pin, &Pin)]| {
-            for (one, other) in seq {
-                one.connect(other);
-            }
-        };
-
-        connect_many(&[
-            (&d[0], counter.in1()),
-            (&d[1], counter.in2()),
-            (&d[2], counter.in3()),
-            (&d[3], counter.in4()),
-        ]);
+        let mut d = drive(
+            &mut graph,
+            [counter.in1(), counter.in2(), counter.in3(), counter.in4()],
+        );
+        set_bus(&mut d, 0b1001);
 
         graph.run();
         clear.set_output(Signal::Low);
@@ -1076,6 +1077,7 @@ mod test_counter {
 
 #[cfg(test)]
 mod test_ram {
+    use crate::test_util::*;
     use crate::*;
     use std::iter::zip;
 
@@ -1120,10 +1122,7 @@ mod test_ram {
 
         assert_outputs(ram.io());
 
-        let mut d = graph.new_pins("d", &[PinState::Output(Signal::Low); 8]);
-        for (one, other) in zip(&d, ram.io()) {
-            graph.connect(one, other);
-        }
+        let mut d = drive(&mut graph, ram.io());
 
         oe_inv.set_output(Signal::High);
         graph.run();
@@ -1162,4 +1161,40 @@ mod test_ram {
 
         assert_states(ram.io(), &expected_outputs);
     }
+
+    #[test]
+    pub fn test_memory_access() {
+        let mut graph = Graph::new();
+
+        let ram = IcCY7C199::new(&mut graph, "d_ram");
+        let memory = ram.memory().clone();
+        memory.poke(5, 0x5a);
+
+        let ce_inv = graph.new_output("ce_inv", Signal::Low);
+        let mut oe_inv = graph.new_output("oe_inv", Signal::Low);
+        let mut we_inv = graph.new_output("we_inv", Signal::High);
+        graph.connect_pairs(&[
+            (&ce_inv, ram.ce_inv()),
+            (&oe_inv, ram.oe_inv()),
+            (&we_inv, ram.we_inv()),
+        ]);
+
+        let mut addr = drive(&mut graph, ram.addr());
+        set_bus(&mut addr, 5);
+        graph.run();
+
+        assert_eq!(
+            ram.io().iter().map(|p| p.sig()).val(),
+            BusValue::new_val(0x5a)
+        );
+
+        let mut d = drive(&mut graph, ram.io());
+        set_bus(&mut d, 0xff);
+        oe_inv.set_output(Signal::High);
+        we_inv.set_output(Signal::Low);
+        graph.run();
+
+        assert_eq!(memory.peek(5), BusValue::new_val(0xff));
+        assert_eq!(memory.peek(4), BusValue::new_val(0xff));
+    }
 }
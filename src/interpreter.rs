@@ -0,0 +1,200 @@
+use crate::*;
+use std::fmt::{Display, Formatter};
+
+/// A `[` or `]` without a partner
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UnmatchedBracket {
+    /// Position of the bracket among the program's opcodes
+    pub index: usize,
+}
+
+impl Display for UnmatchedBracket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unmatched bracket at instruction {}", self.index)
+    }
+}
+
+impl std::error::Error for UnmatchedBracket {}
+
+/// A plain software brainfuck interpreter that behaves like the bfpu
+///
+/// The tape has one cell per word of the `IcCY7C199`, cells wrap at 8 bits like `Counter8Bit`, and
+/// the pointer wraps at 16 bits like `Counter16Bit`, so the RAM only sees its low bits. Input,
+/// output and the EOF convention come from an `IoHost`, just like an `IoBlock`.
+pub struct Interpreter {
+    program: Vec<Opcode>,
+    targets: Vec<usize>,
+    pc: usize,
+    ptr: u16,
+    tape: Vec<u8>,
+    host: IoHost,
+    instructions: usize,
+}
+
+impl Interpreter {
+    /// Creates an interpreter for the given source, with a clear tape
+    pub fn new(source: &str, host: IoHost) -> Result<Self, UnmatchedBracket> {
+        let program = Opcode::parse(source);
+
+        // Each bracket jumps to its partner; execution continues after it
+        let mut targets = vec![0; program.len()];
+        let mut stack = Vec::new();
+        for (index, op) in program.iter().enumerate() {
+            match op {
+                Opcode::LoopStart => stack.push(index),
+                Opcode::LoopEnd => {
+                    let start = stack.pop().ok_or(UnmatchedBracket { index })?;
+                    targets[start] = index;
+                    targets[index] = start;
+                }
+                _ => (),
+            }
+        }
+        if let Some(index) = stack.pop() {
+            return Err(UnmatchedBracket { index });
+        }
+
+        Ok(Self {
+            program,
+            targets,
+            pc: 0,
+            ptr: 0,
+            tape: vec![0; IcCY7C199::NUM_WORDS],
+            host,
+            instructions: 0,
+        })
+    }
+
+    /// The instruction about to be executed. `Halt` past the end of the program
+    pub fn opcode(&self) -> Opcode {
+        self.program.get(self.pc).copied().unwrap_or(Opcode::Halt)
+    }
+
+    /// Index of the instruction about to be executed
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The data pointer
+    pub fn ptr(&self) -> u16 {
+        self.ptr
+    }
+
+    /// The cell under the data pointer
+    pub fn cell(&self) -> u8 {
+        self.tape[self.index()]
+    }
+
+    /// The whole tape
+    pub fn tape(&self) -> &[u8] {
+        &self.tape
+    }
+
+    /// The input and output streams
+    pub fn host(&self) -> &IoHost {
+        &self.host
+    }
+
+    /// Instructions executed so far
+    pub fn instructions(&self) -> usize {
+        self.instructions
+    }
+
+    /// Executes one instruction
+    pub fn step(&mut self) -> Step {
+        let op = self.opcode();
+        let index = self.index();
+        match op {
+            Opcode::Right => self.ptr = self.ptr.wrapping_add(1),
+            Opcode::Left => self.ptr = self.ptr.wrapping_sub(1),
+            Opcode::Inc => self.tape[index] = self.tape[index].wrapping_add(1),
+            Opcode::Dec => self.tape[index] = self.tape[index].wrapping_sub(1),
+            Opcode::Output => self.host.write_byte(self.tape[index]),
+            Opcode::Input => match self.host.peek() {
+                Peek::Byte(byte) => {
+                    self.host.consume();
+                    self.tape[index] = byte;
+                }
+                Peek::Eof => {
+                    if let Some(byte) = self.host.eof().byte() {
+                        self.tape[index] = byte;
+                    }
+                }
+                Peek::Pending => return Step::Waiting,
+            },
+            Opcode::LoopStart if self.tape[index] == 0 => self.pc = self.targets[self.pc],
+            Opcode::LoopEnd if self.tape[index] != 0 => self.pc = self.targets[self.pc],
+            Opcode::LoopStart | Opcode::LoopEnd => (),
+            Opcode::Halt => return Step::Halted,
+        }
+        self.pc += 1;
+        self.instructions += 1;
+        Step::Executed
+    }
+
+    /// Steps until the program halts or waits for input
+    pub fn run(&mut self) -> Step {
+        loop {
+            match self.step() {
+                Step::Executed => continue,
+                step => return step,
+            }
+        }
+    }
+
+    /// Tape index of the current cell
+    fn index(&self) -> usize {
+        self.ptr as usize % self.tape.len()
+    }
+}
+
+#[cfg(test)]
+mod test_interpreter {
+    use crate::*;
+
+    #[test]
+    fn test_run() {
+        let mut interpreter = Interpreter::new("++[>+++<-]>.", IoHost::new([])).unwrap();
+        assert_eq!(interpreter.run(), Step::Halted);
+        assert_eq!(interpreter.host().output(), [6]);
+        assert_eq!(interpreter.ptr(), 1);
+        assert_eq!(&interpreter.tape()[..2], [0, 6]);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let mut interpreter = Interpreter::new("-<-<", IoHost::new([])).unwrap();
+        interpreter.run();
+        assert_eq!(interpreter.ptr(), 0xfffe);
+        let tape = interpreter.tape();
+        assert_eq!([tape[0], tape[IcCY7C199::NUM_WORDS - 1]], [0xff, 0xff]);
+    }
+
+    #[test]
+    fn test_input() {
+        let run = |eof| {
+            let host = IoHost::new(*b"a").with_eof(eof);
+            let mut interpreter = Interpreter::new("+,.,.", host).unwrap();
+            interpreter.run();
+            interpreter.host().output()
+        };
+        assert_eq!(run(EofBehavior::Zero), b"a\0");
+        assert_eq!(run(EofBehavior::MinusOne), b"a\xff");
+        assert_eq!(run(EofBehavior::Unchanged), b"aa");
+
+        let host = IoHost::open();
+        let mut interpreter = Interpreter::new(",.", host.clone()).unwrap();
+        assert_eq!(interpreter.run(), Step::Waiting);
+        host.push_input(b"z");
+        assert_eq!(interpreter.run(), Step::Halted);
+        assert_eq!(host.output(), b"z");
+    }
+
+    #[test]
+    fn test_unmatched() {
+        let error = |source| Interpreter::new(source, IoHost::new([])).err();
+        assert_eq!(error("+[[-]"), Some(UnmatchedBracket { index: 1 }));
+        assert_eq!(error("+]"), Some(UnmatchedBracket { index: 1 }));
+        assert_eq!(error("[-]"), None);
+    }
+}
@@ -0,0 +1,506 @@
+use crate::*;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+/// What `,` stores once the input has run out
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum EofBehavior {
+    /// Store 0
+    Zero,
+
+    /// Store -1, i.e. 255
+    MinusOne,
+
+    /// Leave the cell as it was
+    #[default]
+    Unchanged,
+}
+
+impl EofBehavior {
+    /// The byte stored at end of input, or None to leave the cell unchanged
+    pub fn byte(self) -> Option<u8> {
+        match self {
+            EofBehavior::Zero => Some(0),
+            EofBehavior::MinusOne => Some(0xff),
+            EofBehavior::Unchanged => None,
+        }
+    }
+}
+
+/// The next input byte, as seen by the part
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Peek {
+    Byte(u8),
+    Eof,
+    Pending,
+}
+
+/// Host side streams of an `IoBlock`
+struct IoHostImpl {
+    pending: VecDeque<u8>,
+    reader: Option<Box<dyn Read>>,
+    closed: bool,
+    output: Vec<u8>,
+    writer: Option<Box<dyn Write>>,
+    eof: EofBehavior,
+}
+
+/// A shared handle to the input and output streams of an `IoBlock`
+///
+/// Input comes from bytes pushed by the host and then, if there is one, from a reader such as
+/// stdin. Output is always captured, and also passed on to the writer if there is one.
+#[derive(Clone)]
+pub struct IoHost(Rc<RefCell<IoHostImpl>>);
+
+impl IoHost {
+    /// Creates a host whose input is the given bytes, followed by end of input
+    pub fn new(input: impl Into<Vec<u8>>) -> Self {
+        let host = Self::open();
+        host.push_input(&input.into());
+        host.close_input();
+        host
+    }
+
+    /// Creates a host with no input yet
+    ///
+    /// Input is fed with `push_input`, and `,` waits for it until `close_input` is called
+    pub fn open() -> Self {
+        Self(Rc::new(RefCell::new(IoHostImpl {
+            pending: VecDeque::new(),
+            reader: None,
+            closed: false,
+            output: Vec::new(),
+            writer: None,
+            eof: EofBehavior::default(),
+        })))
+    }
+
+    /// Creates a host that reads input from `reader` until it runs out
+    pub fn from_reader(reader: impl Read + 'static) -> Self {
+        let host = Self::open();
+        host.0.borrow_mut().reader = Some(Box::new(reader));
+        host
+    }
+
+    /// Creates a host connected to stdin and stdout
+    pub fn stdio() -> Self {
+        Self::from_reader(std::io::stdin()).with_writer(std::io::stdout())
+    }
+
+    /// Also passes output on to `writer`
+    pub fn with_writer(self, writer: impl Write + 'static) -> Self {
+        self.0.borrow_mut().writer = Some(Box::new(writer));
+        self
+    }
+
+    /// Sets what `,` stores once the input has run out
+    pub fn with_eof(self, eof: EofBehavior) -> Self {
+        self.0.borrow_mut().eof = eof;
+        self
+    }
+
+    /// What `,` stores once the input has run out
+    pub fn eof(&self) -> EofBehavior {
+        self.0.borrow().eof
+    }
+
+    /// Adds bytes to the input, ahead of anything left in the reader
+    pub fn push_input(&self, bytes: &[u8]) {
+        self.0.borrow_mut().pending.extend(bytes);
+    }
+
+    /// Ends the input once the bytes already pushed have been read
+    pub fn close_input(&self) {
+        let mut host = self.0.borrow_mut();
+        host.reader = None;
+        host.closed = true;
+    }
+
+    /// Everything written by `.` so far
+    pub fn output(&self) -> Vec<u8> {
+        self.0.borrow().output.clone()
+    }
+
+    /// Takes the next input byte, or None at end of input
+    ///
+    /// Returns None without waiting if no input has been pushed to an open host
+    pub fn read_byte(&self) -> Option<u8> {
+        match self.peek() {
+            Peek::Byte(byte) => {
+                self.consume();
+                Some(byte)
+            }
+            Peek::Eof | Peek::Pending => None,
+        }
+    }
+
+    /// Writes a byte to the output
+    pub fn write_byte(&self, byte: u8) {
+        let mut host = self.0.borrow_mut();
+        host.output.push(byte);
+        if let Some(writer) = host.writer.as_mut() {
+            // The output is captured anyway, so a broken writer isn't fatal
+            let _ = writer.write_all(&[byte]).and_then(|_| writer.flush());
+        }
+    }
+
+    /// Looks at the next input byte without taking it, reading from the reader if needed
+    pub(crate) fn peek(&self) -> Peek {
+        let mut host = self.0.borrow_mut();
+        if let Some(byte) = host.pending.front() {
+            return Peek::Byte(*byte);
+        }
+        if let Some(reader) = host.reader.as_mut() {
+            let mut byte = [0];
+            match reader.read(&mut byte) {
+                Ok(1) => {
+                    host.pending.push_back(byte[0]);
+                    return Peek::Byte(byte[0]);
+                }
+                _ => {
+                    host.reader = None;
+                    host.closed = true;
+                }
+            }
+        }
+        if host.closed {
+            Peek::Eof
+        } else {
+            Peek::Pending
+        }
+    }
+
+    /// Takes the byte returned by `peek`
+    pub(crate) fn consume(&self) {
+        self.0.borrow_mut().pending.pop_front();
+    }
+}
+
+/// Connects the data bus to the host for `.` and `,`
+///
+/// On `.`, the bus is written to the host output on the rising edge of `clock`. On `,`, the next
+/// input byte is driven onto the bus while `clock` is high and taken from the host when it falls.
+/// The bus keeps the byte after that, since nothing else drives it during input. Only one byte is
+/// taken until `read` goes low again, so the host isn't asked for input before it's needed.
+///
+/// `ready` is the handshake with the host: it goes low while `,` is waiting for input that hasn't
+/// arrived, and the transfer can't happen until it's high again. `strobe` is high while a byte is
+/// transferred.
+pub struct IoBlock {
+    read: Pin,
+    write: Pin,
+    clock: Pin,
+    ready: Pin,
+    strobe: Pin,
+    i_ce: Pin,
+    data: Vec<Pin>,
+    host: IoHost,
+}
+
+impl IoBlock {
+    /// Read enable input, for `,`
+    pub fn read(&self) -> &Pin {
+        &self.read
+    }
+
+    /// Write enable input, for `.`
+    pub fn write(&self) -> &Pin {
+        &self.write
+    }
+
+    /// Transfer clock input
+    pub fn clock(&self) -> &Pin {
+        &self.clock
+    }
+
+    /// Ready output
+    ///
+    /// Low while reading and the host has no input yet
+    pub fn ready(&self) -> &Pin {
+        &self.ready
+    }
+
+    /// Strobe output
+    ///
+    /// High while the clock is high and a byte is being transferred
+    pub fn strobe(&self) -> &Pin {
+        &self.strobe
+    }
+
+    /// Input enable output for the `DataBlock`
+    ///
+    /// High while reading a byte. Low at end of input when the cell should be left unchanged
+    pub fn i_ce(&self) -> &Pin {
+        &self.i_ce
+    }
+
+    /// Data bus pins
+    pub fn data(&self) -> &[Pin] {
+        &self.data
+    }
+
+    /// The host side streams
+    pub fn host(&self) -> &IoHost {
+        &self.host
+    }
+
+    /// Connects the control signals from a `ControlBlock` and the bus of a `DataBlock`
+    ///
+    /// `ready` is left for the caller, e.g. to hold the control block's clock
+    pub fn connect_blocks(&self, graph: &mut Graph, control: &ControlBlock, data: &DataBlock) {
+        graph.connect_pairs(&[
+            (control.i_ce(), &self.read),
+            (control.o_ce(), &self.write),
+            (control.store(), &self.clock),
+            (&self.i_ce, data.i_ce()),
+        ]);
+        for (pin, bus) in self.data.iter().zip(data.bus()) {
+            graph.connect(pin, bus);
+        }
+    }
+
+    /// Read pin index
+    const READ: usize = 0;
+
+    /// Write pin index
+    const WRITE: usize = 1;
+
+    /// Clock pin index
+    const CLOCK: usize = 2;
+
+    /// Ready pin index
+    const READY: usize = 3;
+
+    /// Strobe pin index
+    const STROBE: usize = 4;
+
+    /// Input enable pin index
+    const I_CE: usize = 5;
+
+    /// Index of the first data pin
+    const DATA_START: usize = 6;
+
+    /// Index after the last data pin
+    const DATA_END: usize = Self::DATA_START + 8;
+
+    // internal
+    /// Previous clock state index, for edge detection
+    const CLOCK_PREV: usize = Self::DATA_END;
+
+    /// Index of the flag set once a byte has been taken during the current read
+    const TAKEN: usize = Self::CLOCK_PREV + 1;
+
+    pub fn new(graph: &mut Graph, name: &str, host: IoHost) -> Self {
+        let mut states = vec![PinState::INPUT; Self::TAKEN + 1];
+        states[Self::READY] = PinState::Output(Signal::High);
+        states[Self::STROBE] = PinState::Output(Signal::Low);
+        states[Self::I_CE] = PinState::Output(Signal::Low);
+
+        let part_host = host.clone();
+        let pins = graph.new_part(name, &states, move |pins| Self::update(&part_host, pins));
+        graph.mark_sequential(&pins[0]);
+
+        Self {
+            read: pins[Self::READ].clone(),
+            write: pins[Self::WRITE].clone(),
+            clock: pins[Self::CLOCK].clone(),
+            ready: pins[Self::READY].clone(),
+            strobe: pins[Self::STROBE].clone(),
+            i_ce: pins[Self::I_CE].clone(),
+            data: pins[Self::DATA_START..Self::DATA_END].to_vec(),
+            host,
+        }
+    }
+
+    /// Part updater
+    fn update(host: &IoHost, pins: &mut [PinState]) {
+        let read = pins[Self::READ].is_high();
+        let write = pins[Self::WRITE].is_high();
+        let clock = pins[Self::CLOCK].is_high();
+        let rising = clock && pins[Self::CLOCK_PREV].is_lowish();
+        let falling = !clock && pins[Self::CLOCK_PREV].is_high();
+        pins[Self::CLOCK_PREV] = pins[Self::CLOCK];
+
+        let mut ready = true;
+        let mut input = None;
+        if !read {
+            pins[Self::TAKEN] = PinState::Input(Signal::Low);
+        }
+        if read && !pins[Self::TAKEN].is_high() {
+            match host.peek() {
+                Peek::Byte(byte) => {
+                    input = Some(byte);
+                    if falling {
+                        host.consume();
+                        pins[Self::TAKEN] = PinState::Input(Signal::High);
+                    }
+                }
+                Peek::Eof => input = host.eof().byte(),
+                Peek::Pending => ready = false,
+            }
+        } else if !read && write && rising {
+            let data = pins[Self::DATA_START..Self::DATA_END].iter().val();
+            host.write_byte(data.val as u8);
+        }
+
+        let data_pins = &mut pins[Self::DATA_START..Self::DATA_END];
+        match input {
+            Some(byte) if clock => drive_bus(data_pins, BusValue::new_val(byte as usize)),
+            _ => release_bus(data_pins),
+        }
+
+        pins[Self::READY] = PinState::Output(ready.into());
+        pins[Self::STROBE] = PinState::Output((clock && ready && (read || write)).into());
+        pins[Self::I_CE] = PinState::Output(input.is_some().into());
+    }
+}
+
+#[cfg(test)]
+mod test_io_block {
+    use crate::test_util::*;
+    use crate::*;
+
+    /// An io block with its inputs driven by outputs
+    struct Fixture {
+        graph: Graph,
+        io: IoBlock,
+        read: Pin,
+        write: Pin,
+        clock: Pin,
+    }
+
+    impl Fixture {
+        fn new(host: IoHost) -> Self {
+            let mut graph = Graph::new();
+            let io = IoBlock::new(&mut graph, "io", host);
+            let read = drive_pin(&mut graph, io.read(), Signal::Low);
+            let write = drive_pin(&mut graph, io.write(), Signal::Low);
+            let clock = drive_pin(&mut graph, io.clock(), Signal::Low);
+            Self {
+                graph,
+                io,
+                read,
+                write,
+                clock,
+            }
+        }
+
+        /// Reads one byte, returning the bus value while the clock is high and the input enable
+        fn read(&mut self) -> (BusValue, Signal) {
+            self.read.set_output(Signal::High);
+            self.graph.run();
+            self.clock.set_output(Signal::High);
+            self.graph.run();
+            let result = (self.io.data().iter().val(), self.io.i_ce().sig());
+            assert_eq!(self.io.strobe().sig(), Signal::High);
+            self.clock.set_output(Signal::Low);
+            self.graph.run();
+            self.read.set_output(Signal::Low);
+            self.graph.run();
+            result
+        }
+    }
+
+    #[test]
+    fn test_read() {
+        for eof in [
+            EofBehavior::Zero,
+            EofBehavior::MinusOne,
+            EofBehavior::Unchanged,
+        ] {
+            let mut fixture = Fixture::new(IoHost::new(*b"hi").with_eof(eof));
+            let byte = |b: u8| (BusValue::new_val(b as usize), Signal::High);
+            assert_eq!(fixture.read(), byte(b'h'));
+            assert_eq!(fixture.read(), byte(b'i'));
+            for _ in 0..2 {
+                let (data, i_ce) = fixture.read();
+                match eof.byte() {
+                    Some(b) => assert_eq!((data, i_ce), byte(b)),
+                    None => assert_eq!(i_ce, Signal::Low),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ready() {
+        let host = IoHost::open();
+        let mut fixture = Fixture::new(host.clone());
+        fixture.graph.run();
+        assert_eq!(fixture.io.ready().sig(), Signal::High);
+
+        fixture.read.set_output(Signal::High);
+        fixture.graph.run();
+        assert_eq!(fixture.io.ready().sig(), Signal::Low);
+        assert_eq!(fixture.io.i_ce().sig(), Signal::Low);
+
+        // Only the first byte is taken while read stays high
+        host.push_input(b"ab");
+        fixture.graph.run();
+        assert_eq!(fixture.io.ready().sig(), Signal::High);
+        fixture.graph.pulse_output(&mut fixture.clock);
+        fixture.graph.pulse_output(&mut fixture.clock);
+        assert_eq!(host.read_byte(), Some(b'b'));
+        assert_eq!(host.read_byte(), None);
+    }
+
+    #[test]
+    fn test_write() {
+        let host = IoHost::open();
+        let mut fixture = Fixture::new(host.clone());
+        let mut data = drive(&mut fixture.graph, fixture.io.data());
+
+        fixture.write.set_output(Signal::High);
+        for byte in *b"ok" {
+            set_bus(&mut data, byte as usize);
+            fixture.graph.run();
+            fixture.graph.pulse_output(&mut fixture.clock);
+        }
+
+        // Nothing is written without a clock edge or while write is low
+        fixture.graph.run();
+        fixture.write.set_output(Signal::Low);
+        fixture.graph.pulse_output(&mut fixture.clock);
+        assert_eq!(host.output(), b"ok");
+    }
+
+    /// Runs a program to completion, returning its output
+    fn run(source: &str, host: IoHost) -> Vec<u8> {
+        let mut graph = Graph::new();
+        let mut bfpu = Bfpu::new(&mut graph, "bfpu", LoopKind::Linear, host.clone());
+        let program = bfpu.program().program();
+        OpcodeEncoding::default().load(program, source).unwrap();
+        bfpu.data().tape().fill(0);
+        bfpu.quick_reset(&mut graph);
+        bfpu.run(&mut graph);
+        host.output()
+    }
+
+    #[test]
+    fn test_eof() {
+        let run_eof = |eof| run("+,.,.", IoHost::new(*b"a").with_eof(eof));
+        assert_eq!(run_eof(EofBehavior::Zero), b"a\0");
+        assert_eq!(run_eof(EofBehavior::MinusOne), b"a\xff");
+        assert_eq!(run("+,.", IoHost::new([])), b"\x01");
+    }
+
+    #[test]
+    fn test_cat() {
+        let mut graph = Graph::new();
+        let host = IoHost::open().with_eof(EofBehavior::Zero);
+        let mut bfpu = Bfpu::new(&mut graph, "bfpu", LoopKind::Linear, host.clone());
+        let program = bfpu.program().program();
+        OpcodeEncoding::default().load(program, ",[.,]").unwrap();
+        bfpu.quick_reset(&mut graph);
+
+        assert_eq!(bfpu.run(&mut graph), Step::Waiting);
+        host.push_input(b"hi");
+        assert_eq!(bfpu.run(&mut graph), Step::Waiting);
+        assert_eq!(host.output(), b"hi");
+        host.close_input();
+        assert_eq!(bfpu.run(&mut graph), Step::Halted);
+        assert_eq!(host.output(), b"hi");
+    }
+}
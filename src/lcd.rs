@@ -0,0 +1,844 @@
+use crate::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Busy time of clear display and return home, in nanoseconds
+const LONG_NS: f64 = 1_520_000.0;
+
+/// Busy time of other instructions, in nanoseconds
+const INSTRUCTION_NS: f64 = 37_000.0;
+
+/// Busy time of data reads and writes, in nanoseconds
+const DATA_NS: f64 = 41_000.0;
+
+/// Controller state, shared with the host
+#[derive(Debug)]
+struct LcdState {
+    /// Display data RAM, by position: line 1 then line 2 in two-line mode
+    ddram: [u8; 80],
+
+    /// Character generator RAM, 8 rows for each of 8 characters
+    cgram: [u8; 64],
+
+    /// Address counter
+    addr: usize,
+
+    /// Whether the address counter points into CGRAM
+    cgram_selected: bool,
+    increment: bool,
+    shift_on_write: bool,
+    display_on: bool,
+    cursor_on: bool,
+    blink_on: bool,
+    eight_bit: bool,
+    two_lines: bool,
+    large_font: bool,
+
+    /// How many positions the display has shifted left
+    shift: usize,
+
+    /// High nibble of a 4-bit write in progress
+    high_nibble: Option<u8>,
+
+    /// Whether the next 4-bit read gives the low nibble
+    read_low: bool,
+
+    /// Tick the current instruction finishes
+    busy_until: usize,
+
+    /// Writes that were ignored
+    dropped: usize,
+}
+
+impl LcdState {
+    /// The state after the internal reset at power-up
+    fn new() -> Self {
+        Self {
+            ddram: [b' '; 80],
+            cgram: [0; 64],
+            addr: 0,
+            cgram_selected: false,
+            increment: true,
+            shift_on_write: false,
+            display_on: false,
+            cursor_on: false,
+            blink_on: false,
+            eight_bit: true,
+            two_lines: false,
+            large_font: false,
+            shift: 0,
+            high_nibble: None,
+            read_low: false,
+            busy_until: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Number of DDRAM positions in each line
+    fn line_len(&self) -> usize {
+        if self.two_lines {
+            40
+        } else {
+            80
+        }
+    }
+
+    /// DDRAM position of a DDRAM address
+    fn position(&self, addr: usize) -> usize {
+        if self.two_lines {
+            (addr >> 6) * 40 + (addr & 0x3f)
+        } else {
+            addr
+        }
+    }
+
+    /// DDRAM address of a DDRAM position
+    fn address(&self, position: usize) -> usize {
+        if self.two_lines {
+            ((position / 40) << 6) | (position % 40)
+        } else {
+            position
+        }
+    }
+
+    /// Moves the address counter one place
+    fn step(&mut self, forward: bool) {
+        let (current, len) = if self.cgram_selected {
+            (self.addr, 64)
+        } else {
+            (self.position(self.addr), 80)
+        };
+        let next = if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        };
+        self.addr = if self.cgram_selected {
+            next
+        } else {
+            self.address(next)
+        };
+    }
+
+    /// Shifts the display one place
+    fn shift_display(&mut self, left: bool) {
+        let len = self.line_len();
+        self.shift = if left {
+            (self.shift + 1) % len
+        } else {
+            (self.shift + len - 1) % len
+        };
+    }
+
+    /// Memory byte at the address counter
+    fn data(&mut self) -> &mut u8 {
+        if self.cgram_selected {
+            &mut self.cgram[self.addr]
+        } else {
+            let position = self.position(self.addr);
+            &mut self.ddram[position]
+        }
+    }
+
+    /// Carries out an instruction or data write, returning how long it takes in nanoseconds
+    fn execute(&mut self, rs: bool, byte: u8) -> f64 {
+        let byte = byte as usize;
+        if rs {
+            *self.data() = byte as u8;
+            self.step(self.increment);
+            if self.shift_on_write && !self.cgram_selected {
+                self.shift_display(self.increment);
+            }
+            return DATA_NS;
+        }
+
+        match byte {
+            _ if byte & 0x80 != 0 => {
+                self.cgram_selected = false;
+                let addr = byte & 0x7f;
+                self.addr = if self.two_lines {
+                    addr & 0x40 | ((addr & 0x3f) % 40)
+                } else {
+                    addr % 80
+                };
+            }
+            _ if byte & 0x40 != 0 => {
+                self.cgram_selected = true;
+                self.addr = byte & 0x3f;
+            }
+            _ if byte & 0x20 != 0 => {
+                self.eight_bit = byte & 0x10 != 0;
+                self.two_lines = byte & 0x08 != 0;
+                self.large_font = byte & 0x04 != 0;
+                self.shift %= self.line_len();
+                self.high_nibble = None;
+                self.read_low = false;
+            }
+            _ if byte & 0x10 != 0 => {
+                let right = byte & 0x04 != 0;
+                if byte & 0x08 != 0 {
+                    self.shift_display(!right);
+                } else {
+                    self.step(right);
+                }
+            }
+            _ if byte & 0x08 != 0 => {
+                self.display_on = byte & 0x04 != 0;
+                self.cursor_on = byte & 0x02 != 0;
+                self.blink_on = byte & 0x01 != 0;
+            }
+            _ if byte & 0x04 != 0 => {
+                self.increment = byte & 0x02 != 0;
+                self.shift_on_write = byte & 0x01 != 0;
+            }
+            _ if byte & 0x02 != 0 => {
+                self.cgram_selected = false;
+                self.addr = 0;
+                self.shift = 0;
+                return LONG_NS;
+            }
+            0x01 => {
+                self.ddram.fill(b' ');
+                self.cgram_selected = false;
+                self.addr = 0;
+                self.shift = 0;
+                self.increment = true;
+                return LONG_NS;
+            }
+            _ => (),
+        }
+        INSTRUCTION_NS
+    }
+}
+
+/// Character for a code in the standard (A00) font
+///
+/// Custom characters are shown as `▒`, and codes with no close match as `?`
+fn glyph(code: u8) -> char {
+    match code {
+        0x00..=0x0f => '▒',
+        b'\\' => '¥',
+        0x20..=0x7d => code as char,
+        0x7e => '→',
+        0x7f => '←',
+        _ => '?',
+    }
+}
+
+/// Character LCD module with an HD44780 controller, 16x2 by default
+///
+/// The parallel interface works like the real one. With R/W Low, the data lines are taken on the
+/// falling edge of E, as an instruction while RS is Low or as data for the display (DDRAM) or
+/// custom characters (CGRAM) while RS is High. With R/W High, the controller drives the data lines
+/// while E is High, with the busy flag and address counter while RS is Low, or with data while RS
+/// is High. In 4-bit mode, only D4-D7 are used and each byte takes two transfers, high nibble
+/// first.
+///
+/// Instructions keep the controller busy for their real duration, counted in the graph's ticks when
+/// they start. At the default 1 ns tick, clearing the display takes 1,520,000 ticks, so
+/// `Graph::set_tick_ns` is worth raising for circuits that poll the busy flag tick by tick. The
+/// controller wakes up when an instruction finishes, so a status read in progress sees the busy
+/// flag drop, and `Graph::run` waits out the instruction. Writes while busy are ignored, as are
+/// writes with Error on RS or the data lines, and `dropped_writes` counts them. Off data lines
+/// count as Low, so D0-D3 can be left unconnected in 4-bit mode.
+///
+/// To show `.` output, connect D0-D7 to `DataBlock::data`, hold RS High and R/W Low, and pulse E
+/// once the byte is on the bus. The controller starts as it does after power-up: 8-bit, one line,
+/// with the display off, so it needs initializing first.
+#[derive(Debug)]
+pub struct IcHD44780 {
+    pins: Vec<Pin>,
+    state: Rc<RefCell<LcdState>>,
+    tick_count: Rc<Cell<usize>>,
+    columns: usize,
+    rows: usize,
+}
+
+impl IcHD44780 {
+    /// Register select pin index
+    pub(crate) const RS: usize = 0;
+
+    /// Read/write pin index
+    pub(crate) const RW: usize = 1;
+
+    /// Enable pin index
+    pub(crate) const E: usize = 2;
+
+    /// Data pin starting index
+    pub(crate) const D_START: usize = 3;
+
+    /// Data pin ending index
+    pub(crate) const D_END: usize = Self::D_START + 8;
+
+    // internal
+    /// Previous enable state index
+    const E_PREV: usize = Self::D_END;
+
+    /// Total number of pins in part
+    pub(crate) const NUM_PINS: usize = Self::E_PREV + 1;
+
+    /// Pin indexes by module pin number, starting from pin 1
+    const DIP: [Option<usize>; 16] = [
+        None,
+        None,
+        None,
+        Some(Self::RS),
+        Some(Self::RW),
+        Some(Self::E),
+        Some(Self::D_START),
+        Some(Self::D_START + 1),
+        Some(Self::D_START + 2),
+        Some(Self::D_START + 3),
+        Some(Self::D_START + 4),
+        Some(Self::D_START + 5),
+        Some(Self::D_START + 6),
+        Some(Self::D_START + 7),
+        None,
+        None,
+    ];
+
+    /// Creates a 16x2 LCD
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        Self::with_size(graph, name, 16, 2)
+    }
+
+    /// Creates an LCD with the given number of characters per row and rows
+    ///
+    /// Rows alternate between the two lines of DDRAM, so the third and fourth rows of a 4-row LCD
+    /// continue the first and second
+    pub fn with_size(graph: &mut Graph, name: &str, columns: usize, rows: usize) -> Self {
+        assert!(
+            columns * rows.div_ceil(2) <= 40 && (1..=4).contains(&rows),
+            "HD44780 can drive up to 4 rows and 80 characters"
+        );
+        let state = Rc::new(RefCell::new(LcdState::new()));
+        let tick_count = graph.tick_counter();
+
+        let mut states = [PinState::INPUT; Self::NUM_PINS];
+        states[Self::E_PREV] = PinState::Input(Signal::Low);
+        let pins = {
+            let state = state.clone();
+            graph.new_timed_part(name, &states, move |pins, timer| {
+                Self::update(pins, &mut state.borrow_mut(), timer)
+            })
+        };
+        graph.mark_sequential(&pins[0]);
+
+        Self {
+            pins,
+            state,
+            tick_count,
+            columns,
+            rows,
+        }
+    }
+
+    /// Part updater
+    fn update(pins: &mut [PinState], state: &mut LcdState, timer: &mut Timer) {
+        let e = level(pins[Self::E]);
+        let prev = level(pins[Self::E_PREV]);
+        pins[Self::E_PREV] = pins[Self::E];
+        let rs = level(pins[Self::RS]);
+        let rw = level(pins[Self::RW]);
+        let busy = timer.now() < state.busy_until;
+
+        // Reads drive the data lines while E is High, D4-D7 only in 4-bit mode
+        let first = if state.eight_bit { 0 } else { 4 };
+        let reading = e != Signal::Low && rw != Signal::Low;
+        let data_pins = &mut pins[Self::D_START..Self::D_END];
+        if reading {
+            let value = match rs {
+                Signal::Low => BusValue::new_val((busy as usize) << 7 | state.addr),
+                Signal::High => BusValue::new_val(*state.data() as usize),
+                _ => BusValue::new_error(0xff),
+            };
+            let value = match (state.eight_bit, state.read_low) {
+                (true, _) => value,
+                (false, false) => BusValue {
+                    val: value.val >> 4,
+                    error: value.error >> 4,
+                },
+                (false, true) => BusValue {
+                    val: value.val & 0xf,
+                    error: value.error & 0xf,
+                },
+            };
+            let value = match (e, rw) {
+                (Signal::High, Signal::High) => value,
+                _ => BusValue::new_error(0xff),
+            };
+            drive_bus(&mut data_pins[first..], value);
+        }
+        let released = if reading { first } else { 8 };
+        release_bus(&mut data_pins[..released]);
+
+        let falling = match (prev, e) {
+            (Signal::High, Signal::Low) => Signal::High,
+            (Signal::High, Signal::Error) | (Signal::Error, Signal::Low) => Signal::Error,
+            _ => Signal::Low,
+        };
+        match (falling, rw) {
+            (Signal::Low, _) => (),
+            (Signal::High, Signal::High) => {
+                // A read finishes, moving on to the next nibble or byte
+                if !state.eight_bit && !state.read_low {
+                    state.read_low = true;
+                } else {
+                    state.read_low = false;
+                    if rs == Signal::High {
+                        state.step(state.increment);
+                    }
+                }
+            }
+            (Signal::High, Signal::Low) => {
+                let data = pins[Self::D_START + first..Self::D_END].iter().val();
+                if data.error != 0 || rs == Signal::Error {
+                    state.high_nibble = None;
+                    state.dropped += 1;
+                    return;
+                }
+                let byte = match (state.eight_bit, state.high_nibble) {
+                    (true, _) => data.val as u8,
+                    (false, None) => {
+                        state.high_nibble = Some(data.val as u8);
+                        return;
+                    }
+                    (false, Some(high)) => {
+                        state.high_nibble = None;
+                        high << 4 | data.val as u8
+                    }
+                };
+                if busy {
+                    state.dropped += 1;
+                } else {
+                    let ns = state.execute(rs == Signal::High, byte);
+                    state.busy_until = timer.now() + timer.ticks(ns);
+                    timer.wake_at(state.busy_until);
+                }
+            }
+            // Might have been a write
+            _ => state.dropped += 1,
+        }
+    }
+
+    /// Register select, Low for instructions and High for data
+    pub fn rs(&self) -> &Pin {
+        &self.pins[Self::RS]
+    }
+
+    /// Read/write, High to read and Low to write
+    pub fn rw(&self) -> &Pin {
+        &self.pins[Self::RW]
+    }
+
+    /// Enable. Writes happen on the falling edge
+    pub fn e(&self) -> &Pin {
+        &self.pins[Self::E]
+    }
+
+    /// Data lines (D0-D7)
+    pub fn d(&self) -> &[Pin] {
+        &self.pins[Self::D_START..Self::D_END]
+    }
+
+    /// The pin with the given module pin number
+    ///
+    /// Panics for the power, contrast and backlight pins: 1-3, 15 and 16
+    pub fn dip(&self, number: usize) -> &Pin {
+        dip_pin(&self.pins, &Self::DIP, number)
+    }
+
+    /// Characters per row
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Number of rows
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The characters on each row, all spaces while the display is off
+    ///
+    /// Codes are shown in the standard font, with custom characters as `▒` and codes with no close
+    /// match as `?`. The cursor isn't shown.
+    pub fn lines(&self) -> Vec<String> {
+        let state = self.state.borrow();
+        (0..self.rows)
+            .map(|row| {
+                let line = row % 2;
+                if !state.display_on || (line == 1 && !state.two_lines) {
+                    return " ".repeat(self.columns);
+                }
+                let len = state.line_len();
+                let start = (row / 2) * self.columns + state.shift;
+                (0..self.columns)
+                    .map(|col| glyph(state.ddram[line * len + (start + col) % len]))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The displayed text, one line per row
+    pub fn text(&self) -> String {
+        self.lines().join("\n")
+    }
+
+    /// The display in a frame, as a few lines of text
+    pub fn render(&self, style: RenderStyle) -> String {
+        let (top, bottom, side) = match style {
+            RenderStyle::Unicode => (('┌', '─', '┐'), ('└', '─', '┘'), '│'),
+            RenderStyle::Ascii => (('+', '-', '+'), ('+', '-', '+'), '|'),
+        };
+        let edge = |(left, mid, right): (char, char, char)| {
+            format!("{}{}{}", left, mid.to_string().repeat(self.columns), right)
+        };
+        let mut lines = vec![edge(top)];
+        for line in self.lines() {
+            let line: String = match style {
+                RenderStyle::Unicode => line,
+                RenderStyle::Ascii => line
+                    .chars()
+                    .map(|c| if c.is_ascii() { c } else { '?' })
+                    .collect(),
+            };
+            lines.push(format!("{}{}{}", side, line, side));
+        }
+        lines.push(edge(bottom));
+        lines.join("\n")
+    }
+
+    /// Display data RAM by position: 80 characters in one-line mode, or 40 for each line in
+    /// two-line mode
+    pub fn ddram(&self) -> Vec<u8> {
+        self.state.borrow().ddram.to_vec()
+    }
+
+    /// Character generator RAM: 8 rows of 5 pixels for each custom character, top first
+    pub fn cgram(&self) -> Vec<u8> {
+        self.state.borrow().cgram.to_vec()
+    }
+
+    /// The address counter
+    pub fn addr(&self) -> usize {
+        self.state.borrow().addr
+    }
+
+    /// Whether the controller is still carrying out an instruction
+    pub fn busy(&self) -> bool {
+        self.tick_count.get() < self.state.borrow().busy_until
+    }
+
+    /// Number of writes ignored because the controller was busy or a signal was Error
+    pub fn dropped_writes(&self) -> usize {
+        self.state.borrow().dropped
+    }
+}
+
+#[cfg(test)]
+mod test_lcd {
+    use crate::test_util::*;
+    use crate::*;
+
+    /// Drives an LCD from the host. The data lines go through a tristate buffer so they can be
+    /// released for reads
+    struct Bench {
+        graph: Graph,
+        lcd: IcHD44780,
+        rs: Pin,
+        rw: Pin,
+        e: Pin,
+        en: Pin,
+        d: Vec<Pin>,
+    }
+
+    impl Bench {
+        fn new(rows: usize) -> Self {
+            let mut graph = Graph::new();
+            // 1us ticks, so instructions take 37 ticks and clearing takes 1520
+            graph.set_tick_ns(1000.0);
+            let lcd = IcHD44780::with_size(&mut graph, "lcd", 16, rows);
+            let buffer = BusTristate::new(&mut graph, "buffer", 8);
+            let rs = drive_pin(&mut graph, lcd.rs(), Signal::Low);
+            let rw = drive_pin(&mut graph, lcd.rw(), Signal::Low);
+            let e = drive_pin(&mut graph, lcd.e(), Signal::Low);
+            let en = drive_pin(&mut graph, buffer.en(), Signal::High);
+            let d = drive(&mut graph, buffer.input());
+            for (out, pin) in buffer.output().iter().zip(lcd.d()) {
+                graph.connect(out, pin);
+            }
+            graph.run_for(10);
+            Self {
+                graph,
+                lcd,
+                rs,
+                rw,
+                e,
+                en,
+                d,
+            }
+        }
+
+        /// Pulses E
+        fn pulse(&mut self) {
+            self.e.set_output(Signal::High);
+            self.graph.run_for(10);
+            self.e.set_output(Signal::Low);
+            self.graph.run_for(10);
+        }
+
+        /// Writes a byte on D0-D7, of which only D4-D7 are used in 4-bit mode
+        fn write(&mut self, rs: bool, byte: u8) {
+            self.rs.set_output(Signal::from(rs));
+            self.rw.set_output(Signal::Low);
+            self.en.set_output(Signal::High);
+            set_bus(&mut self.d, byte as usize);
+            self.graph.run_for(10);
+            self.pulse();
+        }
+
+        /// Writes a byte as two nibbles on D4-D7
+        fn write4(&mut self, rs: bool, byte: u8) {
+            self.write(rs, byte & 0xf0);
+            self.write(rs, byte << 4);
+        }
+
+        /// Reads the data lines while E is High
+        fn read(&mut self, rs: bool) -> usize {
+            self.rs.set_output(Signal::from(rs));
+            self.rw.set_output(Signal::High);
+            self.en.set_output(Signal::Low);
+            self.e.set_output(Signal::High);
+            self.graph.run_for(10);
+            let value = self.lcd.d().iter().val().val;
+            self.e.set_output(Signal::Low);
+            self.graph.run_for(10);
+            value
+        }
+
+        /// Polls the busy flag until it clears, returning the number of polls
+        fn wait(&mut self) -> usize {
+            let mut polls = 1;
+            while self.read(false) & 0x80 != 0 {
+                polls += 1;
+            }
+            polls
+        }
+    }
+
+    #[test]
+    fn test_8_bit() {
+        let mut bench = Bench::new(2);
+        assert_eq!(bench.lcd.text(), " ".repeat(16) + "\n" + &" ".repeat(16));
+
+        // Function set: 8-bit, 2 lines. Display on. Clear
+        bench.write(false, 0x38);
+        bench.wait();
+        bench.write(false, 0x0c);
+        bench.wait();
+        bench.write(false, 0x01);
+        assert!(bench.lcd.busy());
+        assert!(bench.wait() > 10);
+
+        for c in b"Hello" {
+            bench.write(true, *c);
+            bench.wait();
+        }
+        // Second line
+        bench.write(false, 0xc0);
+        bench.wait();
+        for c in b"bfpu\\~" {
+            bench.write(true, *c);
+            bench.wait();
+        }
+        assert_eq!(
+            bench.lcd.lines(),
+            vec!["Hello           ", "bfpu¥→          "]
+        );
+        assert_eq!(bench.lcd.addr(), 0x46);
+        assert_eq!(bench.read(false), 0x46);
+        assert_eq!(bench.lcd.dropped_writes(), 0);
+
+        // Read back from DDRAM, which moves the address counter
+        bench.write(false, 0x80);
+        bench.wait();
+        assert_eq!(bench.read(true), b'H' as usize);
+        assert_eq!(bench.read(true), b'e' as usize);
+        assert_eq!(bench.lcd.addr(), 2);
+
+        assert_eq!(
+            bench.lcd.render(RenderStyle::Ascii),
+            [
+                "+----------------+",
+                "|Hello           |",
+                "|bfpu??          |",
+                "+----------------+",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_4_bit() {
+        let mut bench = Bench::new(2);
+        // The usual reset sequence, then function set: 4-bit, 2 lines
+        for nibble in [0x30, 0x30, 0x30, 0x20] {
+            bench.write(false, nibble);
+            bench.wait();
+        }
+        bench.write4(false, 0x28);
+        bench.wait();
+        bench.write4(false, 0x0c);
+        bench.wait();
+        for c in b"4 bit" {
+            bench.write4(true, *c);
+            bench.wait();
+        }
+        assert_eq!(bench.lcd.lines()[0], "4 bit           ");
+
+        // Reads come a nibble at a time on D4-D7
+        let high = bench.read(false);
+        let low = bench.read(false);
+        assert_eq!(high & 0xf0 | low >> 4, 5);
+    }
+
+    #[test]
+    fn test_busy() {
+        let mut bench = Bench::new(2);
+        bench.write(false, 0x38);
+        bench.wait();
+        bench.write(false, 0x0f);
+        bench.wait();
+
+        bench.write(false, 0x01);
+        bench.write(true, b'x');
+        assert_eq!(bench.lcd.dropped_writes(), 1);
+        bench.wait();
+        bench.write(true, b'y');
+        bench.wait();
+        assert_eq!(bench.lcd.ddram()[0], b'y');
+
+        // Error data is dropped
+        bench.rw.set_output(Signal::Low);
+        bench.en.set_output(Signal::High);
+        bench.d[3].set_output(Signal::Error);
+        bench.graph.run();
+        bench.pulse();
+        assert_eq!(bench.lcd.dropped_writes(), 2);
+        assert_eq!(bench.lcd.addr(), 1);
+
+        // A status read in progress sees the busy flag drop when the instruction finishes
+        bench.write(false, 0x02);
+        bench.rs.set_output(Signal::Low);
+        bench.rw.set_output(Signal::High);
+        bench.en.set_output(Signal::Low);
+        bench.e.set_output(Signal::High);
+        bench.graph.run_for(10);
+        assert_eq!(bench.lcd.d()[7].sig(), Signal::High);
+        assert!(bench.graph.run().ticks > 1000);
+        assert_eq!(bench.lcd.d()[7].sig(), Signal::Low);
+        assert!(!bench.lcd.busy());
+    }
+
+    #[test]
+    fn test_shift_and_cgram() {
+        let mut bench = Bench::new(4);
+        bench.write(false, 0x38);
+        bench.wait();
+        bench.write(false, 0x0c);
+        bench.wait();
+        // Fill line 1 with A..Z..
+        for i in 0..40 {
+            bench.write(true, b'A' + i);
+            bench.wait();
+        }
+        let lines = bench.lcd.lines();
+        assert_eq!(lines[0], "ABCDEFGHIJKLMNOP");
+        assert_eq!(lines[2], "QRSTUVWXYZ[¥]^_`");
+
+        // Shift the display left twice, then right once
+        for instruction in [0x18, 0x18, 0x1c] {
+            bench.write(false, instruction);
+            bench.wait();
+        }
+        assert_eq!(bench.lcd.lines()[0], "BCDEFGHIJKLMNOPQ");
+
+        // A custom character
+        bench.write(false, 0x48);
+        bench.wait();
+        for row in [0x0e, 0x11, 0x11, 0x11, 0x0e, 0, 0, 0] {
+            bench.write(true, row);
+            bench.wait();
+        }
+        assert_eq!(bench.lcd.cgram()[8..13], [0x0e, 0x11, 0x11, 0x11, 0x0e]);
+        bench.write(false, 0x81);
+        bench.wait();
+        bench.write(true, 1);
+        bench.wait();
+        assert_eq!(bench.lcd.lines()[0], "▒CDEFGHIJKLMNOPQ");
+        assert_eq!(bench.lcd.ddram()[1], 1);
+    }
+
+    #[test]
+    fn test_data_block() {
+        let mut graph = Graph::new();
+        let data_block = DataBlock::new(&mut graph, "data");
+        let lcd = IcHD44780::new(&mut graph, "lcd");
+        for (pin, d) in data_block.data().iter().zip(lcd.d()) {
+            graph.connect(pin, d);
+        }
+        let mut d = drive(
+            &mut graph,
+            [
+                data_block.d_ce(),
+                data_block.up(),
+                data_block.down(),
+                data_block.reset(),
+            ],
+        );
+        drive(&mut graph, [data_block.p_ce(), data_block.i_ce()]);
+        drive(&mut graph, [data_block.store(), data_block.clear()]);
+        let mut count = drive_pin(&mut graph, data_block.count(), Signal::Low);
+        let mut rs = drive_pin(&mut graph, lcd.rs(), Signal::Low);
+        drive_pin(&mut graph, lcd.rw(), Signal::Low);
+        let mut e = drive_pin(&mut graph, lcd.e(), Signal::Low);
+        let [d_ce, up, down, reset] = &mut d[..] else {
+            unreachable!()
+        };
+
+        // Clear the register and put it on the bus
+        reset.set_output(Signal::High);
+        graph.run();
+        reset.set_output(Signal::Low);
+        d_ce.set_output(Signal::High);
+        graph.run();
+
+        // Count the register to each byte and write it. Running the graph waits out the busy
+        // time, which is counted in ticks as each instruction starts
+        let mut value = 0;
+        for (data, byte, tick_ns, min_ticks) in [
+            (false, 0x38, 1.0, 37_000),
+            (false, 0x0c, 1.0, 37_000),
+            (true, b'H', 1.0, 41_000),
+            (true, b'i', 1000.0, 41),
+        ] {
+            up.set_output(Signal::from(byte > value));
+            down.set_output(Signal::from(byte < value));
+            for _ in 0..byte.abs_diff(value) {
+                graph.pulse_output(&mut count);
+            }
+            value = byte;
+            assert_eq!(data_block.data().iter().val().unwrap(), byte as usize);
+
+            rs.set_output(Signal::from(data));
+            graph.set_tick_ns(tick_ns);
+            let start = graph.tick_count();
+            graph.pulse_output(&mut e);
+            let ticks = graph.tick_count() - start;
+            assert!((min_ticks..min_ticks + 100).contains(&ticks), "{}", ticks);
+            assert!(!lcd.busy());
+        }
+        assert_eq!(lcd.lines(), ["Hi              ", "                "]);
+        assert_eq!(lcd.dropped_writes(), 0);
+    }
+}
@@ -1,15 +1,69 @@
+pub mod access_log;
+pub mod arithmetic;
+pub mod bfpu;
+pub mod brainfuck;
+pub mod bus_interface;
+pub mod clock;
+pub mod control_block;
 pub mod data_block;
+pub mod decoder;
+pub mod delay;
+pub mod display;
+pub mod flip_flop;
+pub mod gal;
 pub mod gate;
 pub mod graph;
 pub mod ic;
+pub mod interpreter;
+pub mod io_block;
+pub mod lcd;
+pub mod lockstep;
+pub mod lookup_table;
+pub mod loop_unit;
+pub mod memory;
+pub mod power;
+pub mod program_block;
+pub mod ram;
+pub mod rom;
+pub mod shift_register;
+pub mod synthesis;
+#[cfg(test)]
+mod test_util;
+pub mod timing;
 
 use std::fmt::{Debug, Formatter};
 use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
 
+pub use access_log::*;
+pub use arithmetic::*;
+pub use bfpu::*;
+pub use brainfuck::*;
+pub use bus_interface::*;
+pub use clock::*;
+pub use control_block::*;
 pub use data_block::*;
+pub use decoder::*;
+pub use delay::*;
+pub use display::*;
+pub use flip_flop::*;
+pub use gal::*;
 pub use gate::*;
 pub use graph::*;
 pub use ic::*;
+pub use interpreter::*;
+pub use io_block::*;
+pub use lcd::*;
+pub use lockstep::*;
+pub use lookup_table::*;
+pub use loop_unit::*;
+pub use memory::*;
+pub use power::*;
+pub use program_block::*;
+pub use ram::*;
+pub use rom::*;
+pub use shift_register::*;
+pub use synthesis::*;
+pub use timing::*;
 
 /// The logical value for a given node, pin, etc.
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Hash)]
@@ -32,10 +86,7 @@ pub enum Signal {
 impl Signal {
     /// Helper to treat Off and Low as the same value
     fn is_lowish(&self) -> bool {
-        match self {
-            Signal::Low | Signal::Off => true,
-            _ => false,
-        }
+        matches!(self, Signal::Low | Signal::Off)
     }
 
     fn is_high(&self) -> bool {
@@ -43,6 +94,17 @@ impl Signal {
     }
 }
 
+impl From<bool> for Signal {
+    /// High for true, Low for false
+    fn from(b: bool) -> Self {
+        if b {
+            Signal::High
+        } else {
+            Signal::Low
+        }
+    }
+}
+
 pub trait ToSignal {
     fn sig(&self) -> Signal;
 }
@@ -271,9 +333,10 @@ impl BitXorAssign for Signal {
 ///
 /// Parts update their PinStates each tick. This allows connections to change between input, output,
 /// and high impedance states (e.g. for chip enable, bidirectional ports, etc.)
-#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Hash)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd, Hash)]
 pub enum PinState {
     /// High impedance, acting as neither an input nor an output. Logically Off
+    #[default]
     HiZ,
 
     /// An input receives its signal from the connected Node
@@ -302,12 +365,6 @@ impl PinState {
     }
 }
 
-impl Default for PinState {
-    fn default() -> Self {
-        PinState::HiZ
-    }
-}
-
 impl ToSignal for PinState {
     /// Get the logical signal for the PinState
     fn sig(&self) -> Signal {
@@ -330,9 +387,9 @@ impl ToValue for &PinState {
     }
 }
 
-impl Into<Signal> for PinState {
-    fn into(self) -> Signal {
-        self.sig()
+impl From<PinState> for Signal {
+    fn from(val: PinState) -> Self {
+        val.sig()
     }
 }
 
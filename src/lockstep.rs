@@ -0,0 +1,181 @@
+use crate::*;
+use std::fmt::{Display, Formatter};
+
+/// The data state compared after each instruction
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// How the last step ended
+    pub step: Step,
+
+    /// The data pointer
+    pub ptr: BusValue,
+
+    /// The current cell
+    pub cell: BusValue,
+
+    /// Everything output so far
+    pub output: Vec<u8>,
+}
+
+impl Snapshot {
+    /// Takes a snapshot of the interpreter
+    pub fn of_interpreter(interpreter: &Interpreter, step: Step) -> Self {
+        Self {
+            step,
+            ptr: BusValue::new_val(interpreter.ptr() as usize),
+            cell: BusValue::new_val(interpreter.cell() as usize),
+            output: interpreter.host().output(),
+        }
+    }
+
+    /// Takes a snapshot of the bfpu, reading the pointer and the data bus
+    pub fn of_bfpu(bfpu: &Bfpu, step: Step) -> Self {
+        Self {
+            step,
+            ptr: bfpu.data().addr().iter().val(),
+            cell: bfpu.data().data().iter().val(),
+            output: bfpu.io().host().output(),
+        }
+    }
+}
+
+/// The first point where the bfpu and the interpreter disagree
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    /// Number of instructions that matched before this one
+    pub matched: usize,
+
+    /// The instruction that was executed, according to the interpreter
+    pub opcode: Opcode,
+
+    /// The interpreter's state
+    pub expected: Snapshot,
+
+    /// The bfpu's state
+    pub actual: Snapshot,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "diverged at `{}` after {} matching instructions: expected {:?}, got {:?}",
+            self.opcode.to_char(),
+            self.matched,
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+/// Whether the instruction touches the pointer, the tape or the I/O streams
+fn changes_data(op: Opcode) -> bool {
+    !matches!(op, Opcode::LoopStart | Opcode::LoopEnd | Opcode::Halt)
+}
+
+/// Steps the interpreter through the next instruction that changes data
+fn step_interpreter(interpreter: &mut Interpreter) -> (Opcode, Step) {
+    loop {
+        let op = interpreter.opcode();
+        match interpreter.step() {
+            Step::Executed if !changes_data(op) => continue,
+            step => return (op, step),
+        }
+    }
+}
+
+/// Steps the bfpu through the next instruction that changes data, ignoring skipped instructions
+fn step_bfpu(graph: &mut Graph, bfpu: &mut Bfpu) -> Step {
+    loop {
+        let step = bfpu.step(graph);
+        let skipped = bfpu.loop_unit().outputs().skip().sig() == Signal::High;
+        match bfpu.control().opcode() {
+            Some(op) if step == Step::Executed && (skipped || !changes_data(op)) => continue,
+            None if step == Step::Executed => continue,
+            _ => return step,
+        }
+    }
+}
+
+/// Runs the bfpu and the interpreter side by side until they halt, stop for input, or disagree
+///
+/// Loop instructions only move the program counter, and the bfpu may take any number of steps to
+/// jump, so the two are compared after each instruction that changes data. Both should start from
+/// reset with a clear tape and the same input. Returns the number of matching instructions.
+pub fn lockstep(
+    graph: &mut Graph,
+    bfpu: &mut Bfpu,
+    interpreter: &mut Interpreter,
+) -> Result<usize, Box<Divergence>> {
+    let mut matched = 0;
+    loop {
+        let (opcode, expected) = step_interpreter(interpreter);
+        let actual = step_bfpu(graph, bfpu);
+        let expected = Snapshot::of_interpreter(interpreter, expected);
+        let actual = Snapshot::of_bfpu(bfpu, actual);
+        if expected != actual {
+            return Err(Box::new(Divergence {
+                matched,
+                opcode,
+                expected,
+                actual,
+            }));
+        }
+        if expected.step != Step::Executed {
+            return Ok(matched);
+        }
+        matched += 1;
+    }
+}
+
+#[cfg(test)]
+mod test_lockstep {
+    use crate::*;
+
+    /// Builds a bfpu and an interpreter for the same program and input, both ready to run
+    fn setup(kind: LoopKind, source: &str, input: &[u8]) -> (Graph, Bfpu, Interpreter) {
+        let mut graph = Graph::new();
+        let host = IoHost::new(input).with_eof(EofBehavior::Zero);
+        let mut bfpu = Bfpu::new(&mut graph, "bfpu", kind, host);
+        let program = bfpu.program().program();
+        OpcodeEncoding::default().load(program, source).unwrap();
+        bfpu.data().tape().fill(0);
+        bfpu.quick_reset(&mut graph);
+
+        let host = IoHost::new(input).with_eof(EofBehavior::Zero);
+        let interpreter = Interpreter::new(source, host).unwrap();
+        (graph, bfpu, interpreter)
+    }
+
+    #[test]
+    fn test_match() {
+        // Includes a loop that is skipped on entry
+        let source = ">,[<++>-]<.<-.>>[<+>-],.";
+        for kind in LoopKind::ALL {
+            let (mut graph, mut bfpu, mut interpreter) = setup(kind, source, &[3]);
+            assert_eq!(
+                lockstep(&mut graph, &mut bfpu, &mut interpreter),
+                Ok(26),
+                "{:?}",
+                kind
+            );
+            assert_eq!(bfpu.io().host().output(), [6, 0xff, 0], "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_divergence() {
+        for kind in LoopKind::ALL {
+            let (mut graph, mut bfpu, mut interpreter) = setup(kind, ">+.", &[]);
+            bfpu.data().tape().fill(1);
+
+            let divergence = lockstep(&mut graph, &mut bfpu, &mut interpreter).unwrap_err();
+            assert_eq!(divergence.matched, 0, "{:?}", kind);
+            assert_eq!(divergence.opcode, Opcode::Right, "{:?}", kind);
+            assert_eq!(divergence.expected.cell, BusValue::new_val(0), "{:?}", kind);
+            assert_eq!(divergence.actual.cell, BusValue::new_val(1), "{:?}", kind);
+        }
+    }
+}
@@ -0,0 +1,597 @@
+use crate::*;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+/// Most inputs a table can have
+pub const MAX_TABLE_INPUTS: usize = 16;
+
+/// Most outputs a table can have, one for each bit of a `BusValue`
+pub const MAX_TABLE_OUTPUTS: usize = usize::BITS as usize;
+
+/// A problem with a truth table or boolean expression
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableError {
+    /// Line of the problem, numbered from 1, or 0 for tables that weren't parsed from text
+    pub line: usize,
+
+    /// What went wrong
+    pub message: String,
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TableError {}
+
+/// Creates a `TableError`
+fn table_error<T>(line: usize, message: impl Into<String>) -> Result<T, TableError> {
+    Err(TableError {
+        line,
+        message: message.into(),
+    })
+}
+
+/// A combinational function of up to `MAX_TABLE_INPUTS` named inputs
+///
+/// Input and output words are least-significant first, so `inputs[0]` is bit 0 of the index into
+/// `values`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TruthTable {
+    /// Input names
+    pub inputs: Vec<String>,
+
+    /// Output names
+    pub outputs: Vec<String>,
+
+    /// The outputs for each combination of inputs
+    pub values: Vec<usize>,
+
+    /// Outputs that don't matter for each combination of inputs, e.g. for logic synthesis
+    pub dont_care: Vec<usize>,
+}
+
+impl TruthTable {
+    /// Creates a table with inputs and outputs named `in0`, `in1`... and `out0`, `out1`...
+    pub fn from_fn<F>(inputs: usize, outputs: usize, f: F) -> Result<Self, TableError>
+    where
+        F: Fn(usize) -> usize,
+    {
+        if inputs > MAX_TABLE_INPUTS {
+            return table_error(0, format!("more than {} inputs", MAX_TABLE_INPUTS));
+        }
+        if outputs > MAX_TABLE_OUTPUTS {
+            return table_error(0, format!("more than {} outputs", MAX_TABLE_OUTPUTS));
+        }
+        let mask = usize::MAX
+            .checked_shr((MAX_TABLE_OUTPUTS - outputs) as u32)
+            .unwrap_or(0);
+        Ok(Self {
+            inputs: (0..inputs).map(|i| format!("in{}", i)).collect(),
+            outputs: (0..outputs).map(|i| format!("out{}", i)).collect(),
+            values: (0..1 << inputs).map(|i| f(i) & mask).collect(),
+            dont_care: vec![0; 1 << inputs],
+        })
+    }
+
+    /// Parses a text truth table
+    ///
+    /// The first line names the inputs, then a `|`, then the outputs, separated by spaces. Each
+    /// following line is a row in the same layout, with `0`, `1` or `X` for each input and output.
+    /// Spaces in rows are optional. An `X` input stands for both values, and an `X` output doesn't
+    /// matter and is Low in a part. Combinations not covered by any row are Low. Everything after a
+    /// `#` is a comment.
+    ///
+    /// ```text
+    /// # Majority vote, and whether anyone voted
+    /// a b c | maj any
+    /// 0 0 0 | 0   0
+    /// 0 0 1 | 0   1
+    /// 0 1 0 | 0   1
+    /// 1 0 0 | 0   1
+    /// X 1 1 | 1   1
+    /// 1 X 1 | 1   1
+    /// 1 1 X | 1   1
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, TableError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.split('#').next().unwrap().trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let Some((header_line, header)) = lines.next() else {
+            return table_error(1, "missing header");
+        };
+        let Some((inputs, outputs)) = header.split_once('|') else {
+            return table_error(header_line, "header needs a | between inputs and outputs");
+        };
+        let inputs: Vec<String> = inputs.split_whitespace().map(String::from).collect();
+        let outputs: Vec<String> = outputs.split_whitespace().map(String::from).collect();
+        if inputs.len() > MAX_TABLE_INPUTS {
+            let message = format!("more than {} inputs", MAX_TABLE_INPUTS);
+            return table_error(header_line, message);
+        }
+        if outputs.len() > MAX_TABLE_OUTPUTS {
+            let message = format!("more than {} outputs", MAX_TABLE_OUTPUTS);
+            return table_error(header_line, message);
+        }
+
+        let mut table = Self {
+            values: vec![0; 1 << inputs.len()],
+            dont_care: vec![0; 1 << inputs.len()],
+            inputs,
+            outputs,
+        };
+        // The line that set each combination, to catch conflicts
+        let mut set_by = vec![None; table.values.len()];
+
+        for (line, row) in lines {
+            let Some((ins, outs)) = row.split_once('|') else {
+                return table_error(line, "row needs a | between inputs and outputs");
+            };
+            let ins = Self::parse_bits(line, ins, table.inputs.len(), "inputs")?;
+            let (value, dont_care) = Self::parse_bits(line, outs, table.outputs.len(), "outputs")?;
+
+            // Every combination of the X inputs
+            let (fixed, free) = ins;
+            let mut sub = free;
+            loop {
+                let i = fixed | sub;
+                if let Some(other) = set_by[i] {
+                    if (table.values[i], table.dont_care[i]) != (value, dont_care) {
+                        let message = format!("conflicts with line {}", other);
+                        return table_error(line, message);
+                    }
+                }
+                set_by[i] = Some(line);
+                table.values[i] = value;
+                table.dont_care[i] = dont_care;
+                if sub == 0 {
+                    break;
+                }
+                sub = (sub - 1) & free;
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Parses a row's bits as the value and the mask of X bits
+    fn parse_bits(
+        line: usize,
+        text: &str,
+        count: usize,
+        what: &str,
+    ) -> Result<(usize, usize), TableError> {
+        let bits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+        if bits.len() != count {
+            let message = format!("expected {} {}, found {}", count, what, bits.len());
+            return table_error(line, message);
+        }
+        let mut value = 0;
+        let mut x = 0;
+        for (i, c) in bits.iter().enumerate() {
+            match c {
+                '0' => (),
+                '1' => value |= 1 << i,
+                'X' | 'x' => x |= 1 << i,
+                _ => return table_error(line, format!("unexpected '{}' in {}", c, what)),
+            }
+        }
+        Ok((value, x))
+    }
+
+    /// Builds a one-output table from a boolean expression like `!a & (b | c)`
+    ///
+    /// Names are letters, digits and underscores, starting with a letter or underscore. The inputs
+    /// are the names in alphabetical order and the output is named `out`. Operators from lowest to
+    /// highest precedence are `|`, `^`, `&` and `!`, and `0` and `1` are constants.
+    pub fn from_expr(expr: &str) -> Result<Self, TableError> {
+        let tokens = Expr::tokenize(expr)?;
+        let names: BTreeSet<&str> = tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Name(name) => Some(*name),
+                _ => None,
+            })
+            .collect();
+        let names: Vec<&str> = names.into_iter().collect();
+        if names.len() > MAX_TABLE_INPUTS {
+            return table_error(1, format!("more than {} inputs", MAX_TABLE_INPUTS));
+        }
+
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            names: &names,
+        };
+        let parsed = parser.or()?;
+        if parser.pos < tokens.len() {
+            return table_error(1, format!("unexpected {}", tokens[parser.pos]));
+        }
+
+        Ok(Self {
+            inputs: names.iter().map(|name| name.to_string()).collect(),
+            outputs: vec!["out".to_string()],
+            values: (0..1 << names.len())
+                .map(|i| parsed.eval(i) as usize)
+                .collect(),
+            dont_care: vec![0; 1 << names.len()],
+        })
+    }
+}
+
+/// A token of a boolean expression
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Name(&'a str),
+    Const(bool),
+    Op(char),
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Name(name) => write!(f, "'{}'", name),
+            Token::Const(val) => write!(f, "'{}'", *val as u8),
+            Token::Op(op) => write!(f, "'{}'", op),
+        }
+    }
+}
+
+/// A parsed boolean expression
+enum Expr {
+    Input(usize),
+    Const(bool),
+    Not(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Splits an expression into tokens
+    fn tokenize(expr: &str) -> Result<Vec<Token<'_>>, TableError> {
+        let mut tokens = Vec::new();
+        let mut chars = expr.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            match c {
+                _ if c.is_whitespace() => (),
+                '0' | '1' => tokens.push(Token::Const(c == '1')),
+                '!' | '&' | '|' | '^' | '(' | ')' => tokens.push(Token::Op(c)),
+                _ if c.is_alphabetic() || c == '_' => {
+                    let mut end = start + c.len_utf8();
+                    while let Some((i, c)) = chars.peek() {
+                        if !c.is_alphanumeric() && *c != '_' {
+                            break;
+                        }
+                        end = i + c.len_utf8();
+                        chars.next();
+                    }
+                    tokens.push(Token::Name(&expr[start..end]));
+                }
+                _ => return table_error(1, format!("unexpected '{}' at column {}", c, start + 1)),
+            }
+        }
+        Ok(tokens)
+    }
+
+    /// Value of the expression for a combination of inputs
+    fn eval(&self, inputs: usize) -> bool {
+        match self {
+            Expr::Input(i) => inputs & (1 << i) != 0,
+            Expr::Const(val) => *val,
+            Expr::Not(a) => !a.eval(inputs),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(inputs), b.eval(inputs));
+                match op {
+                    '&' => a & b,
+                    '|' => a | b,
+                    _ => a ^ b,
+                }
+            }
+        }
+    }
+}
+
+/// Recursive descent parser for boolean expressions
+struct Parser<'a> {
+    tokens: &'a [Token<'a>],
+    pos: usize,
+    names: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    /// Parses operands separated by `op`, using `operand` for each
+    fn binary(
+        &mut self,
+        op: char,
+        operand: fn(&mut Self) -> Result<Expr, TableError>,
+    ) -> Result<Expr, TableError> {
+        let mut expr = operand(self)?;
+        while self.tokens.get(self.pos) == Some(&Token::Op(op)) {
+            self.pos += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(operand(self)?));
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr, TableError> {
+        self.binary('|', Self::xor)
+    }
+
+    fn xor(&mut self) -> Result<Expr, TableError> {
+        self.binary('^', Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, TableError> {
+        self.binary('&', Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, TableError> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        match token {
+            Some(Token::Op('!')) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Op('(')) => {
+                let expr = self.or()?;
+                if self.tokens.get(self.pos) != Some(&Token::Op(')')) {
+                    return table_error(1, "missing ')'");
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Some(Token::Name(name)) => {
+                let i = self.names.iter().position(|n| *n == name).unwrap();
+                Ok(Expr::Input(i))
+            }
+            Some(Token::Const(val)) => Ok(Expr::Const(val)),
+            Some(token) => table_error(1, format!("unexpected {}", token)),
+            None => table_error(1, "unexpected end of expression"),
+        }
+    }
+}
+
+/// A combinational part that looks its outputs up in a table, like a ROM or PLD
+///
+/// Off inputs count as Low. For Error inputs, every combination of the Error bits is looked up, and
+/// outputs that differ between them are Error. Table entries can have Error bits of their own.
+#[derive(Debug)]
+pub struct LookupTable {
+    pins: Vec<Pin>,
+    inputs: usize,
+}
+
+impl LookupTable {
+    /// Creates a part with `inputs` input pins, `outputs` output pins and a table with an entry for
+    /// every combination of inputs
+    pub fn new(
+        graph: &mut Graph,
+        name: &str,
+        inputs: usize,
+        outputs: usize,
+        table: Vec<BusValue>,
+    ) -> Self {
+        assert!(
+            inputs <= MAX_TABLE_INPUTS,
+            "Tables can have at most {} inputs",
+            MAX_TABLE_INPUTS
+        );
+        assert!(
+            outputs <= MAX_TABLE_OUTPUTS,
+            "Tables can have at most {} outputs",
+            MAX_TABLE_OUTPUTS
+        );
+        assert_eq!(table.len(), 1 << inputs, "Table needs 2^inputs entries");
+
+        let mut states = vec![PinState::INPUT; inputs + outputs];
+        states[inputs..].fill(PinState::OUTPUT);
+        let pins = graph.new_part(name, &states, move |pins| {
+            let (input_pins, output_pins) = pins.split_at_mut(inputs);
+            let input = input_pins.iter().val();
+            drive_bus(output_pins, Self::lookup(&table, input));
+        });
+        Self { pins, inputs }
+    }
+
+    /// Creates a part from a function of the inputs
+    pub fn from_fn<F>(
+        graph: &mut Graph,
+        name: &str,
+        inputs: usize,
+        outputs: usize,
+        f: F,
+    ) -> Result<Self, TableError>
+    where
+        F: Fn(usize) -> usize,
+    {
+        let table = TruthTable::from_fn(inputs, outputs, f)?;
+        Ok(Self::from_table(graph, name, &table))
+    }
+
+    /// Creates a part from a truth table, with Low for don't-care outputs
+    pub fn from_table(graph: &mut Graph, name: &str, table: &TruthTable) -> Self {
+        let values = table.values.iter().map(|v| BusValue::new_val(*v)).collect();
+        Self::new(graph, name, table.inputs.len(), table.outputs.len(), values)
+    }
+
+    /// Creates a part from a text truth table, see `TruthTable::parse`
+    pub fn from_text(graph: &mut Graph, name: &str, text: &str) -> Result<Self, TableError> {
+        Ok(Self::from_table(graph, name, &TruthTable::parse(text)?))
+    }
+
+    /// Creates a one-output part from a boolean expression, see `TruthTable::from_expr`
+    pub fn from_expr(graph: &mut Graph, name: &str, expr: &str) -> Result<Self, TableError> {
+        Ok(Self::from_table(graph, name, &TruthTable::from_expr(expr)?))
+    }
+
+    /// Looks up an input word that may have Error bits
+    fn lookup(table: &[BusValue], input: BusValue) -> BusValue {
+        let mut result = table[input.val];
+        let mut sub = input.error;
+        while sub != 0 {
+            let other = table[input.val | sub];
+            result.error |= other.error | (other.val ^ result.val);
+            sub = (sub - 1) & input.error;
+        }
+        result.val &= !result.error;
+        result
+    }
+
+    /// Input pins, least-significant first
+    pub fn input(&self) -> &[Pin] {
+        &self.pins[..self.inputs]
+    }
+
+    /// Output pins, least-significant first
+    pub fn output(&self) -> &[Pin] {
+        &self.pins[self.inputs..]
+    }
+}
+
+#[cfg(test)]
+mod test_lookup_table {
+    use crate::test_util::*;
+    use crate::*;
+
+    /// Drives every combination of inputs, checking the outputs against `f`
+    fn check(graph: &mut Graph, part: &LookupTable, f: impl Fn(usize) -> usize) {
+        let mut drivers = drive(graph, part.input());
+        for i in 0..1 << drivers.len() {
+            set_bus(&mut drivers, i);
+            graph.run();
+            assert_eq!(part.output().iter().val(), BusValue::new_val(f(i)), "{}", i);
+        }
+    }
+
+    #[test]
+    fn test_values() {
+        let mut graph = Graph::new();
+        let square = (0..16).map(|i| BusValue::new_val(i * i)).collect();
+        let part = LookupTable::new(&mut graph, "square", 4, 8, square);
+        check(&mut graph, &part, |i| i * i);
+
+        let parity = |i: usize| i.count_ones() as usize & 1;
+        let part = LookupTable::from_fn(&mut graph, "parity", 5, 1, parity).unwrap();
+        check(&mut graph, &part, parity);
+
+        // Every output bit of a word is fine, but one more doesn't fit
+        let table = TruthTable::from_fn(1, MAX_TABLE_OUTPUTS, |i| usize::MAX * i).unwrap();
+        assert_eq!(table.values, [0, usize::MAX]);
+        let err = TruthTable::from_fn(1, MAX_TABLE_OUTPUTS + 1, |i| i).unwrap_err();
+        assert_eq!(err.line, 0);
+        let err = TruthTable::from_fn(MAX_TABLE_INPUTS + 1, 1, |i| i).unwrap_err();
+        assert_eq!(err.line, 0);
+    }
+
+    #[test]
+    fn test_text() {
+        let text = "
+            # Majority vote, and whether anyone voted
+            a b c | maj any
+            0 0 0 | 0   0
+            0 0 1 | 0   1
+            0 1 0 | 0   1
+            1 0 0 | 0   1
+            X 1 1 | 1   1
+            1 X 1 | 1   1
+            11X   | 1   1
+        ";
+        let table = TruthTable::parse(text).unwrap();
+        assert_eq!(table.inputs, ["a", "b", "c"]);
+        assert_eq!(table.outputs, ["maj", "any"]);
+
+        let mut graph = Graph::new();
+        let part = LookupTable::from_text(&mut graph, "vote", text).unwrap();
+        check(&mut graph, &part, |i| {
+            let maj = (i.count_ones() >= 2) as usize;
+            let any = (i != 0) as usize;
+            maj | any << 1
+        });
+
+        // Don't-care outputs are recorded, and unlisted rows are Low
+        let table = TruthTable::parse("a b | y\n1 X | X\n").unwrap();
+        assert_eq!(table.values, [0, 0, 0, 0]);
+        assert_eq!(table.dont_care, [0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_text_errors() {
+        let error = |text| TruthTable::parse(text).unwrap_err().to_string();
+        assert_eq!(error(""), "line 1: missing header");
+        assert_eq!(
+            error("a b\n"),
+            "line 1: header needs a | between inputs and outputs"
+        );
+        assert_eq!(
+            error("a b | y\n\n0 | 1"),
+            "line 3: expected 2 inputs, found 1"
+        );
+        assert_eq!(error("a | y\n2 | 1"), "line 2: unexpected '2' in inputs");
+        assert_eq!(
+            error("a b | y\n0 X | 1\n0 1 | 0"),
+            "line 3: conflicts with line 2"
+        );
+
+        let outputs: Vec<String> = (0..=MAX_TABLE_OUTPUTS).map(|i| format!("y{}", i)).collect();
+        assert_eq!(
+            error(&format!("a | {}", outputs.join(" "))),
+            format!("line 1: more than {} outputs", MAX_TABLE_OUTPUTS)
+        );
+    }
+
+    #[test]
+    fn test_expr() {
+        let table = TruthTable::from_expr("!a & (b | c)").unwrap();
+        assert_eq!(table.inputs, ["a", "b", "c"]);
+        assert_eq!(table.outputs, ["out"]);
+
+        let mut graph = Graph::new();
+        let part = LookupTable::from_expr(&mut graph, "expr", "!a & (b | c)").unwrap();
+        check(&mut graph, &part, |i| {
+            let (a, b, c) = (i & 1, (i >> 1) & 1, (i >> 2) & 1);
+            (1 - a) & (b | c)
+        });
+
+        // Precedence from lowest to highest is | ^ & !
+        let xor = TruthTable::from_expr("x_1 ^ y & 1 | 0").unwrap();
+        assert_eq!(xor.inputs, ["x_1", "y"]);
+        assert_eq!(xor.values, [0, 1, 1, 0]);
+        assert_eq!(TruthTable::from_expr("!!a").unwrap().values, [0, 1]);
+
+        let error = |expr| TruthTable::from_expr(expr).unwrap_err().to_string();
+        assert_eq!(error("a & (b"), "line 1: missing ')'");
+        assert_eq!(error("a &"), "line 1: unexpected end of expression");
+        assert_eq!(error("a b"), "line 1: unexpected 'b'");
+        assert_eq!(error("a + b"), "line 1: unexpected '+' at column 3");
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut graph = Graph::new();
+        let part = LookupTable::from_expr(&mut graph, "expr", "a & b | c").unwrap();
+        let table = vec![BusValue::new_val(1), BusValue::new_error(1)];
+        let errors = LookupTable::new(&mut graph, "errors", 1, 1, table);
+        let mut a = graph.new_output("a", Signal::Low);
+        let mut b = graph.new_output("b", Signal::Low);
+        graph.connect(&a, &part.input()[0]);
+        graph.connect_all(&[&b, &part.input()[1], &errors.input()[0]]);
+        graph.run();
+
+        // c is Off, which counts as Low
+        assert_eq!(part.output()[0].sig(), Signal::Low);
+        assert_eq!(errors.output()[0].sig(), Signal::High);
+
+        // An Error on a only matters when b is High
+        a.set_output(Signal::Error);
+        graph.run();
+        assert_eq!(part.output()[0].sig(), Signal::Low);
+        b.set_output(Signal::High);
+        graph.run();
+        assert_eq!(part.output()[0].sig(), Signal::Error);
+
+        // Error entries in the table come out as Error
+        assert_eq!(errors.output()[0].sig(), Signal::Error);
+    }
+}
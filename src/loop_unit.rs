@@ -0,0 +1,697 @@
+use crate::*;
+
+use std::iter::zip;
+
+/// Inputs shared by all loop units
+pub struct LoopInputs {
+    count: Pin,
+    store: Pin,
+    reset: Pin,
+    loop_start: Pin,
+    loop_end: Pin,
+    halt: Pin,
+    zero: Pin,
+    pc: Vec<Pin>,
+}
+
+impl LoopInputs {
+    /// Count phase clock input
+    pub fn count(&self) -> &Pin {
+        &self.count
+    }
+
+    /// Store phase clock input
+    pub fn store(&self) -> &Pin {
+        &self.store
+    }
+
+    /// Reset input
+    pub fn reset(&self) -> &Pin {
+        &self.reset
+    }
+
+    /// High while the current instruction is `[`
+    pub fn loop_start(&self) -> &Pin {
+        &self.loop_start
+    }
+
+    /// High while the current instruction is `]`
+    pub fn loop_end(&self) -> &Pin {
+        &self.loop_end
+    }
+
+    /// High while the current instruction is `Halt`
+    pub fn halt(&self) -> &Pin {
+        &self.halt
+    }
+
+    /// High while the current cell is zero
+    pub fn zero(&self) -> &Pin {
+        &self.zero
+    }
+
+    /// Program counter input. Holds the address after the current instruction
+    pub fn pc(&self) -> &[Pin] {
+        &self.pc
+    }
+
+    fn new(graph: &mut Graph, name: &str) -> Self {
+        let make_name = |n: &str| format!("{}.{}", name, n);
+        Self {
+            count: graph.new_input(&make_name("count")),
+            store: graph.new_input(&make_name("store")),
+            reset: graph.new_input(&make_name("reset")),
+            loop_start: graph.new_input(&make_name("loop_start")),
+            loop_end: graph.new_input(&make_name("loop_end")),
+            halt: graph.new_input(&make_name("halt")),
+            zero: graph.new_input(&make_name("zero")),
+            pc: graph.new_pins(&make_name("pc"), &[PinState::INPUT; 16]),
+        }
+    }
+}
+
+/// Outputs shared by all loop units. Each one drives the `ProgramBlock` input of the same name
+pub struct LoopOutputs {
+    skip: Pin,
+    reverse: Pin,
+    step_up: Pin,
+    step_down: Pin,
+    load_inv: Pin,
+    target: Vec<Pin>,
+}
+
+impl LoopOutputs {
+    /// High while instructions should be fetched without executing them
+    pub fn skip(&self) -> &Pin {
+        &self.skip
+    }
+
+    /// High while the program counter should count down after each fetch
+    pub fn reverse(&self) -> &Pin {
+        &self.reverse
+    }
+
+    /// Pulses to step the program counter up outside of a fetch
+    pub fn step_up(&self) -> &Pin {
+        &self.step_up
+    }
+
+    /// Pulses to step the program counter down outside of a fetch
+    pub fn step_down(&self) -> &Pin {
+        &self.step_down
+    }
+
+    /// Inverted load signal for jumping the program counter to `target`
+    pub fn load_inv(&self) -> &Pin {
+        &self.load_inv
+    }
+
+    /// Jump target for the program counter
+    pub fn target(&self) -> &[Pin] {
+        &self.target
+    }
+}
+
+/// Circuitry that implements `[` and `]` by steering the program counter
+///
+/// All loop units share the same inputs and outputs, so a bfpu can be built with any of them.
+/// Jumps are taken during the store phase of the `[` or `]`, and the program counter must hold
+/// the address of the next instruction when the following fetch starts.
+pub trait LoopUnit {
+    /// The input pins
+    fn inputs(&self) -> &LoopInputs;
+
+    /// The output pins
+    fn outputs(&self) -> &LoopOutputs;
+
+    /// Connects the loop unit to the control and program blocks, and to the zero flag
+    ///
+    /// `reset` is left for the caller to connect
+    fn connect(
+        &self,
+        graph: &mut Graph,
+        control: &ControlBlock,
+        program: &ProgramBlock,
+        zero: &Pin,
+    ) {
+        let inputs = self.inputs();
+        let outputs = self.outputs();
+        graph.connect_pairs(&[
+            (control.count(), inputs.count()),
+            (control.store(), inputs.store()),
+            (control.decoded(Opcode::LoopStart), inputs.loop_start()),
+            (control.decoded(Opcode::LoopEnd), inputs.loop_end()),
+            (control.decoded(Opcode::Halt), inputs.halt()),
+            (zero, inputs.zero()),
+            (outputs.skip(), control.skip()),
+            (outputs.reverse(), program.reverse()),
+            (outputs.step_up(), program.step_up()),
+            (outputs.step_down(), program.step_down()),
+            (outputs.load_inv(), program.load_inv()),
+        ]);
+        for (pc, input) in zip(program.pc(), inputs.pc()) {
+            graph.connect(pc, input);
+        }
+        for (output, target) in zip(outputs.target(), program.target()) {
+            graph.connect(output, target);
+        }
+    }
+}
+
+/// The looping mechanisms a bfpu can be built with
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoopKind {
+    /// See `LinearLoop`
+    Linear,
+
+    /// See `PreprocessedLoop`
+    Preprocessed,
+
+    /// See `AmortizedLoop`
+    Amortized,
+}
+
+impl LoopKind {
+    /// All loop kinds
+    pub const ALL: [LoopKind; 3] = [
+        LoopKind::Linear,
+        LoopKind::Preprocessed,
+        LoopKind::Amortized,
+    ];
+
+    /// Creates a loop unit of this kind
+    pub fn new_unit(self, graph: &mut Graph, name: &str) -> Box<dyn LoopUnit> {
+        match self {
+            LoopKind::Linear => Box::new(LinearLoop::new(graph, name)),
+            LoopKind::Preprocessed => Box::new(PreprocessedLoop::new(graph, name)),
+            LoopKind::Amortized => Box::new(AmortizedLoop::new(graph, name)),
+        }
+    }
+}
+
+/// Creates an output pin that is always Low or always High
+fn constant(graph: &mut Graph, name: &str, signal: Signal) -> Pin {
+    graph.new_output(&format!("{}.{:?}", name, signal), signal)
+}
+
+/// Outputs high while any of the counter outputs are high
+fn non_zero(graph: &mut Graph, name: &str, counter: &Counter8Bit) -> Pin {
+    let gate = or_nary(graph, name, 8);
+    gate.connect_inputs(&counter.output());
+    gate.output().clone()
+}
+
+/// Steps over instructions one at a time until reaching the matching bracket
+///
+/// A depth counter tracks the nesting level while skipping. Backward jumps count the program
+/// counter down after each fetch, with two extra steps at each end to get past the brackets.
+///
+/// Takes 3 cycles for every instruction between the brackets, every time the jump is taken. Loops
+/// can be nested up to 255 deep.
+pub struct LinearLoop {
+    inputs: LoopInputs,
+    outputs: LoopOutputs,
+    depth: Counter8Bit,
+    backward: TFlipFlop,
+}
+
+impl LoopUnit for LinearLoop {
+    fn inputs(&self) -> &LoopInputs {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &LoopOutputs {
+        &self.outputs
+    }
+}
+
+impl LinearLoop {
+    /// The nesting level while skipping, 0 otherwise
+    pub fn depth(&self) -> [&Pin; 8] {
+        self.depth.output()
+    }
+
+    /// High while skipping backwards
+    pub fn backward(&self) -> &Pin {
+        self.backward.output()
+    }
+
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let make_name = |n: &str| format!("{}.{}", name, n);
+        let inputs = LoopInputs::new(graph, name);
+        let (count, store) = (inputs.count(), inputs.store());
+        let (ls, le, zero) = (inputs.loop_start(), inputs.loop_end(), inputs.zero());
+
+        let low = constant(graph, name, Signal::Low);
+        let high = constant(graph, name, Signal::High);
+
+        let depth = Counter8Bit::new(graph, &make_name("depth"));
+        graph.connect(inputs.reset(), depth.clear());
+        graph.connect(&high, depth.load_inv());
+        let skipping = non_zero(graph, &make_name("skipping"), &depth);
+        let idle = !&skipping;
+
+        // `[` on a zero cell starts skipping forwards, `]` on a non-zero cell backwards
+        let start_forward = &(&idle & ls) & zero;
+        let start_backward = &(&idle & le) & &!zero;
+
+        let backward = TFlipFlop::new(graph, &make_name("backward"));
+        graph.connect(&low, backward.toggle());
+        let set_backward = store & &start_backward;
+        graph.connect(&set_backward, backward.set());
+        let reset_backward = &(store & &start_forward) | inputs.reset();
+        graph.connect(&reset_backward, backward.reset());
+        let forward = backward.out_inv();
+
+        // Brackets facing the direction of travel nest deeper, the others unwind
+        let nest = &skipping & &(&(forward & ls) | &(backward.output() & le));
+        let unnest = &skipping & &(&(forward & le) | &(backward.output() & ls));
+
+        let depth_up = nand_gate(graph, &make_name("depth_up"));
+        let depth_down = nand_gate(graph, &make_name("depth_down"));
+        let start = &(&start_forward | &start_backward) | &nest;
+        graph.connect_all(&[store, depth_up.input_a(), depth_down.input_a()]);
+        graph.connect(&start, depth_up.input_b());
+        graph.connect(&unnest, depth_down.input_b());
+        graph.connect(depth_up.output(), depth.up());
+        graph.connect(depth_down.output(), depth.down());
+
+        let upper = or_nary(graph, &make_name("upper"), 7);
+        upper.connect_inputs(&depth.output()[1..]);
+        let depth_one = depth.output()[0] & &!upper.output();
+        let end_backward = &(backward.output() & &unnest) & &depth_one;
+
+        // The program counter is past the `]` when starting backwards, so step back over it and
+        // fetch the instruction before. At the `[` it has already gone past, so step forward over
+        // the `[` to the first instruction of the loop
+        let count_or_store = count | store;
+        let step_down = &count_or_store & &start_backward;
+        let step_up = &count_or_store & &end_backward;
+        let reverse = &skipping & backward.output();
+
+        let target = graph.new_pins(&make_name("target"), &[PinState::Output(Signal::Low); 16]);
+
+        Self {
+            inputs,
+            outputs: LoopOutputs {
+                skip: skipping,
+                reverse,
+                step_up,
+                step_down,
+                load_inv: high,
+                target,
+            },
+            depth,
+            backward,
+        }
+    }
+}
+
+/// Jump table indexed by program counter, and the stack of `[`s used to fill it
+///
+/// Entries are keyed by the program counter while executing a bracket, i.e. the bracket's address
+/// plus one. Each `[` entry holds the address after its `]` and vice versa, so a jump is just a
+/// load of the program counter.
+struct JumpCache {
+    table: Ram,
+}
+
+impl JumpCache {
+    /// Creates the cache for the given loop unit inputs
+    ///
+    /// During the count phase `push` increments the stack pointer, and `pop` writes the table
+    /// entries for the top of the stack and the current `]`. During the store phase, `push`
+    /// writes the program counter to the stack and `pop` decrements the stack pointer.
+    /// `write_table` and `write_stack` are the actual write enables, in case they need to be
+    /// active at other times too.
+    fn new(
+        graph: &mut Graph,
+        name: &str,
+        inputs: &LoopInputs,
+        push: &Pin,
+        pop: &Pin,
+        write_table: &Pin,
+        write_stack: &Pin,
+    ) -> Self {
+        let make_name = |n: &str| format!("{}.{}", name, n);
+        let (count, store) = (inputs.count(), inputs.store());
+
+        let low = constant(graph, name, Signal::Low);
+        let high = constant(graph, name, Signal::High);
+
+        let sp = Counter8Bit::new(graph, &make_name("sp"));
+        graph.connect(inputs.reset(), sp.clear());
+        graph.connect(&high, sp.load_inv());
+        let sp_up = nand_gate(graph, &make_name("sp_up"));
+        graph.connect(count, sp_up.input_a());
+        graph.connect(push, sp_up.input_b());
+        graph.connect(sp_up.output(), sp.up());
+        let sp_down = nand_gate(graph, &make_name("sp_down"));
+        graph.connect(store, sp_down.input_a());
+        graph.connect(pop, sp_down.input_b());
+        graph.connect(sp_down.output(), sp.down());
+
+        // The stack holds the program counter of each `[` that hasn't been matched yet
+        let stack = Ram::new(graph, &make_name("stack"), RamConfig::new(16, 8));
+        let stack_port = stack.port(0);
+        for (sp_pin, addr) in zip(sp.output(), stack_port.addr()) {
+            graph.connect(sp_pin, addr);
+        }
+        let push_bus = BusTristate::new(graph, &make_name("push_bus"), 16);
+        for ((pc, input), (output, io)) in zip(
+            zip(inputs.pc(), push_bus.input()),
+            zip(push_bus.output(), stack_port.io()),
+        ) {
+            graph.connect(pc, input);
+            graph.connect(output, io);
+        }
+        let write_stack_inv = !write_stack;
+        graph.connect_all(&[write_stack, push_bus.en(), stack_port.oe_inv()]);
+        graph.connect(&write_stack_inv, stack_port.we_inv());
+        low.connect_all(&[stack_port.ce_inv(), stack_port.clk()]);
+
+        // Port 0 reads and writes the entry for the current program counter. Its output is
+        // registered at the start of the store phase, so loading the program counter from it
+        // doesn't change it. Port 1 writes the entry for the `[` on top of the stack
+        let table = Ram::new(
+            graph,
+            &make_name("table"),
+            RamConfig::new(16, 15)
+                .ports(RamPorts::TrueDual)
+                .registered_output(true),
+        );
+        let (current, matching) = (table.port(0), table.port(1));
+        for ((pc, current_addr), (top, matching_addr)) in zip(
+            zip(inputs.pc(), current.addr()),
+            zip(stack_port.io(), matching.addr()),
+        ) {
+            graph.connect(pc, current_addr);
+            graph.connect(top, matching_addr);
+        }
+        let pop_bus = BusTristate::new(graph, &make_name("pop_bus"), 16);
+        for (((pc, matching_io), top), (input, (output, current_io))) in zip(
+            zip(zip(inputs.pc(), matching.io()), stack_port.io()),
+            zip(pop_bus.input(), zip(pop_bus.output(), current.io())),
+        ) {
+            graph.connect(pc, matching_io);
+            graph.connect(top, input);
+            graph.connect(output, current_io);
+        }
+        let write_table_inv = !write_table;
+        graph.connect_all(&[write_table, pop_bus.en(), current.oe_inv()]);
+        graph.connect_all(&[&write_table_inv, current.we_inv(), matching.we_inv()]);
+        graph.connect(store, current.clk());
+        low.connect_all(&[current.ce_inv(), matching.ce_inv(), matching.clk()]);
+        graph.connect(&high, matching.oe_inv());
+
+        Self { table }
+    }
+
+    /// The jump target for the current program counter
+    fn target(&self) -> Vec<Pin> {
+        self.table.port(0).io().to_vec()
+    }
+}
+
+/// Fills a jump table with every loop's target after reset, then jumps in constant time
+///
+/// After reset, the whole program is fetched with `skip` high to fill the table, ending with a
+/// jump back to address 0 when `Halt` is reached. This takes 3 cycles per instruction once.
+/// Loops can be nested up to 255 deep.
+pub struct PreprocessedLoop {
+    inputs: LoopInputs,
+    outputs: LoopOutputs,
+    busy: TFlipFlop,
+    cache: JumpCache,
+}
+
+impl LoopUnit for PreprocessedLoop {
+    fn inputs(&self) -> &LoopInputs {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &LoopOutputs {
+        &self.outputs
+    }
+}
+
+impl PreprocessedLoop {
+    /// High while filling the jump table
+    pub fn busy(&self) -> &Pin {
+        self.busy.output()
+    }
+
+    /// The jump table, indexed by the program counter after fetching a bracket
+    pub fn table(&self) -> &Memory {
+        self.cache.table.memory()
+    }
+
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let make_name = |n: &str| format!("{}.{}", name, n);
+        let inputs = LoopInputs::new(graph, name);
+        let (count, store) = (inputs.count(), inputs.store());
+        let (ls, le, zero) = (inputs.loop_start(), inputs.loop_end(), inputs.zero());
+
+        let low = constant(graph, name, Signal::Low);
+
+        // Busy from reset until the store phase of the first `Halt`
+        let busy = TFlipFlop::new(graph, &make_name("busy"));
+        graph.connect(inputs.reset(), busy.set());
+        graph.connect(&low, busy.reset());
+        let finish = busy.output() & inputs.halt();
+        let busy_toggle = nand_gate(graph, &make_name("busy_toggle"));
+        graph.connect(store, busy_toggle.input_a());
+        graph.connect(&finish, busy_toggle.input_b());
+        graph.connect(busy_toggle.output(), busy.toggle());
+
+        // `Halt` reads the 0 from the bottom of the stack the same way `]` reads its `[`. The 0 is
+        // written during reset while the program counter is cleared
+        let push = busy.output() & ls;
+        let pop = busy.output() & le;
+        let write_table = count & &(&pop | &finish);
+        let write_stack = &(store & &push) | inputs.reset();
+        let cache = JumpCache::new(
+            graph,
+            &make_name("cache"),
+            &inputs,
+            &push,
+            &pop,
+            &write_table,
+            &write_stack,
+        );
+
+        let jump = busy.out_inv() & &(&(ls & zero) | &(le & &!zero));
+        let load_inv = nand_gate(graph, &make_name("load_inv"));
+        graph.connect(store, load_inv.input_a());
+        let load = &jump | &finish;
+        graph.connect(&load, load_inv.input_b());
+
+        let target = cache.target();
+        Self {
+            outputs: LoopOutputs {
+                skip: busy.output().clone(),
+                reverse: low.clone(),
+                step_up: low.clone(),
+                step_down: low,
+                load_inv: load_inv.output().clone(),
+                target,
+            },
+            inputs,
+            busy,
+            cache,
+        }
+    }
+}
+
+/// Fills a jump table as the program runs, and jumps in constant time to targets it has seen
+///
+/// A counter tracks the next program counter that hasn't been visited. Brackets are added to the
+/// table the first time they're visited. A `[` on a zero cell that hasn't been visited skips
+/// forward linearly, filling the table along the way. Every other jump has a known target, since
+/// a `]` can only be reached after its `[`. Loops can be nested up to 255 deep.
+pub struct AmortizedLoop {
+    inputs: LoopInputs,
+    outputs: LoopOutputs,
+    next: Counter16Bit,
+    depth: Counter8Bit,
+    cache: JumpCache,
+}
+
+impl LoopUnit for AmortizedLoop {
+    fn inputs(&self) -> &LoopInputs {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &LoopOutputs {
+        &self.outputs
+    }
+}
+
+impl AmortizedLoop {
+    /// The program counter of the first instruction that hasn't been visited
+    pub fn next(&self) -> [&Pin; 16] {
+        self.next.output()
+    }
+
+    /// The nesting level while skipping, 0 otherwise
+    pub fn depth(&self) -> [&Pin; 8] {
+        self.depth.output()
+    }
+
+    /// The jump table, indexed by the program counter after fetching a bracket
+    pub fn table(&self) -> &Memory {
+        self.cache.table.memory()
+    }
+
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let make_name = |n: &str| format!("{}.{}", name, n);
+        let inputs = LoopInputs::new(graph, name);
+        let (count, store) = (inputs.count(), inputs.store());
+        let (ls, le, zero) = (inputs.loop_start(), inputs.loop_end(), inputs.zero());
+
+        let low = constant(graph, name, Signal::Low);
+        let high = constant(graph, name, Signal::High);
+
+        // The program counter is 1 after fetching the first instruction, so start there
+        let next = Counter16Bit::new(graph, &make_name("next"));
+        let [first, rest @ ..] = next.input();
+        graph.connect(&high, first);
+        for input in rest {
+            graph.connect(&low, input);
+        }
+        let reset_inv = !inputs.reset();
+        graph.connect(&reset_inv, next.load_inv());
+        graph.connect(&low, next.clear());
+        graph.connect(&high, next.down());
+
+        let differences = nor_nary(graph, &make_name("new"), 16);
+        for (i, (pc, next_pc)) in zip(inputs.pc(), next.output()).enumerate() {
+            let difference = pc ^ next_pc;
+            graph.connect(&difference, differences.input_n(i));
+        }
+        let new = differences.output().clone();
+        let next_up = nand_gate(graph, &make_name("next_up"));
+        graph.connect(store, next_up.input_a());
+        graph.connect(&new, next_up.input_b());
+        graph.connect(next_up.output(), next.up());
+
+        let push = &new & ls;
+        let pop = &new & le;
+        let write_table = count & &pop;
+        let write_stack = store & &push;
+        let cache = JumpCache::new(
+            graph,
+            &make_name("cache"),
+            &inputs,
+            &push,
+            &pop,
+            &write_table,
+            &write_stack,
+        );
+
+        // Only a new `[` on a zero cell has no target yet, so skip forward to it
+        let depth = Counter8Bit::new(graph, &make_name("depth"));
+        graph.connect(inputs.reset(), depth.clear());
+        graph.connect(&high, depth.load_inv());
+        let skipping = non_zero(graph, &make_name("skipping"), &depth);
+        let idle = !&skipping;
+        let start = &(&(&idle & &new) & ls) & zero;
+        let nest = &start | &(&skipping & ls);
+        let unnest = &skipping & le;
+
+        let depth_up = nand_gate(graph, &make_name("depth_up"));
+        let depth_down = nand_gate(graph, &make_name("depth_down"));
+        graph.connect_all(&[store, depth_up.input_a(), depth_down.input_a()]);
+        graph.connect(&nest, depth_up.input_b());
+        graph.connect(&unnest, depth_down.input_b());
+        graph.connect(depth_up.output(), depth.up());
+        graph.connect(depth_down.output(), depth.down());
+
+        let revisit = !&new;
+        let known = &(&(ls & zero) & &revisit) | &(le & &!zero);
+        let jump = &idle & &known;
+        let load_inv = nand_gate(graph, &make_name("load_inv"));
+        graph.connect(store, load_inv.input_a());
+        graph.connect(&jump, load_inv.input_b());
+
+        let target = cache.target();
+        Self {
+            outputs: LoopOutputs {
+                skip: skipping,
+                reverse: low.clone(),
+                step_up: low.clone(),
+                step_down: low,
+                load_inv: load_inv.output().clone(),
+                target,
+            },
+            inputs,
+            next,
+            depth,
+            cache,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_loop_unit {
+    use crate::*;
+
+    /// Runs a program and returns the first few cells of the tape and the number of cycles
+    fn run(loop_kind: LoopKind, source: &str) -> (Vec<usize>, usize) {
+        let mut graph = Graph::new();
+        let mut bfpu = Bfpu::new(&mut graph, "bfpu", loop_kind, IoHost::new([]));
+        let program = bfpu.program().program();
+        OpcodeEncoding::default().load(program, source).unwrap();
+        bfpu.data().tape().fill(0);
+
+        bfpu.quick_reset(&mut graph);
+        let start = bfpu.data().addr().iter().val().val;
+        while bfpu.step(&mut graph) == Step::Executed {}
+
+        let tape = bfpu.data().tape();
+        let cells = (0..4)
+            .map(|i| tape.peek((start + i) % IcCY7C199::NUM_WORDS).unwrap())
+            .collect();
+        (cells, bfpu.cycles())
+    }
+
+    /// Nested loops, with one to skip on entry
+    const SOURCE: &str = "[>]++[>+[>+<-]<-]";
+
+    #[test]
+    fn test_linear() {
+        assert_eq!(run(LoopKind::Linear, SOURCE).0, [0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_preprocessed() {
+        assert_eq!(run(LoopKind::Preprocessed, SOURCE).0, [0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_amortized() {
+        assert_eq!(run(LoopKind::Amortized, SOURCE).0, [0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_cycles() {
+        let [linear, preprocessed, amortized] = [
+            LoopKind::Linear,
+            LoopKind::Preprocessed,
+            LoopKind::Amortized,
+        ]
+        .map(|kind| run(kind, SOURCE).1);
+        assert!(
+            amortized < linear,
+            "Amortized: {} cycles, Linear: {} cycles",
+            amortized,
+            linear
+        );
+        assert!(
+            amortized < preprocessed,
+            "Amortized: {} cycles, Preprocessed: {} cycles",
+            amortized,
+            preprocessed
+        );
+    }
+}
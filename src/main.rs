@@ -1,185 +1,165 @@
 use befrust::*;
-use std::io::Write;
 
-fn main() {
-    #![allow(unused_assignments, unused_mut)]
+use std::path::PathBuf;
+use std::process::exit;
+use std::time::Instant;
 
-    let mut graph = Graph::new();
+const USAGE: &str = "usage: befrust [run [--loop linear|preprocessed|amortized] \
+[--eof zero|minus-one|unchanged] <program> | timing [--loop linear|preprocessed|amortized]]";
+
+/// Options for `befrust run`
+struct RunOptions {
+    path: PathBuf,
+    loop_kind: LoopKind,
+    eof: EofBehavior,
+}
 
-    // Create signals to send as inputs to the data block
-    //
-    // these will be outputs of the control block eventually
-
-    // Increment direction
-    let mut down = graph.new_output("down", Signal::Low);
-    let up = !&down;
-
-    // clock phases
-    //
-    // TODO:Instruction - Latches the next instruction and increments program counter
-    //
-    // Count - increments any enabled counters (pointers, registers, etc.)
-    // Store - commits any changes (e.g. writing ram after data increments)
-    // Clear - only active during reset, used to step through RAM addresses
-    let mut count = graph.new_output("count", Signal::Low);
-    let mut store = graph.new_output("store", Signal::Low);
-    let mut clear = graph.new_output("clear", Signal::Low);
-
-    // Enable lines
-    let mut p_ce = graph.new_output("p_ce", Signal::Low);
-    let mut d_ce = graph.new_output("d_ce", Signal::Low);
-
-    let mut reset = graph.new_output("reset", Signal::High);
-
-    let d_block = DataBlock::new(&mut graph, "data");
-
-    // Connect our manual signals to the data block
-    graph.connect_pairs(&[
-        (&up, d_block.up()),
-        (&down, d_block.down()),
-        (&count, d_block.count()),
-        (&store, d_block.store()),
-        (&p_ce, d_block.p_ce()),
-        (&d_ce, d_block.d_ce()),
-        (&reset, d_block.reset()),
-        (&clear, d_block.clear()),
-    ]);
-
-    // The zero flag constantly reads from the bus for use in control signals
-    let zero = nor_nary(&mut graph, "zero", d_block.data().len());
-
-    let print_debug =
-        |m: &str| println!("{}: {:?}, z:{:?}", m, d_block, zero.output().state().val());
-
-    print_debug("connected graph");
-
-    ////// Use the graph
-
-    dbg!(graph.run());
-    print_debug("first_run");
-
-    // TODO: need to optimize so we can actually clear all of RAM
-    //
-    // For now ust do a couple clears to verify that the circuitry is working
-    const NUM_CLEARS: i32 = 5; // should be at least the size of ram (1 << 15)
-
-    // This is a cheat for now. IRL the clear clock would always be running but ignored while reset
-    // is off
-    println!("Clearing RAM");
-    //println!("This can take a while on debug builds");
-    for i in 0..NUM_CLEARS {
-        graph.pulse_output(&mut clear);
-        if i % 1000 == 0 {
-            print!("{}% ", ((i as f32 / (1 << 15) as f32) * 100.0) as i32);
-            std::io::stdout().flush().unwrap();
+impl RunOptions {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut path = None;
+        let mut loop_kind = LoopKind::Linear;
+        let mut eof = EofBehavior::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+            match arg.as_str() {
+                "--loop" => {
+                    loop_kind = match value()?.as_str() {
+                        "linear" => LoopKind::Linear,
+                        "preprocessed" => LoopKind::Preprocessed,
+                        "amortized" => LoopKind::Amortized,
+                        other => return Err(format!("unknown loop kind: {}", other)),
+                    }
+                }
+                "--eof" => {
+                    eof = match value()?.as_str() {
+                        "zero" | "0" => EofBehavior::Zero,
+                        "minus-one" | "-1" => EofBehavior::MinusOne,
+                        "unchanged" => EofBehavior::Unchanged,
+                        other => return Err(format!("unknown eof behavior: {}", other)),
+                    }
+                }
+                _ if path.is_none() && !arg.starts_with("--") => path = Some(PathBuf::from(arg)),
+                _ => return Err(format!("unexpected argument: {}", arg)),
+            }
         }
+        Ok(Self {
+            path: path.ok_or("missing program")?,
+            loop_kind,
+            eof,
+        })
     }
-    println!("100%");
-
-    print_debug("end of loop");
-
-    clear.set_output(Signal::High);
-    dbg!(graph.run());
-    clear.set_output(Signal::Low);
-    dbg!(graph.run());
-
-    print_debug("end of clear");
-
-    // At this point, the address pointer is at some arbitrary location depending on how long the
-    // reset line is held (i.e. how many ticks of the clear count happened while reset was active).
-    // IRL the clear clock will be in the 2 MHz range which can clear all of RAM in a few dozen ms.
-
-    // TODO: maybe reset ram address to 0 at the end of reset, but isn't necessary since counters
-    // wrap. It would be useful for debugging to have programs always start at 0 though...
-
-    // Test data reg
-    reset.set_output(Signal::Low);
-    graph.run();
-
-    print_debug("end reset");
-
-    d_ce.set_output(Signal::High);
-    graph.run();
-    print_debug("d_ce high");
-
-    count.set_output(Signal::High);
-    graph.run();
-    print_debug("count high");
-
-    count.set_output(Signal::Low);
-    graph.run();
-    print_debug("count low");
-
-    graph.pulse_output(&mut count);
-    print_debug("count pulse");
-
-    store.set_output(Signal::High);
-    dbg!(graph.run());
-    print_debug("store high");
-
-    store.set_output(Signal::Low);
-    graph.run();
-    print_debug("store low");
-
-    // test data ptr
-    d_ce.set_output(Signal::Low);
-    graph.run();
-    print_debug("d_ce low");
-
-    p_ce.set_output(Signal::High);
-    graph.run();
-    print_debug("p_ce high");
-
-    graph.pulse_output(&mut count);
-
-    print_debug("ptr pulse 1");
-    count.set_output(Signal::High);
-    graph.run();
-    count.set_output(Signal::Low);
-    graph.run();
-    print_debug("ptr pulse 2");
-
-    down.set_output(Signal::High);
-
-    count.set_output(Signal::High);
-    graph.run();
-    count.set_output(Signal::Low);
-    graph.run();
-
-    graph.pulse_output(&mut count);
-    graph.pulse_output(&mut count);
-    graph.pulse_output(&mut count);
-
-    count.set_output(Signal::High);
-    graph.run();
-    count.set_output(Signal::Low);
-    graph.run();
-
-    print_debug("ptr down 5");
+}
 
-    down.set_output(Signal::Low);
-    graph.pulse_output(&mut count);
-    graph.pulse_output(&mut count);
-    graph.pulse_output(&mut count);
-    graph.pulse_output(&mut count);
-    graph.pulse_output(&mut store);
+/// Runs a program on the bfpu, streaming I/O through stdin and stdout, then prints statistics to
+/// stderr
+///
+/// Returns how the run ended, which is `Halted` unless the bfpu was left waiting for input
+fn run(options: RunOptions) -> Result<Step, ImageError> {
+    let mut graph = Graph::new();
+    let host = IoHost::stdio().with_eof(options.eof);
+    let mut bfpu = Bfpu::new(&mut graph, "bfpu", options.loop_kind, host);
+
+    let program = bfpu.program().program();
+    OpcodeEncoding::default().load_file(program, &options.path)?;
+
+    let start = Instant::now();
+    bfpu.reset(&mut graph);
+    // stdin blocks rather than leaving the bfpu waiting, so this should only return on halt
+    let step = bfpu.run(&mut graph);
+    let elapsed = start.elapsed();
+
+    let stats = bfpu.run_stats();
+    eprintln!();
+    eprintln!("instructions: {}", bfpu.instructions());
+    eprintln!("clock cycles: {}", bfpu.cycles());
+    eprintln!("graph ticks:  {}", stats.ticks);
+    eprintln!("node updates: {}", stats.updates);
+    eprintln!("final cycle:  {}", stats.cycle);
+    eprintln!("time:         {:.3?}", elapsed);
+    Ok(step)
+}
 
-    print_debug("ptr up 4");
+/// Builds the bfpu and prints its longest combinational path and any combinational loops
+fn timing(loop_kind: LoopKind) {
+    let mut graph = Graph::new();
+    let _bfpu = Bfpu::new(&mut graph, "bfpu", loop_kind, IoHost::new([]));
+    let report = graph.timing();
+    match report.critical {
+        Some(path) => println!("critical path: {}", path),
+        None => println!("no combinational paths"),
+    }
+    println!("combinational loops: {}", report.loops.len());
+    for pins in report.loops {
+        println!("    {}", pins.join(" -> "));
+    }
+}
 
-    p_ce.set_output(Signal::Low);
-    graph.run();
-    print_debug("pe low");
+/// Runs a small built-in program, printing the data block's front panel after each instruction
+fn demo() {
+    let mut graph = Graph::new();
 
-    d_ce.set_output(Signal::High);
+    let mut bfpu = Bfpu::new(&mut graph, "bfpu", LoopKind::Linear, IoHost::stdio());
+    let panel = DataPanel::new(&mut graph, "panel", bfpu.data());
+    let style = RenderStyle::default();
 
-    graph.run();
-    print_debug("p_ce/d_ce swap");
+    let encoding = OpcodeEncoding::default();
+    let program = bfpu.program().program();
+    encoding.load(program, "++[>+++<-]>").unwrap();
 
-    graph.pulse_output(&mut count);
+    bfpu.reset(&mut graph);
+    println!("end reset:\n{}\n", panel.render(style));
 
-    print_debug("data count up");
+    // Run the program one instruction -> count -> store cycle at a time until it halts
+    let mut steps = 0;
+    while bfpu.step(&mut graph) == Step::Executed {
+        steps += 1;
+        println!("step {}:\n{}\n", steps, panel.render(style));
+    }
 
-    graph.pulse_output(&mut store);
+    println!(
+        "halt after {} cycles:\n{}",
+        bfpu.cycles(),
+        panel.render(style)
+    );
+}
 
-    print_debug("data store");
+fn main() {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        None => demo(),
+        Some("run") => {
+            let options = RunOptions::parse(args).unwrap_or_else(|err| {
+                eprintln!("{}\n{}", err, USAGE);
+                exit(2);
+            });
+            match run(options) {
+                Ok(Step::Halted) => {}
+                Ok(step) => {
+                    eprintln!("error: the program stopped without halting ({:?})", step);
+                    exit(1);
+                }
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    exit(1);
+                }
+            }
+        }
+        Some("timing") => {
+            let loop_kind = match (args.next().as_deref(), args.next().as_deref()) {
+                (None, _) => LoopKind::Linear,
+                (Some("--loop"), Some("linear")) => LoopKind::Linear,
+                (Some("--loop"), Some("preprocessed")) => LoopKind::Preprocessed,
+                (Some("--loop"), Some("amortized")) => LoopKind::Amortized,
+                _ => {
+                    eprintln!("{}", USAGE);
+                    exit(2);
+                }
+            };
+            timing(loop_kind);
+        }
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    }
 }
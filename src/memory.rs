@@ -0,0 +1,557 @@
+use crate::*;
+use std::cell::{Ref, RefCell, RefMut};
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+
+/// File formats for loading and saving memory images
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Raw bytes, one or more per word (little-endian), starting at address 0
+    Binary,
+
+    /// Intel HEX records. Addresses are byte addresses, so wide words span several bytes
+    IntelHex,
+
+    /// Logisim's "v2.0 raw" text format: whitespace-separated hex words, with `N*word` runs
+    LogisimRaw,
+}
+
+impl ImageFormat {
+    /// Guesses the format from a file extension
+    ///
+    /// `.hex`/`.ihex` are Intel HEX, `.raw`/`.logisim` are Logisim images, anything else is binary
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("hex" | "ihex" | "ihx") => ImageFormat::IntelHex,
+            Some("raw" | "logisim") => ImageFormat::LogisimRaw,
+            _ => ImageFormat::Binary,
+        }
+    }
+}
+
+/// Problems encountered while reading or writing a memory image
+#[derive(Debug)]
+pub enum ImageError {
+    /// The file could not be read or written
+    Io(std::io::Error),
+
+    /// The image is malformed. Lines are numbered from 1
+    Parse { line: usize, message: String },
+
+    /// The image has data beyond the end of the memory
+    OutOfRange { addr: usize },
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::Io(err) => write!(f, "{}", err),
+            ImageError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            ImageError::OutOfRange { addr } => write!(f, "address {:#x} is out of range", addr),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<std::io::Error> for ImageError {
+    fn from(err: std::io::Error) -> Self {
+        ImageError::Io(err)
+    }
+}
+
+/// The contents of a memory part
+#[derive(Debug)]
+struct MemoryImpl {
+    words: Vec<BusValue>,
+    word_size: usize,
+}
+
+/// A shared handle to the words stored in a memory part
+///
+/// The part's updater and the host hold clones of the same handle, so the contents can be examined
+/// or changed between runs of the graph
+#[derive(Clone, Debug)]
+pub struct Memory(Rc<RefCell<MemoryImpl>>);
+
+impl Memory {
+    /// Creates a memory with `len` words of `word_size` bits, all set to `fill`
+    pub fn new(len: usize, word_size: usize, fill: usize) -> Self {
+        assert!(
+            word_size > 0 && (word_size as u32) <= usize::BITS,
+            "Invalid word size {}",
+            word_size
+        );
+        let fill = BusValue::new_val(fill & Self::mask_for(word_size));
+        Self(Rc::new(RefCell::new(MemoryImpl {
+            words: vec![fill; len],
+            word_size,
+        })))
+    }
+
+    fn mask_for(word_size: usize) -> usize {
+        usize::MAX >> (usize::BITS as usize - word_size)
+    }
+
+    /// Read-only access to the words, e.g. for part updaters
+    pub(crate) fn words(&self) -> Ref<'_, Vec<BusValue>> {
+        Ref::map(self.0.borrow(), |m| &m.words)
+    }
+
+    /// Mutable access to the words, e.g. for part updaters
+    pub(crate) fn words_mut(&self) -> RefMut<'_, Vec<BusValue>> {
+        RefMut::map(self.0.borrow_mut(), |m| &mut m.words)
+    }
+
+    /// Number of words
+    pub fn len(&self) -> usize {
+        self.0.borrow().words.len()
+    }
+
+    /// True if the memory holds no words
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of bits per word
+    pub fn word_size(&self) -> usize {
+        self.0.borrow().word_size
+    }
+
+    /// Bit mask covering a single word
+    pub fn mask(&self) -> usize {
+        Self::mask_for(self.word_size())
+    }
+
+    /// Number of bytes used to store each word in binary and Intel HEX images
+    pub fn bytes_per_word(&self) -> usize {
+        self.word_size().div_ceil(8)
+    }
+
+    /// Gets the word at the given address
+    ///
+    /// Panics if the address is out of range
+    pub fn peek(&self, addr: usize) -> BusValue {
+        self.0.borrow().words[addr]
+    }
+
+    /// Sets the word at the given address. Bits beyond the word size are dropped
+    ///
+    /// Panics if the address is out of range
+    pub fn poke(&self, addr: usize, val: usize) {
+        let mask = self.mask();
+        self.0.borrow_mut().words[addr] = BusValue::new_val(val & mask);
+    }
+
+    /// Copies out a range of words
+    ///
+    /// Panics if the range is out of bounds
+    pub fn dump(&self, range: Range<usize>) -> Vec<BusValue> {
+        self.0.borrow().words[range].to_vec()
+    }
+
+    /// Sets every word to the given value
+    pub fn fill(&self, val: usize) {
+        let val = BusValue::new_val(val & self.mask());
+        self.0.borrow_mut().words.fill(val);
+    }
+
+    /// Writes consecutive words starting at the given address
+    pub fn load_words(&self, start: usize, vals: &[usize]) -> Result<(), ImageError> {
+        if start + vals.len() > self.len() {
+            return Err(ImageError::OutOfRange {
+                addr: self.len().max(start),
+            });
+        }
+        for (i, val) in vals.iter().enumerate() {
+            self.poke(start + i, *val);
+        }
+        Ok(())
+    }
+
+    /// Loads an image into memory. Words not covered by the image are left alone
+    pub fn load(&self, image: &[u8], format: ImageFormat) -> Result<(), ImageError> {
+        match format {
+            ImageFormat::Binary => {
+                let bytes_per_word = self.bytes_per_word();
+                let words: Vec<usize> = image
+                    .chunks(bytes_per_word)
+                    .map(Self::from_le_bytes)
+                    .collect();
+                self.load_words(0, &words)
+            }
+            ImageFormat::IntelHex => self.load_intel_hex(image),
+            ImageFormat::LogisimRaw => self.load_logisim(image),
+        }
+    }
+
+    /// Saves the whole memory as an image
+    ///
+    /// Images have no way to represent `Error` bits, so they are saved as 0
+    pub fn save(&self, format: ImageFormat) -> Vec<u8> {
+        match format {
+            ImageFormat::Binary => {
+                let bytes_per_word = self.bytes_per_word();
+                self.words()
+                    .iter()
+                    .flat_map(|word| Self::to_le_bytes(Self::saved_val(word), bytes_per_word))
+                    .collect()
+            }
+            ImageFormat::IntelHex => self.save_intel_hex(),
+            ImageFormat::LogisimRaw => self.save_logisim(),
+        }
+    }
+
+    /// Loads an image from a file
+    pub fn load_file(&self, path: &Path, format: ImageFormat) -> Result<(), ImageError> {
+        let image = std::fs::read(path)?;
+        self.load(&image, format)
+    }
+
+    /// Saves the memory to an image file
+    pub fn save_file(&self, path: &Path, format: ImageFormat) -> Result<(), ImageError> {
+        std::fs::write(path, self.save(format))?;
+        Ok(())
+    }
+
+    fn saved_val(word: &BusValue) -> usize {
+        word.val & !word.error
+    }
+
+    fn from_le_bytes(bytes: &[u8]) -> usize {
+        bytes
+            .iter()
+            .rev()
+            .fold(0, |acc, byte| (acc << 8) | *byte as usize)
+    }
+
+    fn to_le_bytes(val: usize, len: usize) -> impl Iterator<Item = u8> {
+        (0..len).map(move |i| (val >> (8 * i)) as u8)
+    }
+
+    fn load_intel_hex(&self, image: &[u8]) -> Result<(), ImageError> {
+        let text = String::from_utf8_lossy(image);
+        let bytes_per_word = self.bytes_per_word();
+        let mut base = 0;
+        // Bytes are gathered per word so that wide words can be split across records
+        let mut pending: Vec<(usize, u8)> = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let parse_err = |message: &str| ImageError::Parse {
+                line: line_no,
+                message: message.to_owned(),
+            };
+
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let hex = line
+                .strip_prefix(':')
+                .ok_or_else(|| parse_err("record must start with ':'"))?;
+            if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(parse_err("invalid hex digit"));
+            }
+            if hex.len() % 2 != 0 || hex.len() < 10 {
+                return Err(parse_err("record has the wrong length"));
+            }
+            let bytes: Vec<u8> = (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect();
+
+            let count = bytes[0] as usize;
+            if bytes.len() != count + 5 {
+                return Err(parse_err("byte count doesn't match record length"));
+            }
+            let checksum = bytes.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+            if checksum != 0 {
+                return Err(parse_err("bad checksum"));
+            }
+
+            let offset = ((bytes[1] as usize) << 8) | bytes[2] as usize;
+            let data = &bytes[4..4 + count];
+            match bytes[3] {
+                0x00 => {
+                    for (j, byte) in data.iter().enumerate() {
+                        pending.push((base + offset + j, *byte));
+                    }
+                }
+                0x01 => break,
+                0x02 | 0x04 if count != 2 => {
+                    return Err(parse_err("address record has the wrong length"))
+                }
+                0x02 => base = Self::from_be_pair(data) << 4,
+                0x04 => base = Self::from_be_pair(data) << 16,
+                // Start address records don't affect the contents
+                0x03 | 0x05 => {}
+                _ => return Err(parse_err("unsupported record type")),
+            }
+        }
+
+        // Nothing is written unless the whole image fits
+        let len = self.len();
+        if let Some(addr) = pending
+            .iter()
+            .map(|(byte_addr, _)| byte_addr / bytes_per_word)
+            .find(|addr| *addr >= len)
+        {
+            return Err(ImageError::OutOfRange { addr });
+        }
+
+        let mask = self.mask();
+        let mut words = self.words_mut();
+        for (byte_addr, byte) in pending {
+            let addr = byte_addr / bytes_per_word;
+            let shift = 8 * (byte_addr % bytes_per_word);
+            let val = (words[addr].val & !(0xff << shift)) | ((byte as usize) << shift);
+            words[addr] = BusValue::new_val(val & mask);
+        }
+
+        Ok(())
+    }
+
+    fn from_be_pair(data: &[u8]) -> usize {
+        ((data[0] as usize) << 8) | data[1] as usize
+    }
+
+    fn save_intel_hex(&self) -> Vec<u8> {
+        const RECORD_LEN: usize = 16;
+
+        let record = |kind: u8, offset: usize, data: &[u8]| {
+            let mut bytes = vec![data.len() as u8, (offset >> 8) as u8, offset as u8, kind];
+            bytes.extend_from_slice(data);
+            let checksum = bytes.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+            bytes.push(checksum.wrapping_neg());
+            let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            format!(":{}\n", hex)
+        };
+
+        let image = self.save(ImageFormat::Binary);
+        let mut text = String::new();
+        let mut segment = 0;
+        for (i, chunk) in image.chunks(RECORD_LEN).enumerate() {
+            let addr = i * RECORD_LEN;
+            if addr >> 16 != segment {
+                segment = addr >> 16;
+                text += &record(0x04, 0, &[(segment >> 8) as u8, segment as u8]);
+            }
+            text += &record(0x00, addr & 0xffff, chunk);
+        }
+        text += &record(0x01, 0, &[]);
+
+        text.into_bytes()
+    }
+
+    fn load_logisim(&self, image: &[u8]) -> Result<(), ImageError> {
+        let text = String::from_utf8_lossy(image);
+        let mut lines = text.lines().enumerate();
+        let mut words = vec![];
+
+        match lines.next() {
+            Some((_, header)) if header.trim() == "v2.0 raw" => {}
+            _ => {
+                return Err(ImageError::Parse {
+                    line: 1,
+                    message: "missing \"v2.0 raw\" header".to_owned(),
+                })
+            }
+        }
+
+        for (i, line) in lines {
+            let parse_err = |message: String| ImageError::Parse {
+                line: i + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or_default();
+
+            for token in line.split_whitespace() {
+                let (count, word) = match token.split_once('*') {
+                    Some((count, word)) => (
+                        count
+                            .parse::<usize>()
+                            .map_err(|_| parse_err(format!("invalid count {:?}", count)))?,
+                        word,
+                    ),
+                    None => (1, token),
+                };
+                let word = usize::from_str_radix(word, 16)
+                    .map_err(|_| parse_err(format!("invalid word {:?}", word)))?;
+                words.extend(std::iter::repeat_n(word, count));
+            }
+        }
+
+        self.load_words(0, &words)
+    }
+
+    fn save_logisim(&self) -> Vec<u8> {
+        const WORDS_PER_LINE: usize = 8;
+        const MIN_RUN: usize = 4;
+
+        let mut tokens = vec![];
+        let words = self.words();
+        let mut i = 0;
+        while i < words.len() {
+            let word = Self::saved_val(&words[i]);
+            let run = words[i..]
+                .iter()
+                .take_while(|w| Self::saved_val(w) == word)
+                .count();
+            if run >= MIN_RUN {
+                tokens.push(format!("{}*{:x}", run, word));
+                i += run;
+            } else {
+                tokens.push(format!("{:x}", word));
+                i += 1;
+            }
+        }
+
+        let mut text = String::from("v2.0 raw\n");
+        for line in tokens.chunks(WORDS_PER_LINE) {
+            text += &line.join(" ");
+            text.push('\n');
+        }
+
+        text.into_bytes()
+    }
+}
+
+/// The pin interface shared by the memory chips
+///
+/// RAM and ROM chips use the same layout so they can be swapped on the same address and data bus
+pub trait MemoryChip {
+    /// Inverted chip enable
+    fn ce_inv(&self) -> &Pin;
+
+    /// Inverted output enable
+    fn oe_inv(&self) -> &Pin;
+
+    /// Inverted write enable
+    fn we_inv(&self) -> &Pin;
+
+    /// I/O pins
+    ///
+    /// Pins are set to HiZ if:
+    ///     `ce_inv` is High, or
+    ///     `oe_inv` and `we_inv` are both Low
+    ///
+    /// Otherwise, pins are set to output the contents of memory at the current address if
+    ///     `oe_inv` is Low
+    ///
+    /// Otherwise, pins are set to input and their value is written into memory if `we_inv` is Low
+    fn io(&self) -> &[Pin];
+
+    /// Address pins
+    fn addr(&self) -> &[Pin];
+
+    /// The contents of the chip
+    ///
+    /// Can be used to examine or pre-populate the memory between runs of the graph
+    fn memory(&self) -> &Memory;
+}
+
+#[cfg(test)]
+mod test_memory {
+    use crate::*;
+
+    #[test]
+    fn test_peek_poke() {
+        let mem = Memory::new(16, 8, 0xff);
+
+        assert_eq!(mem.peek(3), BusValue::new_val(0xff));
+
+        mem.poke(3, 0x142);
+        assert_eq!(mem.peek(3), BusValue::new_val(0x42));
+
+        mem.load_words(4, &[1, 2, 3]).unwrap();
+        assert_eq!(
+            mem.dump(2..8),
+            [0xff, 0x42, 1, 2, 3, 0xff].map(BusValue::new_val)
+        );
+
+        assert!(matches!(
+            mem.load_words(15, &[1, 2]),
+            Err(ImageError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_binary() {
+        let mem = Memory::new(4, 12, 0);
+        mem.load(&[0x34, 0x12, 0xcd, 0xab], ImageFormat::Binary)
+            .unwrap();
+
+        assert_eq!(mem.peek(0), BusValue::new_val(0x234));
+        assert_eq!(mem.peek(1), BusValue::new_val(0xbcd));
+        assert_eq!(
+            mem.save(ImageFormat::Binary),
+            [0x34, 0x02, 0xcd, 0x0b, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_intel_hex() {
+        let mem = Memory::new(0x20, 8, 0);
+        let image = b":0300100002337A3E\n:00000001FF\n";
+        mem.load(image, ImageFormat::IntelHex).unwrap();
+
+        assert_eq!(
+            mem.dump(0x10..0x13),
+            [0x02, 0x33, 0x7a].map(BusValue::new_val)
+        );
+
+        let copy = Memory::new(0x20, 8, 0xff);
+        copy.load(&mem.save(ImageFormat::IntelHex), ImageFormat::IntelHex)
+            .unwrap();
+        assert_eq!(copy.dump(0..0x20), mem.dump(0..0x20));
+
+        let bad_checksum = b":0300100002337A1E\n";
+        assert!(matches!(
+            mem.load(bad_checksum, ImageFormat::IntelHex),
+            Err(ImageError::Parse { line: 1, .. })
+        ));
+
+        let not_ascii = ":0300100002337A3\u{e9}\n".as_bytes();
+        assert!(matches!(
+            mem.load(not_ascii, ImageFormat::IntelHex),
+            Err(ImageError::Parse { line: 1, .. })
+        ));
+
+        let short_segment = b":0100000401FA\n";
+        match mem.load(short_segment, ImageFormat::IntelHex) {
+            Err(ImageError::Parse { line: 1, message }) => assert!(message.contains("length")),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // A record past the end leaves the earlier ones unwritten
+        let past_end = b":0100000011EE\n:01003000AA25\n:00000001FF\n";
+        assert!(matches!(
+            mem.load(past_end, ImageFormat::IntelHex),
+            Err(ImageError::OutOfRange { addr: 0x30 })
+        ));
+        assert_eq!(mem.peek(0), BusValue::new_val(0));
+    }
+
+    #[test]
+    fn test_logisim_raw() {
+        let mem = Memory::new(12, 8, 0);
+        mem.load(
+            b"v2.0 raw\n1 2f # comment\n5*a b\n",
+            ImageFormat::LogisimRaw,
+        )
+        .unwrap();
+
+        assert_eq!(
+            mem.dump(0..9),
+            [1, 0x2f, 0xa, 0xa, 0xa, 0xa, 0xa, 0xb, 0].map(BusValue::new_val)
+        );
+
+        assert_eq!(
+            String::from_utf8(mem.save(ImageFormat::LogisimRaw)).unwrap(),
+            "v2.0 raw\n1 2f 5*a b 4*0\n"
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use crate::*;
+
+/// The state that memory and sequential parts are put into at power-on
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PowerOnState {
+    /// Every bit is Low
+    Zeros,
+
+    /// Every bit is High
+    Ones,
+
+    /// Every bit is `Error`, so anything that reads state before it is initialized shows up
+    Unknown,
+
+    /// Every bit is random. The same seed always produces the same state
+    Random(u64),
+}
+
+/// Source of initial values handed to part initializers by `Graph::power_on`
+pub struct PowerOn {
+    state: PowerOnState,
+    rng: SplitMix64,
+}
+
+impl PowerOn {
+    pub(crate) fn new(state: PowerOnState) -> Self {
+        let seed = match state {
+            PowerOnState::Random(seed) => seed,
+            _ => 0,
+        };
+        Self {
+            state,
+            rng: SplitMix64(seed),
+        }
+    }
+
+    /// The policy being applied
+    pub fn state(&self) -> PowerOnState {
+        self.state
+    }
+
+    /// Gets the initial signal for a single bit of state
+    pub fn signal(&mut self) -> Signal {
+        match self.state {
+            PowerOnState::Zeros => Signal::Low,
+            PowerOnState::Ones => Signal::High,
+            PowerOnState::Unknown => Signal::Error,
+            PowerOnState::Random(_) => {
+                if self.rng.next() & 1 == 1 {
+                    Signal::High
+                } else {
+                    Signal::Low
+                }
+            }
+        }
+    }
+
+    /// Gets the initial value for a word of state with the given bit mask
+    pub fn word(&mut self, mask: usize) -> BusValue {
+        match self.state {
+            PowerOnState::Zeros => BusValue::new_val(0),
+            PowerOnState::Ones => BusValue::new_val(mask),
+            PowerOnState::Unknown => BusValue::new_error(mask),
+            PowerOnState::Random(_) => BusValue::new_val(self.rng.next() as usize & mask),
+        }
+    }
+
+    /// Initializes every word in a memory
+    pub fn fill(&mut self, memory: &Memory) {
+        let mask = memory.mask();
+        for word in memory.words_mut().iter_mut() {
+            *word = self.word(mask);
+        }
+    }
+
+    /// Sets a Q/Q̅ pair of output pins to a new initial state
+    pub fn outputs(&mut self, q: &mut PinState, q_inv: &mut PinState) {
+        let sig = self.signal();
+        *q = PinState::Output(sig);
+        *q_inv = PinState::Output(!sig);
+    }
+}
+
+/// Small, fast pseudo-random generator so seeded power-on states don't need a dependency
+///
+/// See <https://prng.di.unimi.it/splitmix64.c>
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod test_power {
+    use crate::*;
+
+    #[test]
+    fn test_flip_flop() {
+        let mut graph = Graph::new();
+        let ff = TFlipFlop::new(&mut graph, "ff");
+
+        graph.power_on(PowerOnState::Ones);
+        assert_eq!(ff.output().sig(), Signal::High);
+        assert_eq!(ff.out_inv().sig(), Signal::Low);
+
+        graph.power_on(PowerOnState::Unknown);
+        graph.run();
+        assert_eq!(ff.output().sig(), Signal::Error);
+
+        let mut reset = graph.new_output("reset", Signal::High);
+        graph.connect(&reset, ff.reset());
+        graph.run();
+        reset.set_output(Signal::Low);
+        graph.run();
+        assert_eq!(ff.output().sig(), Signal::Low);
+    }
+
+    #[test]
+    fn test_flip_flop_toggle_high() {
+        let mut graph = Graph::new();
+        let ff = TFlipFlop::new(&mut graph, "ff");
+        let mut toggle = graph.new_output("toggle", Signal::Low);
+        graph.connect(&toggle, ff.toggle());
+        graph.run();
+
+        // A toggle that is already High at power-on isn't an edge
+        toggle.set_output(Signal::High);
+        graph.power_on(PowerOnState::Zeros);
+        graph.run();
+        assert_eq!(ff.output().sig(), Signal::Low);
+
+        toggle.set_output(Signal::Low);
+        graph.run();
+        toggle.set_output(Signal::High);
+        graph.run();
+        assert_eq!(ff.output().sig(), Signal::High);
+    }
+
+    #[test]
+    fn test_ram() {
+        let mut graph = Graph::new();
+        let ram = IcCY7C199::new(&mut graph, "ram");
+
+        graph.power_on(PowerOnState::Zeros);
+        assert_eq!(ram.memory().peek(100), BusValue::new_val(0));
+
+        graph.power_on(PowerOnState::Unknown);
+        assert_eq!(ram.memory().peek(100), BusValue::new_error(0xff));
+
+        graph.power_on(PowerOnState::Random(1234));
+        let first = ram.memory().dump(0..IcCY7C199::NUM_WORDS);
+        assert!(first.iter().any(|w| w.val != first[0].val));
+
+        graph.power_on(PowerOnState::Zeros);
+        graph.power_on(PowerOnState::Random(1234));
+        assert_eq!(ram.memory().dump(0..IcCY7C199::NUM_WORDS), first);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::*;
+
+use std::fmt::{Debug, Formatter};
+use std::iter::zip;
+
+/// Holds the program and fetches instructions from it for the `ControlBlock`
+///
+/// The program counter addresses a ROM. While `fetch` is high, the instruction latch follows the
+/// ROM output. When `fetch` falls, the latch holds the instruction and the program counter moves
+/// on to the next address, or the previous one while `reverse` is high.
+pub struct ProgramBlock {
+    fetch: Pin,
+    reset: Pin,
+    reverse: Pin,
+    step_up: Pin,
+    step_down: Pin,
+    pc: Counter16Bit,
+    latch: Counter8Bit,
+    rom: Ic27C256,
+}
+
+impl Debug for ProgramBlock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgramBlock")
+            .field("pc", &self.pc().iter().val())
+            .field("instruction", &self.instruction().iter().val())
+            .finish()
+    }
+}
+
+impl ProgramBlock {
+    /// Fetch clock input, normally the `ControlBlock`'s instruction phase
+    pub fn fetch(&self) -> &Pin {
+        &self.fetch
+    }
+
+    /// Reset input
+    ///
+    /// While high, the program counter and instruction latch are cleared to 0
+    pub fn reset(&self) -> &Pin {
+        &self.reset
+    }
+
+    /// Reverse input
+    ///
+    /// While high, the program counter counts down after each fetch instead of up
+    pub fn reverse(&self) -> &Pin {
+        &self.reverse
+    }
+
+    /// Input for stepping the program counter up outside of a fetch
+    ///
+    /// The program counter counts up when it falls
+    pub fn step_up(&self) -> &Pin {
+        &self.step_up
+    }
+
+    /// Input for stepping the program counter down outside of a fetch
+    ///
+    /// The program counter counts down when it falls
+    pub fn step_down(&self) -> &Pin {
+        &self.step_down
+    }
+
+    /// Inverted load signal for the program counter
+    ///
+    /// While low, the program counter is set to the value on `target`
+    pub fn load_inv(&self) -> &Pin {
+        self.pc.load_inv()
+    }
+
+    /// Jump target input, loaded into the program counter by `load_inv`
+    pub fn target(&self) -> [&Pin; 16] {
+        self.pc.input()
+    }
+
+    /// Program counter output
+    pub fn pc(&self) -> [&Pin; 16] {
+        self.pc.output()
+    }
+
+    /// Instruction bus output, held from one fetch to the next
+    pub fn instruction(&self) -> [&Pin; 8] {
+        self.latch.output()
+    }
+
+    /// The program ROM. Only the low 15 bits of the program counter are used to address it
+    pub fn rom(&self) -> &Ic27C256 {
+        &self.rom
+    }
+
+    /// The contents of the program ROM
+    pub fn program(&self) -> &Memory {
+        self.rom.memory()
+    }
+
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let make_name = |n: &str| format!("{}.{}", name, n);
+
+        let pc = Counter16Bit::new(graph, &make_name("pc"));
+        let latch = Counter8Bit::new(graph, &make_name("latch"));
+        let rom = Ic27C256::new(graph, &make_name("rom"));
+
+        for (pc_pin, rom_pin) in zip(pc.output(), rom.addr()) {
+            pc_pin.connect(rom_pin);
+        }
+        for (rom_pin, latch_pin) in zip(rom.io(), latch.input()) {
+            rom_pin.connect(latch_pin);
+        }
+
+        // The ROM always outputs the instruction at the program counter
+        let low = graph.new_output(&make_name("LOW"), Signal::Low);
+        low.connect_all(&[rom.ce_inv(), rom.oe_inv()]);
+
+        // The latch never counts, so its counts are held high
+        let high = graph.new_output(&make_name("HIGH"), Signal::High);
+        high.connect_all(&[latch.up(), latch.down()]);
+
+        // The latch is transparent while fetching. The program counter counts when `fetch` falls,
+        // after the latch has closed, since the new address still has to make it through the
+        // counter and ROM
+        let fetch = graph.new_input(&make_name("fetch"));
+        let fetch_inv = !&fetch;
+        graph.connect(&fetch_inv, latch.load_inv());
+
+        let reverse = graph.new_input(&make_name("reverse"));
+        let step_up = graph.new_input(&make_name("step_up"));
+        let step_down = graph.new_input(&make_name("step_down"));
+        let reverse_inv = !&reverse;
+        let fetch_up = &fetch & &reverse_inv;
+        let fetch_down = &fetch & &reverse;
+        let pc_up = nor_gate(graph, &make_name("pc_up"));
+        let pc_down = nor_gate(graph, &make_name("pc_down"));
+        graph.connect(&fetch_up, pc_up.input_a());
+        graph.connect(&step_up, pc_up.input_b());
+        graph.connect(&fetch_down, pc_down.input_a());
+        graph.connect(&step_down, pc_down.input_b());
+        graph.connect(pc_up.output(), pc.up());
+        graph.connect(pc_down.output(), pc.down());
+
+        let reset = graph.new_input(&make_name("reset"));
+        reset.connect_all(&[pc.clear(), latch.clear()]);
+
+        Self {
+            fetch,
+            reset,
+            reverse,
+            step_up,
+            step_down,
+            pc,
+            latch,
+            rom,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_program_block {
+    use crate::*;
+
+    #[test]
+    fn test_fetch() {
+        let mut graph = Graph::new();
+        let p_block = ProgramBlock::new(&mut graph, "program");
+        let program = p_block.program();
+        program.load_words(0, &[3, 1, 4, 1, 5, 9, 2, 6]).unwrap();
+
+        let mut fetch = graph.new_output("fetch", Signal::Low);
+        let mut reset = graph.new_output("reset", Signal::High);
+        let mut load_inv = graph.new_output("load_inv", Signal::High);
+        let mut target = graph.new_pins("target", &[PinState::Output(Signal::Low); 16]);
+        let low = graph.new_output("low", Signal::Low);
+        low.connect_all(&[p_block.reverse(), p_block.step_up(), p_block.step_down()]);
+        graph.connect(&fetch, p_block.fetch());
+        graph.connect(&reset, p_block.reset());
+        graph.connect(&load_inv, p_block.load_inv());
+        for (one, other) in target.iter().zip(p_block.target()) {
+            graph.connect(one, other);
+        }
+
+        graph.run();
+        reset.set_output(Signal::Low);
+        graph.run();
+        assert_eq!(p_block.pc().iter().val(), BusValue::new_val(0));
+
+        for (pc, code) in [3, 1, 4, 1].into_iter().enumerate() {
+            graph.pulse_output(&mut fetch);
+            assert_eq!(p_block.instruction().iter().val(), BusValue::new_val(code));
+            assert_eq!(p_block.pc().iter().val(), BusValue::new_val(pc + 1));
+        }
+
+        // Jump back to 2
+        target[1].set_output(Signal::High);
+        graph.pulse_output(&mut load_inv);
+        assert_eq!(p_block.pc().iter().val(), BusValue::new_val(2));
+        assert_eq!(p_block.instruction().iter().val(), BusValue::new_val(1));
+
+        graph.pulse_output(&mut fetch);
+        assert_eq!(p_block.instruction().iter().val(), BusValue::new_val(4));
+        assert_eq!(p_block.pc().iter().val(), BusValue::new_val(3));
+    }
+
+    #[test]
+    fn test_control_block() {
+        let mut graph = Graph::new();
+        let c_block = ControlBlock::new(&mut graph, "control");
+        let d_block = DataBlock::new(&mut graph, "data");
+        let p_block = ProgramBlock::new(&mut graph, "program");
+        c_block.connect_data_block(&mut graph, &d_block);
+        graph.connect(c_block.instr(), p_block.fetch());
+        for (one, other) in p_block.instruction().iter().zip(c_block.instruction()) {
+            graph.connect(one, other);
+        }
+
+        let encoding = OpcodeEncoding::default();
+        let program = p_block.program();
+        encoding.load(program, "+++>+<-").unwrap();
+
+        let mut clock = graph.new_output("clock", Signal::Low);
+        let mut reset = graph.new_output("reset", Signal::High);
+        let clear = graph.new_output("clear", Signal::Low);
+        let load_inv = graph.new_output("load_inv", Signal::High);
+        let low = graph.new_output("low", Signal::Low);
+        low.connect_all(&[
+            c_block.skip(),
+            c_block.hold(),
+            p_block.reverse(),
+            p_block.step_up(),
+            p_block.step_down(),
+        ]);
+        graph.connect(&clock, c_block.clock());
+        graph.connect(&clear, d_block.clear());
+        graph.connect(&load_inv, p_block.load_inv());
+        graph.connect_all(&[&reset, c_block.reset(), d_block.reset(), p_block.reset()]);
+
+        graph.run();
+        reset.set_output(Signal::Low);
+        graph.run();
+        let start = d_block.addr().iter().val().val;
+        let cell = |offset| (start + offset) % IcCY7C199::NUM_WORDS;
+        d_block.tape().poke(cell(1), 0);
+
+        // Reset leaves `Halt` in the latch, so run until the program's own `Halt` is fetched
+        while !c_block.decoded(Opcode::Halt).sig().is_high() || p_block.pc().iter().val().val == 0 {
+            graph.pulse_output(&mut clock);
+        }
+
+        assert_eq!(p_block.pc().iter().val(), BusValue::new_val(8));
+        assert_eq!(d_block.tape().peek(cell(0)), BusValue::new_val(2));
+        assert_eq!(d_block.tape().peek(cell(1)), BusValue::new_val(1));
+    }
+}
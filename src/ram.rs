@@ -0,0 +1,633 @@
+use crate::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Port arrangement of a `Ram`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RamPorts {
+    /// One port that can read or write
+    Single,
+
+    /// Port 0 only writes and port 1 only reads
+    SimpleDual,
+
+    /// Two ports that can each read or write
+    TrueDual,
+}
+
+impl RamPorts {
+    /// Number of ports
+    pub fn count(&self) -> usize {
+        match self {
+            RamPorts::Single => 1,
+            RamPorts::SimpleDual | RamPorts::TrueDual => 2,
+        }
+    }
+
+    fn can_read(&self, port: usize) -> bool {
+        !matches!((self, port), (RamPorts::SimpleDual, 0))
+    }
+
+    fn can_write(&self, port: usize) -> bool {
+        !matches!((self, port), (RamPorts::SimpleDual, 1))
+    }
+}
+
+/// What a port reads from an address that is being written in the same tick
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReadDuringWrite {
+    /// The contents from before the write
+    OldData,
+
+    /// The data being written
+    NewData,
+
+    /// `Error` on every bit
+    Unknown,
+}
+
+/// Shape and behavior of a `Ram`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RamConfig {
+    /// Bits per word
+    pub data_width: usize,
+
+    /// Number of address bits. The RAM has `1 << addr_width` words
+    pub addr_width: usize,
+
+    /// Port arrangement
+    pub ports: RamPorts,
+
+    /// What reads see when the same address is written in the same tick
+    pub read_during_write: ReadDuringWrite,
+
+    /// If true, reads are captured in a register on the rising edge of the port's `clk` and the
+    /// register is output. Otherwise reads are combinational
+    pub registered_output: bool,
+}
+
+impl RamConfig {
+    /// A single-port, combinational RAM with the given size
+    pub fn new(data_width: usize, addr_width: usize) -> Self {
+        Self {
+            data_width,
+            addr_width,
+            ports: RamPorts::Single,
+            read_during_write: ReadDuringWrite::NewData,
+            registered_output: false,
+        }
+    }
+
+    /// Sets the port arrangement
+    pub fn ports(self, ports: RamPorts) -> Self {
+        Self { ports, ..self }
+    }
+
+    /// Sets the read-during-write behavior
+    pub fn read_during_write(self, read_during_write: ReadDuringWrite) -> Self {
+        Self {
+            read_during_write,
+            ..self
+        }
+    }
+
+    /// Sets whether outputs are registered
+    pub fn registered_output(self, registered_output: bool) -> Self {
+        Self {
+            registered_output,
+            ..self
+        }
+    }
+
+    /// Number of words
+    pub fn num_words(&self) -> usize {
+        1 << self.addr_width
+    }
+
+    /// Number of pins in each port, including the hidden edge detection pin
+    fn port_len(&self) -> usize {
+        RamPort::IO_START + self.data_width + self.addr_width
+    }
+}
+
+/// The pins for one port of a `Ram`
+///
+/// Behaves like the `IcCY7C199` interface. Ports that can't write ignore `we_inv`, and ports that
+/// can't read ignore `oe_inv`. When outputs are registered, reads come from a register loaded on the
+/// rising edge of `clk`
+#[derive(Clone)]
+pub struct RamPort {
+    pins: Vec<Pin>,
+    data_width: usize,
+    memory: Memory,
+}
+
+impl RamPort {
+    /// Inverse chip enable pin index
+    const CE_INV: usize = 0;
+
+    /// Inverse output enable pin index
+    const OE_INV: usize = 1;
+
+    /// Inverse write enable pin index
+    const WE_INV: usize = 2;
+
+    /// Output register clock pin index
+    const CLK: usize = 3;
+
+    // internal for edge detection
+    /// Previous clock state index
+    const CLK_PREV: usize = 4;
+
+    /// IO pin starting index
+    const IO_START: usize = 5;
+
+    /// Output register clock
+    ///
+    /// Only used when outputs are registered
+    pub fn clk(&self) -> &Pin {
+        &self.pins[Self::CLK]
+    }
+
+    fn addr_start(&self) -> usize {
+        Self::IO_START + self.data_width
+    }
+}
+
+impl MemoryChip for RamPort {
+    fn ce_inv(&self) -> &Pin {
+        &self.pins[Self::CE_INV]
+    }
+
+    fn oe_inv(&self) -> &Pin {
+        &self.pins[Self::OE_INV]
+    }
+
+    fn we_inv(&self) -> &Pin {
+        &self.pins[Self::WE_INV]
+    }
+
+    fn io(&self) -> &[Pin] {
+        &self.pins[Self::IO_START..self.addr_start()]
+    }
+
+    fn addr(&self) -> &[Pin] {
+        &self.pins[self.addr_start()..]
+    }
+
+    fn memory(&self) -> &Memory {
+        &self.memory
+    }
+}
+
+/// A RAM with configurable width, depth and ports
+///
+/// A single-port RAM with `RamConfig::new(8, 15)` behaves like the `IcCY7C199`
+pub struct Ram {
+    ports: Vec<RamPort>,
+    memory: Memory,
+    access_log: AccessLog,
+}
+
+impl Ram {
+    /// The pins for the given port
+    pub fn port(&self, port: usize) -> &RamPort {
+        &self.ports[port]
+    }
+
+    /// The pins for all the ports
+    pub fn ports(&self) -> &[RamPort] {
+        &self.ports
+    }
+
+    /// The contents of the RAM
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// The reads, writes and diagnostics of all the ports
+    pub fn access_log(&self) -> &AccessLog {
+        &self.access_log
+    }
+
+    /// Create a new RAM part
+    ///
+    /// The contents and output registers start as all ones, or in the graph's power-on state after
+    /// `Graph::power_on`
+    pub fn new(graph: &mut Graph, name: &str, config: RamConfig) -> Self {
+        let port_len = config.port_len();
+        let num_ports = config.ports.count();
+        let mut states = vec![PinState::INPUT; port_len * num_ports];
+        for port_states in states.chunks_mut(port_len) {
+            port_states[RamPort::IO_START..RamPort::IO_START + config.data_width]
+                .fill(PinState::HiZ);
+        }
+
+        let memory = Memory::new(config.num_words(), config.data_width, usize::MAX);
+        let registers = Rc::new(RefCell::new(vec![
+            BusValue::new_val(memory.mask());
+            num_ports
+        ]));
+
+        let access_log = AccessLog::new();
+        let mut monitors: Vec<AccessMonitor> = (0..num_ports)
+            .map(|port| AccessMonitor::new(graph, &access_log, port))
+            .collect();
+
+        let ram = memory.clone();
+        let regs = registers.clone();
+        let init_ram = memory.clone();
+        let pins = graph.new_stateful_part(
+            name,
+            &states,
+            move |pins| {
+                Self::update(
+                    &config,
+                    &mut ram.words_mut(),
+                    &mut regs.borrow_mut(),
+                    &mut monitors,
+                    pins,
+                )
+            },
+            move |power_on, _| {
+                power_on.fill(&init_ram);
+                let mask = init_ram.mask();
+                for reg in registers.borrow_mut().iter_mut() {
+                    *reg = power_on.word(mask);
+                }
+            },
+        );
+
+        let ports = pins
+            .chunks(port_len)
+            .map(|port_pins| RamPort {
+                pins: port_pins.to_vec(),
+                data_width: config.data_width,
+                memory: memory.clone(),
+            })
+            .collect();
+
+        Self {
+            ports,
+            memory,
+            access_log,
+        }
+    }
+
+    /// Part updater
+    fn update(
+        config: &RamConfig,
+        ram: &mut [BusValue],
+        registers: &mut [BusValue],
+        monitors: &mut [AccessMonitor],
+        pins: &mut [PinState],
+    ) {
+        let mask = usize::MAX >> (usize::BITS as usize - config.data_width);
+        let io_end = RamPort::IO_START + config.data_width;
+
+        // Addresses with Error bits can't be used
+        let addr_of = |port: &[PinState]| {
+            let addr = port[io_end..].iter().val();
+            (addr.error == 0).then_some(addr.val)
+        };
+
+        // Gather this tick's writes before any reads so read-during-write can be resolved
+        let mut writes: Vec<(usize, BusValue)> = vec![];
+        let mut writing_ports = vec![false; monitors.len()];
+        for (i, port) in pins.chunks(config.port_len()).enumerate() {
+            let reading = config.ports.can_read(i) && (!port[RamPort::OE_INV]).is_high();
+            let writing = config.ports.can_write(i)
+                && (!port[RamPort::CE_INV]).is_high()
+                && !reading
+                && (!port[RamPort::WE_INV]).is_high();
+            writing_ports[i] = writing;
+            if !writing {
+                continue;
+            }
+            let write = monitors[i].write(&port[io_end..], &port[RamPort::IO_START..io_end]);
+            if let Some((addr, data)) = write {
+                match writes.iter_mut().find(|(a, _)| *a == addr) {
+                    // Both ports writing the same address: differing bits are unknown
+                    Some((_, other)) => {
+                        let error = other.error | data.error | (other.val ^ data.val);
+                        *other = BusValue {
+                            val: other.val & !error,
+                            error,
+                        };
+                    }
+                    None => writes.push((addr, data)),
+                }
+            }
+        }
+
+        for (i, port) in pins.chunks_mut(config.port_len()).enumerate() {
+            let ce = !port[RamPort::CE_INV];
+            let oe = if config.ports.can_read(i) {
+                !port[RamPort::OE_INV]
+            } else {
+                Signal::Low
+            };
+
+            let read = addr_of(port).map_or(BusValue::new_error(mask), |addr| {
+                match writes.iter().find(|(a, _)| *a == addr) {
+                    None => ram[addr],
+                    Some((_, data)) => match config.read_during_write {
+                        ReadDuringWrite::OldData => ram[addr],
+                        ReadDuringWrite::NewData => *data,
+                        ReadDuringWrite::Unknown => BusValue::new_error(mask),
+                    },
+                }
+            });
+
+            if config.registered_output {
+                if port[RamPort::CLK].is_high() && port[RamPort::CLK_PREV].is_lowish() {
+                    registers[i] = read;
+                }
+                port[RamPort::CLK_PREV] = port[RamPort::CLK];
+            }
+            let out = if config.registered_output {
+                registers[i]
+            } else {
+                read
+            };
+
+            let io_pins = &mut port[RamPort::IO_START..io_end];
+            if ce.is_lowish() {
+                monitors[i].idle();
+                io_pins.fill(PinState::HiZ);
+            } else if oe.is_high() {
+                drive_bus(io_pins, out);
+                monitors[i].read(driven_val(&port[io_end..]), out);
+            } else {
+                if !writing_ports[i] {
+                    monitors[i].idle();
+                }
+                release_bus(io_pins);
+            }
+        }
+
+        for (addr, data) in writes {
+            ram[addr] = data;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_ram {
+    use crate::test_util::*;
+    use crate::*;
+    use std::iter::zip;
+
+    /// Host-driven control, address and data pins for one RAM port
+    struct PortDriver {
+        ce_inv: Pin,
+        oe_inv: Pin,
+        we_inv: Pin,
+        clk: Pin,
+        addr: Vec<Pin>,
+        data: Vec<Pin>,
+        data_en: Pin,
+    }
+
+    impl PortDriver {
+        fn new(graph: &mut Graph, port: &RamPort) -> Self {
+            let driver = Self {
+                ce_inv: graph.new_output("ce_inv", Signal::Low),
+                oe_inv: graph.new_output("oe_inv", Signal::High),
+                we_inv: graph.new_output("we_inv", Signal::High),
+                clk: graph.new_output("clk", Signal::Low),
+                addr: drive(graph, port.addr()),
+                data: graph.new_pins(
+                    "data",
+                    &vec![PinState::Output(Signal::Low); port.io().len()],
+                ),
+                data_en: graph.new_output("data_en", Signal::Low),
+            };
+            graph.connect_pairs(&[
+                (&driver.ce_inv, port.ce_inv()),
+                (&driver.oe_inv, port.oe_inv()),
+                (&driver.we_inv, port.we_inv()),
+                (&driver.clk, port.clk()),
+            ]);
+            let tristate = BusTristate::new(graph, "tristate", port.io().len());
+            graph.connect(&driver.data_en, tristate.en());
+            for ((d, t_in), (t_out, io)) in zip(
+                zip(&driver.data, tristate.input()),
+                zip(tristate.output(), port.io()),
+            ) {
+                graph.connect(d, t_in);
+                graph.connect(t_out, io);
+            }
+            driver
+        }
+
+        fn set_addr(&mut self, addr: usize) {
+            set_bus(&mut self.addr, addr);
+        }
+
+        /// Puts the address and data on the bus without enabling the write
+        fn setup_write(&mut self, addr: usize, data: usize) {
+            self.set_addr(addr);
+            set_bus(&mut self.data, data);
+            self.oe_inv.set_output(Signal::High);
+            self.data_en.set_output(Signal::High);
+        }
+
+        fn start_write(&mut self, addr: usize, data: usize) {
+            self.setup_write(addr, data);
+            self.we_inv.set_output(Signal::Low);
+        }
+
+        fn end_write(&mut self) {
+            self.we_inv.set_output(Signal::High);
+            self.data_en.set_output(Signal::Low);
+        }
+
+        fn start_read(&mut self, addr: usize) {
+            self.set_addr(addr);
+            self.oe_inv.set_output(Signal::Low);
+        }
+    }
+
+    #[test]
+    fn test_single_port() {
+        let mut graph = Graph::new();
+        let ram = Ram::new(&mut graph, "ram", RamConfig::new(12, 4));
+        let port = ram.port(0);
+        let mut driver = PortDriver::new(&mut graph, port);
+
+        driver.start_write(9, 0xabc);
+        graph.run();
+        driver.end_write();
+        graph.run();
+
+        assert_eq!(ram.memory().peek(9), BusValue::new_val(0xabc));
+
+        driver.start_read(9);
+        graph.run();
+        assert_eq!(port.io().iter().val(), BusValue::new_val(0xabc));
+    }
+
+    fn read_during_write(config: RamConfig) -> BusValue {
+        let mut graph = Graph::new();
+        let ram = Ram::new(&mut graph, "ram", config.ports(RamPorts::SimpleDual));
+        ram.memory().poke(3, 0x11);
+
+        let mut writer = PortDriver::new(&mut graph, ram.port(0));
+        let mut reader = PortDriver::new(&mut graph, ram.port(1));
+        reader.start_read(3);
+        graph.run();
+        assert_eq!(ram.port(1).io().iter().val(), BusValue::new_val(0x11));
+
+        writer.setup_write(3, 0x22);
+        graph.run();
+
+        // Sample the read port on the first tick the write is seen by the RAM
+        writer.we_inv.set_output(Signal::Low);
+        graph.tick();
+        graph.tick();
+        let seen = ram.port(1).io().iter().val();
+
+        graph.run();
+        writer.end_write();
+        graph.run();
+        assert_eq!(ram.memory().peek(3), BusValue::new_val(0x22));
+        seen
+    }
+
+    #[test]
+    fn test_read_during_write() {
+        let config = RamConfig::new(8, 4);
+        assert_eq!(
+            read_during_write(config.read_during_write(ReadDuringWrite::OldData)),
+            BusValue::new_val(0x11)
+        );
+        assert_eq!(
+            read_during_write(config.read_during_write(ReadDuringWrite::NewData)),
+            BusValue::new_val(0x22)
+        );
+        assert_eq!(
+            read_during_write(config.read_during_write(ReadDuringWrite::Unknown)),
+            BusValue::new_error(0xff)
+        );
+    }
+
+    #[test]
+    fn test_true_dual_port() {
+        let mut graph = Graph::new();
+        let config = RamConfig::new(8, 4).ports(RamPorts::TrueDual);
+        let ram = Ram::new(&mut graph, "ram", config);
+        let mut a = PortDriver::new(&mut graph, ram.port(0));
+        let mut b = PortDriver::new(&mut graph, ram.port(1));
+
+        // Independent writes
+        a.start_write(1, 0x0f);
+        b.start_write(2, 0xf0);
+        graph.run();
+        a.end_write();
+        b.end_write();
+        a.start_read(2);
+        b.start_read(1);
+        graph.run();
+        assert_eq!(ram.port(0).io().iter().val(), BusValue::new_val(0xf0));
+        assert_eq!(ram.port(1).io().iter().val(), BusValue::new_val(0x0f));
+
+        // Colliding writes leave the differing bits unknown
+        a.start_write(5, 0x0f);
+        b.start_write(5, 0x3c);
+        graph.run();
+        a.end_write();
+        b.end_write();
+        graph.run();
+        assert_eq!(
+            ram.memory().peek(5),
+            BusValue {
+                val: 0x0c,
+                error: 0x33
+            }
+        );
+    }
+
+    #[test]
+    fn test_registered_output() {
+        let mut graph = Graph::new();
+        let config = RamConfig::new(8, 4).registered_output(true);
+        let ram = Ram::new(&mut graph, "ram", config);
+        graph.power_on(PowerOnState::Zeros);
+        ram.memory().load_words(0, &[0x12, 0x34]).unwrap();
+
+        let mut driver = PortDriver::new(&mut graph, ram.port(0));
+        driver.start_read(1);
+        graph.run();
+        assert_eq!(ram.port(0).io().iter().val(), BusValue::new_val(0));
+
+        driver.clk.set_output(Signal::High);
+        graph.run();
+        assert_eq!(ram.port(0).io().iter().val(), BusValue::new_val(0x34));
+
+        driver.clk.set_output(Signal::Low);
+        driver.set_addr(0);
+        graph.run();
+        assert_eq!(ram.port(0).io().iter().val(), BusValue::new_val(0x34));
+    }
+
+    #[test]
+    fn test_access_log() {
+        let mut graph = Graph::new();
+        let ram = Ram::new(&mut graph, "ram", RamConfig::new(8, 4));
+        let log = ram.access_log();
+        log.set_enabled(true);
+        let mut driver = PortDriver::new(&mut graph, ram.port(0));
+        let clash = BusTristate::new(&mut graph, "clash", 1);
+        let mut clash_en = graph.new_output("clash_en", Signal::Low);
+        graph.connect(&clash_en, clash.en());
+        graph.connect(&clash.output()[0], &ram.port(0).io()[0]);
+
+        driver.setup_write(9, 0x42);
+        graph.run();
+        driver.we_inv.set_output(Signal::Low);
+        graph.run();
+        let write = *log.records().last().unwrap();
+        assert_eq!(write.access, Access::Write);
+        assert_eq!(write.addr, BusValue::new_val(9));
+        assert_eq!(write.data, BusValue::new_val(0x42));
+        assert!(log.diagnostics().is_empty());
+
+        // Moving the address with the write still enabled writes a second word
+        driver.set_addr(10);
+        graph.run();
+        assert_eq!(ram.memory().peek(10), BusValue::new_val(0x42));
+        let violations: Vec<Violation> = log.diagnostics().iter().map(|d| d.violation).collect();
+        assert_eq!(violations, [Violation::AddressChangedDuringWrite]);
+
+        // A data bit with two drivers is stored as an error
+        driver.end_write();
+        graph.run();
+        log.clear();
+        driver.start_write(10, 0x42);
+        clash_en.set_output(Signal::High);
+        graph.run();
+        assert_eq!(ram.memory().peek(10).error, 1);
+        let diagnostic = log.diagnostics()[0];
+        assert_eq!(diagnostic.violation, Violation::InvalidData);
+        assert_eq!(diagnostic.record.addr, BusValue::new_val(10));
+
+        driver.end_write();
+        clash_en.set_output(Signal::Low);
+        graph.run();
+        log.clear();
+        log.set_enabled(false);
+        driver.start_read(9);
+        graph.run();
+        assert!(log.records().is_empty());
+
+        log.set_enabled(true);
+        driver.set_addr(10);
+        let tick = graph.tick_count();
+        graph.run();
+        let read = log.records()[0];
+        assert_eq!(read.access, Access::Read);
+        assert_eq!(read.addr, BusValue::new_val(10));
+        assert!(read.tick > tick);
+    }
+}
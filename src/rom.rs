@@ -0,0 +1,343 @@
+use crate::*;
+
+/// Bit mask of a ROM word
+const WORD_MASK: usize = (1 << IcCY7C199::WORD_SIZE) - 1;
+
+/// Creates the pins for a 32k x 8bit memory chip with the same layout as `IcCY7C199`
+fn new_rom_part<F>(graph: &mut Graph, name: &str, updater: F) -> Vec<Pin>
+where
+    F: 'static + FnMut(&mut [PinState], &mut Timer),
+{
+    let mut states = [PinState::INPUT; IcCY7C199::NUM_PINS];
+    IcCY7C199::set_io(&mut states, PinState::HiZ);
+    graph.new_timed_part(name, &states, updater)
+}
+
+/// Splits the pins of a ROM into the IO pins and the current address
+///
+/// An address with `Error` bits gives `None`. `Off` bits count as Low, like the RAM's
+fn split_rom_pins(pins: &mut [PinState]) -> (&mut [PinState], Option<usize>) {
+    let (_, io_addr) = pins.split_at_mut(IcCY7C199::IO_START);
+    let (io_pins, addr_pins) = io_addr.split_at_mut(IcCY7C199::WORD_SIZE);
+    let addr = if addr_pins.iter().any(|p| matches!(p.sig(), Signal::Error)) {
+        None
+    } else {
+        Some(addr_pins.iter().val().val)
+    };
+    (io_pins, addr)
+}
+
+/// Outputs a word, or all `Error` if the address isn't valid
+fn output_word(io_pins: &mut [PinState], rom: &[BusValue], addr: Option<usize>) {
+    let word = match addr {
+        Some(addr) => rom[addr],
+        None => BusValue::new_error(WORD_MASK),
+    };
+    drive_bus(io_pins, word);
+}
+
+/// 32k x 8bit EPROM modeled after the 27C256
+///
+/// Uses the same pin layout as `IcCY7C199` so the two can be swapped on the same bus. Contents
+/// start erased (all ones) and are programmed by the host through `memory()`, so `we_inv` (`PGM̅`
+/// on the real chip) is ignored and the I/O pins are never inputs. Contents are non-volatile and
+/// aren't affected by `Graph::power_on`
+pub struct Ic27C256 {
+    pins: Vec<Pin>,
+    memory: Memory,
+}
+
+impl Ic27C256 {
+    /// Create a new EPROM part
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let memory = Memory::new(IcCY7C199::NUM_WORDS, IcCY7C199::WORD_SIZE, WORD_MASK);
+
+        let rom = memory.clone();
+        let pins = new_rom_part(graph, name, move |pins, _| {
+            let ce = !pins[IcCY7C199::CE_INV];
+            let oe = !pins[IcCY7C199::OE_INV];
+            let (io_pins, addr) = split_rom_pins(pins);
+
+            if ce.is_high() && oe.is_high() {
+                output_word(io_pins, &rom.words(), addr);
+            } else {
+                io_pins.fill(PinState::HiZ);
+            }
+        });
+
+        Self { pins, memory }
+    }
+}
+
+impl MemoryChip for Ic27C256 {
+    fn ce_inv(&self) -> &Pin {
+        &self.pins[IcCY7C199::CE_INV]
+    }
+
+    fn oe_inv(&self) -> &Pin {
+        &self.pins[IcCY7C199::OE_INV]
+    }
+
+    fn we_inv(&self) -> &Pin {
+        &self.pins[IcCY7C199::WE_INV]
+    }
+
+    fn io(&self) -> &[Pin] {
+        &self.pins[IcCY7C199::IO_START..IcCY7C199::IO_END]
+    }
+
+    fn addr(&self) -> &[Pin] {
+        &self.pins[IcCY7C199::ADDR_START..IcCY7C199::ADDR_END]
+    }
+
+    fn memory(&self) -> &Memory {
+        &self.memory
+    }
+}
+
+/// 32k x 8bit EEPROM modeled after the 28C256
+///
+/// Uses the same pin layout as `IcCY7C199` so the two can be swapped on the same bus. Contents
+/// start erased (all ones) and aren't affected by `Graph::power_on`.
+///
+/// By default writes behave like the RAM's. With a write cycle, the address and data are latched
+/// when the write ends (`we_inv`, `ce_inv` or `oe_inv` going High) and only committed after the
+/// given number of ticks. Until then further writes are ignored, and reads give DATA̅ polling: I/O7
+/// is the complement of the bit being written and the other I/O pins are `Error`
+pub struct Ic28C256 {
+    pins: Vec<Pin>,
+    memory: Memory,
+}
+
+impl Ic28C256 {
+    /// Create a new EEPROM part where writes take effect immediately
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        Self::with_write_cycle(graph, name, 0)
+    }
+
+    /// Create a new EEPROM part where each write takes `write_ticks` ticks to be committed
+    pub fn with_write_cycle(graph: &mut Graph, name: &str, write_ticks: usize) -> Self {
+        let memory = Memory::new(IcCY7C199::NUM_WORDS, IcCY7C199::WORD_SIZE, WORD_MASK);
+
+        // Address and data of a write in progress
+        let mut latch: Option<(usize, BusValue)> = None;
+        // Address, data, and finishing tick of the internal write cycle
+        let mut pending: Option<(usize, BusValue, usize)> = None;
+
+        let rom = memory.clone();
+        let pins = new_rom_part(graph, name, move |pins, timer| {
+            let ce = !pins[IcCY7C199::CE_INV];
+            let oe = !pins[IcCY7C199::OE_INV];
+            let we = !pins[IcCY7C199::WE_INV];
+            let (io_pins, addr) = split_rom_pins(pins);
+            let mut rom = rom.words_mut();
+
+            if let Some((addr, data, done)) = pending {
+                if timer.now() >= done {
+                    rom[addr] = data;
+                    pending = None;
+                }
+            }
+
+            if ce.is_lowish() {
+                io_pins.fill(PinState::HiZ);
+            } else if oe.is_high() {
+                match pending {
+                    Some((_, data, _)) => {
+                        io_pins.fill(PinState::Output(Signal::Error));
+                        io_pins[IcCY7C199::WORD_SIZE - 1] =
+                            PinState::Output(!data.sig(IcCY7C199::WORD_SIZE - 1));
+                    }
+                    None => output_word(io_pins, &rom, addr),
+                }
+            } else {
+                release_bus(io_pins);
+                let data = io_pins.iter().val();
+
+                match (we.is_high(), addr) {
+                    (true, Some(addr)) if write_ticks == 0 => rom[addr] = data,
+                    (true, Some(addr)) if pending.is_none() => latch = Some((addr, data)),
+                    _ => {}
+                }
+            }
+
+            let writing = ce.is_high() && oe.is_lowish() && we.is_high();
+            if !writing {
+                if let Some((addr, data)) = latch.take() {
+                    pending = Some((addr, data, timer.now() + write_ticks));
+                    timer.wake_in(write_ticks);
+                }
+            }
+        });
+
+        Self { pins, memory }
+    }
+}
+
+impl MemoryChip for Ic28C256 {
+    fn ce_inv(&self) -> &Pin {
+        &self.pins[IcCY7C199::CE_INV]
+    }
+
+    fn oe_inv(&self) -> &Pin {
+        &self.pins[IcCY7C199::OE_INV]
+    }
+
+    fn we_inv(&self) -> &Pin {
+        &self.pins[IcCY7C199::WE_INV]
+    }
+
+    fn io(&self) -> &[Pin] {
+        &self.pins[IcCY7C199::IO_START..IcCY7C199::IO_END]
+    }
+
+    fn addr(&self) -> &[Pin] {
+        &self.pins[IcCY7C199::ADDR_START..IcCY7C199::ADDR_END]
+    }
+
+    fn memory(&self) -> &Memory {
+        &self.memory
+    }
+}
+
+#[cfg(test)]
+mod test_rom {
+    use crate::test_util::*;
+    use crate::*;
+    use std::iter::zip;
+
+    /// Control, address and data pins for driving a memory chip
+    struct Harness {
+        graph: Graph,
+        oe_inv: Pin,
+        we_inv: Pin,
+        addr: Vec<Pin>,
+        data_en: Pin,
+        data_in: Vec<Pin>,
+    }
+
+    impl Harness {
+        fn new(graph: &mut Graph, chip: &impl MemoryChip) -> Self {
+            let ce_inv = graph.new_output("ce_inv", Signal::Low);
+            let oe_inv = graph.new_output("oe_inv", Signal::Low);
+            let we_inv = graph.new_output("we_inv", Signal::High);
+            graph.connect_pairs(&[
+                (&ce_inv, chip.ce_inv()),
+                (&oe_inv, chip.oe_inv()),
+                (&we_inv, chip.we_inv()),
+            ]);
+
+            let addr = drive(graph, chip.addr());
+
+            let data = BusTristate::new(graph, "data", 8);
+            let data_in = graph.new_pins("data_in", &[PinState::Output(Signal::Low); 8]);
+            for (one, other) in zip(data.output(), chip.io()) {
+                graph.connect(one, other);
+            }
+            for (one, other) in zip(&data_in, data.input()) {
+                graph.connect(one, other);
+            }
+            let data_en = graph.new_output("data_en", Signal::Low);
+            graph.connect(&data_en, data.en());
+
+            Self {
+                graph: graph.clone(),
+                oe_inv,
+                we_inv,
+                addr,
+                data_en,
+                data_in,
+            }
+        }
+
+        fn read(&mut self, chip: &impl MemoryChip, addr: usize) -> BusValue {
+            set_bus(&mut self.addr, addr);
+            self.graph.run();
+            chip.io().iter().val()
+        }
+
+        fn write(&mut self, addr: usize, data: usize) {
+            set_bus(&mut self.addr, addr);
+            set_bus(&mut self.data_in, data);
+            self.oe_inv.set_output(Signal::High);
+            self.data_en.set_output(Signal::High);
+            self.we_inv.set_output(Signal::Low);
+            self.graph.run_for(10);
+            self.we_inv.set_output(Signal::High);
+            self.data_en.set_output(Signal::Low);
+            self.oe_inv.set_output(Signal::Low);
+            self.graph.run_for(10);
+        }
+    }
+
+    #[test]
+    fn test_eprom() {
+        let mut graph = Graph::new();
+        let rom = Ic27C256::new(&mut graph, "rom");
+        OpcodeEncoding::default()
+            .load(rom.memory(), "+[-].")
+            .unwrap();
+
+        let mut harness = Harness::new(&mut graph, &rom);
+        assert_eq!(harness.read(&rom, 1), BusValue::new_val(7));
+        assert_eq!(harness.read(&rom, 5), BusValue::new_val(0));
+        assert_eq!(harness.read(&rom, 6), BusValue::new_val(0xff));
+
+        harness.write(6, 0x12);
+        assert_eq!(rom.memory().peek(6), BusValue::new_val(0xff));
+    }
+
+    #[test]
+    fn test_eeprom_swap() {
+        // The same wiring works for the RAM and both ROMs
+        fn write_read(chip: &impl MemoryChip, graph: &mut Graph) -> BusValue {
+            let mut harness = Harness::new(graph, chip);
+            harness.write(0x1234, 0x5a);
+            harness.graph.run();
+            harness.read(chip, 0x1234)
+        }
+
+        let mut graph = Graph::new();
+        let ram = IcCY7C199::new(&mut graph, "ram");
+        assert_eq!(write_read(&ram, &mut graph), BusValue::new_val(0x5a));
+
+        let mut graph = Graph::new();
+        let eeprom = Ic28C256::new(&mut graph, "eeprom");
+        assert_eq!(write_read(&eeprom, &mut graph), BusValue::new_val(0x5a));
+
+        let mut graph = Graph::new();
+        let eprom = Ic27C256::new(&mut graph, "eprom");
+        assert_eq!(write_read(&eprom, &mut graph), BusValue::new_val(0xff));
+    }
+
+    #[test]
+    fn test_eeprom_write_cycle() {
+        let mut graph = Graph::new();
+        let eeprom = Ic28C256::with_write_cycle(&mut graph, "eeprom", 1000);
+        let mut harness = Harness::new(&mut graph, &eeprom);
+
+        // DATA polling while the write is in progress
+        harness.write(3, 0x0f);
+        assert_eq!(eeprom.io()[7].sig(), Signal::High);
+        assert_eq!(eeprom.io()[0].sig(), Signal::Error);
+        assert_eq!(eeprom.memory().peek(3), BusValue::new_val(0xff));
+
+        // Writes during the write cycle are ignored
+        harness.write(4, 0x42);
+        let stats = graph.run_for(500);
+        assert_eq!(stats.ticks, 500);
+        assert_eq!(eeprom.memory().peek(3), BusValue::new_val(0xff));
+
+        // The write commits without any more input changes, and polling ends with a read of the
+        // current address
+        graph.run();
+        assert_eq!(eeprom.memory().peek(3), BusValue::new_val(0x0f));
+        assert_eq!(eeprom.memory().peek(4), BusValue::new_val(0xff));
+        assert_eq!(eeprom.io().iter().val(), BusValue::new_val(0xff));
+
+        // Writes work again once the cycle is done
+        harness.write(4, 0x42);
+        graph.run_for(1000);
+        assert_eq!(harness.read(&eeprom, 4), BusValue::new_val(0x42));
+    }
+}
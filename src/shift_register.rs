@@ -0,0 +1,526 @@
+use crate::*;
+
+// The registers keep their words in hidden pins, along with the previous clock states for edge
+// detection. The rules for Off and Error inputs are those of the storage primitives in flip_flop.rs.
+
+/// Reads a word kept in hidden pins
+fn stored(pins: &[PinState]) -> BusValue {
+    pins.iter().val()
+}
+
+/// Keeps a word in hidden pins
+fn store(pins: &mut [PinState], val: BusValue) {
+    for (i, pin) in pins.iter_mut().enumerate() {
+        *pin = PinState::Input(val.sig(i));
+    }
+}
+
+/// Each bit of `then` where `cond` is High, of `other` where it's Low, and Error where it's Error and
+/// they differ
+fn choose_word(cond: Signal, then: BusValue, other: BusValue) -> BusValue {
+    (0..8)
+        .map(|i| choose(cond, then.sig(i), other.sig(i)))
+        .val()
+}
+
+/// The word shifted one place towards Q7, with `serial` shifted into Q0
+fn shifted(word: BusValue, serial: Signal) -> BusValue {
+    (0..8)
+        .map(|i| if i == 0 { serial } else { word.sig(i - 1) })
+        .val()
+}
+
+/// Serial-in, parallel-out shift register with output latches, 74HC595
+///
+/// DS is shifted into Q0 of the shift register on the rising edge of SHCP, moving every other bit
+/// one place towards Q7. The rising edge of STCP copies the shift register to the storage
+/// register, which drives Q0-Q7 while OE̅ is Low. With the two clocks tied together, the storage
+/// register is one clock behind the shift register. MR̅ Low clears the shift register straight away.
+///
+/// Q7S always follows the last stage of the shift register. Connect it to the DS of the next chip,
+/// sharing the clocks, to cascade registers.
+#[derive(Debug)]
+pub struct Ic74HC595 {
+    pins: Vec<Pin>,
+}
+
+impl Ic74HC595 {
+    /// Serial data input pin index
+    pub(crate) const DS: usize = 0;
+
+    /// Shift clock pin index
+    pub(crate) const SHCP: usize = 1;
+
+    /// Storage clock pin index
+    pub(crate) const STCP: usize = 2;
+
+    /// Inverse master reset pin index
+    pub(crate) const MR_INV: usize = 3;
+
+    /// Inverse output enable pin index
+    pub(crate) const OE_INV: usize = 4;
+
+    /// Parallel output starting index
+    pub(crate) const Q_START: usize = 5;
+
+    /// Parallel output ending index
+    pub(crate) const Q_END: usize = Self::Q_START + 8;
+
+    /// Serial output pin index
+    pub(crate) const Q7S: usize = Self::Q_END;
+
+    // internal
+    /// Previous shift clock state index
+    const SHCP_PREV: usize = Self::Q7S + 1;
+
+    /// Previous storage clock state index
+    const STCP_PREV: usize = Self::SHCP_PREV + 1;
+
+    /// Shift register starting index
+    const SHIFT_START: usize = Self::STCP_PREV + 1;
+
+    /// Storage register starting index
+    const STORE_START: usize = Self::SHIFT_START + 8;
+
+    /// Total number of pins in part
+    pub(crate) const NUM_PINS: usize = Self::STORE_START + 8;
+
+    /// Pin indexes by DIP pin number, starting from pin 1
+    const DIP: [Option<usize>; 16] = [
+        Some(Self::Q_START + 1),
+        Some(Self::Q_START + 2),
+        Some(Self::Q_START + 3),
+        Some(Self::Q_START + 4),
+        Some(Self::Q_START + 5),
+        Some(Self::Q_START + 6),
+        Some(Self::Q_START + 7),
+        None,
+        Some(Self::Q7S),
+        Some(Self::MR_INV),
+        Some(Self::SHCP),
+        Some(Self::STCP),
+        Some(Self::OE_INV),
+        Some(Self::DS),
+        Some(Self::Q_START),
+        None,
+    ];
+
+    /// Creates a 74HC595
+    ///
+    /// The registers start as all zeros, or in the graph's power-on state after `Graph::power_on`
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let mut states = [PinState::Input(Signal::Low); Self::NUM_PINS];
+        states[Self::DS..Self::Q_START].fill(PinState::INPUT);
+        states[Self::Q_START..Self::Q_END].fill(PinState::HiZ);
+        states[Self::Q7S] = PinState::Output(Signal::Low);
+        let pins = graph.new_stateful_part(name, &states, Self::update, |power_on, pins| {
+            store(
+                &mut pins[Self::SHIFT_START..Self::STORE_START],
+                power_on.word(0xff),
+            );
+            store(&mut pins[Self::STORE_START..], power_on.word(0xff));
+        });
+        Self { pins }
+    }
+
+    /// Part updater
+    fn update(pins: &mut [PinState]) {
+        let shift_edge = rising_edge(pins[Self::SHCP], pins[Self::SHCP_PREV]);
+        let store_edge = rising_edge(pins[Self::STCP], pins[Self::STCP_PREV]);
+        pins[Self::SHCP_PREV] = pins[Self::SHCP];
+        pins[Self::STCP_PREV] = pins[Self::STCP];
+
+        // The storage register takes the shift register from before any shift on the same edge
+        let old_shift = stored(&pins[Self::SHIFT_START..Self::STORE_START]);
+        let storage = stored(&pins[Self::STORE_START..]);
+        let storage = choose_word(store_edge, old_shift, storage);
+
+        let shift = choose_word(
+            shift_edge,
+            shifted(old_shift, level(pins[Self::DS])),
+            old_shift,
+        );
+        let reset = !level(pins[Self::MR_INV]);
+        let shift = choose_word(reset, BusValue::new_val(0), shift);
+
+        store(&mut pins[Self::SHIFT_START..Self::STORE_START], shift);
+        store(&mut pins[Self::STORE_START..], storage);
+        pins[Self::Q7S] = PinState::Output(shift.sig(7));
+
+        let oe = !pins[Self::OE_INV];
+        let q_pins = &mut pins[Self::Q_START..Self::Q_END];
+        if oe.is_high() {
+            drive_bus(q_pins, storage);
+        } else {
+            q_pins.fill(PinState::HiZ);
+        }
+    }
+
+    /// Serial data input (DS)
+    pub fn serial_in(&self) -> &Pin {
+        &self.pins[Self::DS]
+    }
+
+    /// Shift clock (SHCP). Shifts on the rising edge
+    pub fn shift_clock(&self) -> &Pin {
+        &self.pins[Self::SHCP]
+    }
+
+    /// Storage clock (STCP). Latches the shift register on the rising edge
+    pub fn storage_clock(&self) -> &Pin {
+        &self.pins[Self::STCP]
+    }
+
+    /// Master reset (active Low)
+    pub fn mr_inv(&self) -> &Pin {
+        &self.pins[Self::MR_INV]
+    }
+
+    /// Output enable (active Low)
+    pub fn oe_inv(&self) -> &Pin {
+        &self.pins[Self::OE_INV]
+    }
+
+    /// Parallel outputs (Q0-Q7)
+    pub fn q(&self) -> &[Pin] {
+        &self.pins[Self::Q_START..Self::Q_END]
+    }
+
+    /// Serial output for cascading (Q7S)
+    pub fn serial_out(&self) -> &Pin {
+        &self.pins[Self::Q7S]
+    }
+
+    /// The pin with the given DIP pin number
+    ///
+    /// Panics for the power pins, 8 and 16
+    pub fn dip(&self, number: usize) -> &Pin {
+        dip_pin(&self.pins, &Self::DIP, number)
+    }
+}
+
+/// Parallel-in, serial-out shift register, 74HC165
+///
+/// While PL̅ is Low, D0-D7 are loaded straight into the register. Otherwise the register shifts one
+/// place towards Q7 on each rising edge of the clock, with DS shifted into the first stage. The
+/// clock is CP or CE̅, so CE̅ High inhibits clocking from CP. Q7 and Q̅7 follow the last stage.
+///
+/// Connect Q7 to the DS of the next chip towards the reader, sharing the clock and load, to cascade
+/// registers. The reader sees its own chip's D7 first.
+#[derive(Debug)]
+pub struct Ic74HC165 {
+    pins: Vec<Pin>,
+}
+
+impl Ic74HC165 {
+    /// Inverse parallel load pin index
+    pub(crate) const PL_INV: usize = 0;
+
+    /// Clock pin index
+    pub(crate) const CP: usize = 1;
+
+    /// Inverse clock enable pin index
+    pub(crate) const CE_INV: usize = 2;
+
+    /// Serial data input pin index
+    pub(crate) const DS: usize = 3;
+
+    /// Parallel input starting index
+    pub(crate) const D_START: usize = 4;
+
+    /// Parallel input ending index
+    pub(crate) const D_END: usize = Self::D_START + 8;
+
+    /// Serial output pin index
+    pub(crate) const Q7: usize = Self::D_END;
+
+    /// Inverse serial output pin index
+    pub(crate) const Q7_INV: usize = Self::Q7 + 1;
+
+    // internal
+    /// Previous clock state index
+    const CLOCK_PREV: usize = Self::Q7_INV + 1;
+
+    /// Shift register starting index
+    const SHIFT_START: usize = Self::CLOCK_PREV + 1;
+
+    /// Total number of pins in part
+    pub(crate) const NUM_PINS: usize = Self::SHIFT_START + 8;
+
+    /// Pin indexes by DIP pin number, starting from pin 1
+    const DIP: [Option<usize>; 16] = [
+        Some(Self::PL_INV),
+        Some(Self::CP),
+        Some(Self::D_START + 4),
+        Some(Self::D_START + 5),
+        Some(Self::D_START + 6),
+        Some(Self::D_START + 7),
+        Some(Self::Q7_INV),
+        None,
+        Some(Self::Q7),
+        Some(Self::DS),
+        Some(Self::D_START),
+        Some(Self::D_START + 1),
+        Some(Self::D_START + 2),
+        Some(Self::D_START + 3),
+        Some(Self::CE_INV),
+        None,
+    ];
+
+    /// Creates a 74HC165
+    ///
+    /// The register starts as all zeros, or in the graph's power-on state after `Graph::power_on`
+    pub fn new(graph: &mut Graph, name: &str) -> Self {
+        let mut states = [PinState::Input(Signal::Low); Self::NUM_PINS];
+        states[..Self::Q7].fill(PinState::INPUT);
+        states[Self::Q7] = PinState::Output(Signal::Low);
+        states[Self::Q7_INV] = PinState::Output(Signal::High);
+        let pins = graph.new_stateful_part(name, &states, Self::update, |power_on, pins| {
+            store(&mut pins[Self::SHIFT_START..], power_on.word(0xff));
+        });
+        Self { pins }
+    }
+
+    /// Part updater
+    fn update(pins: &mut [PinState]) {
+        let clock = PinState::Input(level(pins[Self::CP]) | level(pins[Self::CE_INV]));
+        let edge = rising_edge(clock, pins[Self::CLOCK_PREV]);
+        pins[Self::CLOCK_PREV] = clock;
+
+        let old_shift = stored(&pins[Self::SHIFT_START..]);
+        let shift = choose_word(edge, shifted(old_shift, level(pins[Self::DS])), old_shift);
+        let data = pins[Self::D_START..Self::D_END]
+            .iter()
+            .map(|pin| level(*pin))
+            .val();
+        let shift = choose_word(!level(pins[Self::PL_INV]), data, shift);
+
+        store(&mut pins[Self::SHIFT_START..], shift);
+        pins[Self::Q7] = PinState::Output(shift.sig(7));
+        pins[Self::Q7_INV] = PinState::Output(!shift.sig(7));
+    }
+
+    /// Parallel load (active Low)
+    pub fn pl_inv(&self) -> &Pin {
+        &self.pins[Self::PL_INV]
+    }
+
+    /// Clock (CP). Shifts on the rising edge
+    pub fn clock(&self) -> &Pin {
+        &self.pins[Self::CP]
+    }
+
+    /// Clock enable (active Low)
+    pub fn ce_inv(&self) -> &Pin {
+        &self.pins[Self::CE_INV]
+    }
+
+    /// Serial data input (DS)
+    pub fn serial_in(&self) -> &Pin {
+        &self.pins[Self::DS]
+    }
+
+    /// Parallel inputs (D0-D7)
+    pub fn d(&self) -> &[Pin] {
+        &self.pins[Self::D_START..Self::D_END]
+    }
+
+    /// Serial output (Q7)
+    pub fn serial_out(&self) -> &Pin {
+        &self.pins[Self::Q7]
+    }
+
+    /// Inverse serial output (Q̅7)
+    pub fn serial_out_inv(&self) -> &Pin {
+        &self.pins[Self::Q7_INV]
+    }
+
+    /// The pin with the given DIP pin number
+    ///
+    /// Panics for the power pins, 8 and 16
+    pub fn dip(&self, number: usize) -> &Pin {
+        dip_pin(&self.pins, &Self::DIP, number)
+    }
+}
+
+#[cfg(test)]
+mod test_shift_register {
+    use crate::test_util::*;
+    use crate::*;
+
+    /// Runs a rising and falling edge through the pin
+    fn pulse(graph: &mut Graph, pin: &mut Pin) {
+        pin.set_output(Signal::High);
+        graph.run();
+        pin.set_output(Signal::Low);
+        graph.run();
+    }
+
+    /// Shifts the bits of a value into a 595 chain, most-significant first
+    fn shift_out(graph: &mut Graph, data: &mut Pin, clock: &mut Pin, val: usize, bits: usize) {
+        for i in (0..bits).rev() {
+            data.set_output(Signal::from(val & (1 << i) != 0));
+            graph.run();
+            pulse(graph, clock);
+        }
+    }
+
+    /// Reads bits from a 165 chain, most-significant first
+    fn shift_in(graph: &mut Graph, output: &Pin, clock: &mut Pin, bits: usize) -> usize {
+        (0..bits).fold(0, |val, _| {
+            let bit = output.sig() == Signal::High;
+            pulse(graph, clock);
+            (val << 1) | bit as usize
+        })
+    }
+
+    #[test]
+    fn test_595() {
+        let mut graph = Graph::new();
+        let sr = Ic74HC595::new(&mut graph, "sr");
+        let mut data = graph.new_output("data", Signal::Low);
+        let mut shift_clock = graph.new_output("shift_clock", Signal::Low);
+        let mut storage_clock = graph.new_output("storage_clock", Signal::Low);
+        let mut mr_inv = graph.new_output("mr_inv", Signal::High);
+        let mut oe_inv = graph.new_output("oe_inv", Signal::Low);
+        graph.connect_pairs(&[
+            (&data, sr.serial_in()),
+            (&shift_clock, sr.shift_clock()),
+            (&storage_clock, sr.storage_clock()),
+            (&mr_inv, sr.mr_inv()),
+            (&oe_inv, sr.oe_inv()),
+        ]);
+        graph.run();
+
+        // The outputs only change when latched
+        shift_out(&mut graph, &mut data, &mut shift_clock, 0xa5, 8);
+        assert_eq!(sr.q().iter().val(), BusValue::new_val(0));
+        assert_eq!(sr.serial_out().sig(), Signal::High);
+        pulse(&mut graph, &mut storage_clock);
+        assert_eq!(sr.q().iter().val(), BusValue::new_val(0xa5));
+
+        // Reset clears the shift register but not the outputs
+        mr_inv.set_output(Signal::Low);
+        graph.run();
+        mr_inv.set_output(Signal::High);
+        graph.run();
+        assert_eq!(sr.serial_out().sig(), Signal::Low);
+        assert_eq!(sr.q().iter().val(), BusValue::new_val(0xa5));
+        pulse(&mut graph, &mut storage_clock);
+        assert_eq!(sr.q().iter().val(), BusValue::new_val(0));
+
+        oe_inv.set_output(Signal::High);
+        graph.run();
+        assert!(sr.q().iter().all(|pin| pin.sig() == Signal::Off));
+    }
+
+    #[test]
+    fn test_595_tied_clocks() {
+        // With the clocks tied, the outputs show the shift register from before each edge
+        let mut graph = Graph::new();
+        let sr = Ic74HC595::new(&mut graph, "sr");
+        let mut data = graph.new_output("data", Signal::High);
+        let mut clock = graph.new_output("clock", Signal::Low);
+        let high = graph.new_output("high", Signal::High);
+        let low = graph.new_output("low", Signal::Low);
+        graph.connect(&data, sr.serial_in());
+        graph.connect_all(&[&clock, sr.shift_clock(), sr.storage_clock()]);
+        graph.connect(&high, sr.mr_inv());
+        graph.connect(&low, sr.oe_inv());
+        graph.run();
+
+        shift_out(&mut graph, &mut data, &mut clock, 0b111, 3);
+        assert_eq!(sr.q().iter().val(), BusValue::new_val(0b11));
+    }
+
+    #[test]
+    fn test_595_cascade() {
+        let mut graph = Graph::new();
+        let sr1 = Ic74HC595::new(&mut graph, "sr1");
+        let sr2 = Ic74HC595::new(&mut graph, "sr2");
+        let mut data = graph.new_output("data", Signal::Low);
+        let mut shift_clock = graph.new_output("shift_clock", Signal::Low);
+        let mut storage_clock = graph.new_output("storage_clock", Signal::Low);
+        let high = graph.new_output("high", Signal::High);
+        let low = graph.new_output("low", Signal::Low);
+        graph.connect(&data, sr1.dip(14));
+        graph.connect(sr1.dip(9), sr2.dip(14));
+        graph.connect_all(&[&shift_clock, sr1.dip(11), sr2.dip(11)]);
+        graph.connect_all(&[&storage_clock, sr1.dip(12), sr2.dip(12)]);
+        graph.connect_all(&[&high, sr1.dip(10), sr2.dip(10)]);
+        graph.connect_all(&[&low, sr1.dip(13), sr2.dip(13)]);
+        graph.run();
+
+        shift_out(&mut graph, &mut data, &mut shift_clock, 0xbeef, 16);
+        pulse(&mut graph, &mut storage_clock);
+        assert_eq!(sr1.q().iter().val(), BusValue::new_val(0xef));
+        assert_eq!(sr2.q().iter().val(), BusValue::new_val(0xbe));
+        assert_eq!(sr2.dip(15).sig(), Signal::Low);
+        assert_eq!(sr2.dip(7).sig(), Signal::High);
+    }
+
+    #[test]
+    fn test_165() {
+        let mut graph = Graph::new();
+        let sr = Ic74HC165::new(&mut graph, "sr");
+        let mut pl_inv = graph.new_output("pl_inv", Signal::High);
+        let mut clock = graph.new_output("clock", Signal::Low);
+        let mut ce_inv = graph.new_output("ce_inv", Signal::Low);
+        let high = graph.new_output("high", Signal::High);
+        let mut switches = drive(&mut graph, sr.d());
+        set_bus(&mut switches, 0x5c);
+        graph.connect_pairs(&[
+            (&pl_inv, sr.pl_inv()),
+            (&clock, sr.clock()),
+            (&ce_inv, sr.ce_inv()),
+            (&high, sr.serial_in()),
+        ]);
+        graph.run();
+
+        // Load, then read D7 first. CE̅ rises during the load, since rising while CP is Low clocks
+        pl_inv.set_output(Signal::Low);
+        graph.run();
+        assert_eq!(sr.serial_out_inv().sig(), Signal::High);
+        ce_inv.set_output(Signal::High);
+        graph.run();
+        pl_inv.set_output(Signal::High);
+        graph.run();
+
+        // CE̅ High holds the register
+        pulse(&mut graph, &mut clock);
+        ce_inv.set_output(Signal::Low);
+        graph.run();
+        assert_eq!(shift_in(&mut graph, sr.serial_out(), &mut clock, 8), 0x5c);
+
+        // DS fills in behind
+        assert_eq!(sr.serial_out().sig(), Signal::High);
+    }
+
+    #[test]
+    fn test_165_cascade() {
+        let mut graph = Graph::new();
+        let sr1 = Ic74HC165::new(&mut graph, "sr1");
+        let sr2 = Ic74HC165::new(&mut graph, "sr2");
+        let mut pl_inv = graph.new_output("pl_inv", Signal::High);
+        let mut clock = graph.new_output("clock", Signal::Low);
+        let low = graph.new_output("low", Signal::Low);
+        let mut switches = drive(&mut graph, sr2.d().iter().chain(sr1.d()));
+        set_bus(&mut switches, 0xcafe);
+
+        // sr1 is next to the reader, with sr2 shifting into it
+        graph.connect_all(&[&pl_inv, sr1.dip(1), sr2.dip(1)]);
+        graph.connect_all(&[&clock, sr1.dip(2), sr2.dip(2)]);
+        graph.connect_all(&[&low, sr1.dip(15), sr2.dip(15), sr2.dip(10)]);
+        graph.connect(sr2.dip(9), sr1.dip(10));
+        graph.run();
+
+        pl_inv.set_output(Signal::Low);
+        graph.run();
+        pl_inv.set_output(Signal::High);
+        graph.run();
+        assert_eq!(
+            shift_in(&mut graph, sr1.serial_out(), &mut clock, 16),
+            0xcafe
+        );
+    }
+}
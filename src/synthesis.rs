@@ -0,0 +1,719 @@
+use crate::*;
+use std::collections::{BTreeSet, HashMap};
+
+/// A product term over a table's inputs
+///
+/// Inputs whose bit is set in `mask` don't appear in the term. The rest must match `value`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Implicant {
+    /// Required input values, zero where `mask` is set
+    pub value: usize,
+
+    /// Inputs the term doesn't depend on
+    pub mask: usize,
+}
+
+impl Implicant {
+    /// Whether the term is true for a combination of inputs
+    pub fn covers(&self, inputs: usize) -> bool {
+        inputs & !self.mask == self.value
+    }
+
+    /// The term's literals as (input, true if not inverted), out of `inputs` inputs
+    pub fn literals(&self, inputs: usize) -> Vec<(usize, bool)> {
+        (0..inputs)
+            .filter(|i| self.mask & (1 << i) == 0)
+            .map(|i| (i, self.value & (1 << i) != 0))
+            .collect()
+    }
+}
+
+/// Finds a minimal sum of products for one output of a table using Quine-McCluskey
+///
+/// Don't-care outputs are used to grow terms. Once the essential prime implicants are chosen, the
+/// rest are picked greedily, so the result is small but not always the smallest. An empty result
+/// is always Low.
+pub fn minimize(table: &TruthTable, output: usize) -> Vec<Implicant> {
+    cover(table, output, true)
+}
+
+/// Minimal sum of products for where an output has the given value
+fn cover(table: &TruthTable, output: usize, value: bool) -> Vec<Implicant> {
+    let bit = 1 << output;
+    let (dc, care): (Vec<usize>, Vec<usize>) =
+        (0..table.values.len()).partition(|i| table.dont_care[*i] & bit != 0);
+    let on: Vec<usize> = care
+        .into_iter()
+        .filter(|i| (table.values[*i] & bit != 0) == value)
+        .collect();
+    if on.is_empty() {
+        return Vec::new();
+    }
+
+    let primes = prime_implicants(table.inputs.len(), &on, &dc);
+
+    // Essential primes are the only ones covering some minterm
+    let mut chosen: Vec<Implicant> = Vec::new();
+    for m in &on {
+        let mut covering = primes.iter().filter(|p| p.covers(*m));
+        let first = covering.next().unwrap();
+        if covering.next().is_none() && !chosen.contains(first) {
+            chosen.push(*first);
+        }
+    }
+
+    let mut uncovered: BTreeSet<usize> = on
+        .into_iter()
+        .filter(|m| !chosen.iter().any(|p| p.covers(*m)))
+        .collect();
+    while !uncovered.is_empty() {
+        // Most uncovered minterms, then fewest literals
+        let best = *primes
+            .iter()
+            .max_by_key(|p| {
+                let count = uncovered.iter().filter(|m| p.covers(**m)).count();
+                (count, p.mask.count_ones())
+            })
+            .unwrap();
+        chosen.push(best);
+        uncovered.retain(|m| !best.covers(*m));
+    }
+
+    chosen.sort();
+    chosen
+}
+
+/// Merges minterms into larger terms until none can grow
+fn prime_implicants(inputs: usize, on: &[usize], dc: &[usize]) -> Vec<Implicant> {
+    let mut current: BTreeSet<Implicant> = on
+        .iter()
+        .chain(dc)
+        .map(|m| Implicant { value: *m, mask: 0 })
+        .collect();
+    let mut primes = Vec::new();
+
+    while !current.is_empty() {
+        let mut next = BTreeSet::new();
+        let mut merged = BTreeSet::new();
+        for term in &current {
+            for i in 0..inputs {
+                let bit = 1 << i;
+                if (term.mask | term.value) & bit != 0 {
+                    continue;
+                }
+                let partner = Implicant {
+                    value: term.value | bit,
+                    mask: term.mask,
+                };
+                if current.contains(&partner) {
+                    next.insert(Implicant {
+                        value: term.value,
+                        mask: term.mask | bit,
+                    });
+                    merged.insert(*term);
+                    merged.insert(partner);
+                }
+            }
+        }
+        primes.extend(current.difference(&merged));
+        current = next;
+    }
+
+    primes
+}
+
+/// Which gates a synthesized network is made of
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GateStyle {
+    /// And gates into or gates, with inverters
+    AndOr,
+
+    /// Only nand gates, including one-input nands as inverters
+    Nand,
+
+    /// Only nor gates, including one-input nors as inverters
+    Nor,
+}
+
+/// Kind of gate in a synthesized network
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GateKind {
+    /// `not_gate`
+    Not,
+
+    /// `and_nary`
+    And,
+
+    /// `or_nary`
+    Or,
+
+    /// `nand_nary`, or an inverter with one input
+    Nand,
+
+    /// `nor_nary`, or an inverter with one input
+    Nor,
+}
+
+/// Where a gate input or network output comes from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Source {
+    /// A network input
+    Input(usize),
+
+    /// The output of a gate, by index
+    Gate(usize),
+
+    /// A fixed signal
+    Const(bool),
+}
+
+/// 74-series gate chips as (part number, kind, inputs per gate, gates per chip)
+///
+/// Gates use the narrowest chip that fits, with spare inputs tied off. Gates wider than every chip
+/// are split up.
+pub const GATE_CHIPS: &[(&str, GateKind, usize, usize)] = &[
+    ("7404", GateKind::Not, 1, 6),
+    ("7408", GateKind::And, 2, 4),
+    ("7411", GateKind::And, 3, 3),
+    ("7421", GateKind::And, 4, 2),
+    ("7432", GateKind::Or, 2, 4),
+    ("74HC4075", GateKind::Or, 3, 3),
+    ("74HC4072", GateKind::Or, 4, 2),
+    ("7400", GateKind::Nand, 2, 4),
+    ("7410", GateKind::Nand, 3, 3),
+    ("7420", GateKind::Nand, 4, 2),
+    ("7430", GateKind::Nand, 8, 1),
+    ("74133", GateKind::Nand, 13, 1),
+    ("7402", GateKind::Nor, 2, 4),
+    ("7427", GateKind::Nor, 3, 3),
+    ("74HC4002", GateKind::Nor, 4, 2),
+    ("74260", GateKind::Nor, 5, 2),
+];
+
+/// Widest gate of a kind in `GATE_CHIPS`
+fn max_fan_in(kind: GateKind) -> usize {
+    GATE_CHIPS
+        .iter()
+        .filter(|chip| chip.1 == kind)
+        .map(|chip| chip.2)
+        .max()
+        .unwrap()
+}
+
+/// A two-level gate network synthesized from a truth table
+///
+/// Product terms are shared between outputs, and gates wider than the widest chip become trees.
+#[derive(Clone, Debug)]
+pub struct GateNetwork {
+    style: GateStyle,
+    inputs: usize,
+    gates: Vec<(GateKind, Vec<Source>)>,
+    outputs: Vec<Source>,
+    cache: HashMap<(GateKind, Vec<Source>), usize>,
+}
+
+impl GateNetwork {
+    /// Minimizes every output of the table and builds it from gates of the given style
+    ///
+    /// And-or and nand networks implement the minimal sum of products. Nor networks implement the
+    /// minimal product of sums, found from where the outputs are Low.
+    pub fn new(table: &TruthTable, style: GateStyle) -> Self {
+        let mut network = Self {
+            style,
+            inputs: table.inputs.len(),
+            gates: Vec::new(),
+            outputs: Vec::new(),
+            cache: HashMap::new(),
+        };
+        for output in 0..table.outputs.len() {
+            let source = match style {
+                GateStyle::AndOr => network.two_level(table, output, GateKind::And, GateKind::Or),
+                GateStyle::Nand => network.two_level(table, output, GateKind::Nand, GateKind::Nand),
+                GateStyle::Nor => network.two_level(table, output, GateKind::Nor, GateKind::Nor),
+            };
+            network.outputs.push(source);
+        }
+        network.prune();
+        network
+    }
+
+    /// Removes gates left unused after double inversions cancel
+    fn prune(&mut self) {
+        let mut used = vec![false; self.gates.len()];
+        for source in &self.outputs {
+            if let Source::Gate(g) = source {
+                used[*g] = true;
+            }
+        }
+        for g in (0..self.gates.len()).rev() {
+            if used[g] {
+                for source in &self.gates[g].1 {
+                    if let Source::Gate(input) = source {
+                        used[*input] = true;
+                    }
+                }
+            }
+        }
+
+        let mut index = vec![0; self.gates.len()];
+        let remap = |index: &[usize], source: &mut Source| {
+            if let Source::Gate(g) = source {
+                *g = index[*g];
+            }
+        };
+        let gates = std::mem::take(&mut self.gates);
+        for (g, (kind, mut inputs)) in gates.into_iter().enumerate() {
+            if used[g] {
+                inputs.iter_mut().for_each(|source| remap(&index, source));
+                index[g] = self.gates.len();
+                self.gates.push((kind, inputs));
+            }
+        }
+        self.outputs
+            .iter_mut()
+            .for_each(|source| remap(&index, source));
+        self.cache.clear();
+    }
+
+    /// Builds one output from its minimized terms
+    ///
+    /// Nor terms are built from the Low cover with inverted literals, so each first-level nor is
+    /// High where the output is Low, and the second level inverts that back.
+    fn two_level(
+        &mut self,
+        table: &TruthTable,
+        output: usize,
+        term_kind: GateKind,
+        sum_kind: GateKind,
+    ) -> Source {
+        let inverted = term_kind == GateKind::Nor;
+        let terms = cover(table, output, !inverted);
+        if terms.is_empty() {
+            return Source::Const(inverted);
+        }
+        if terms
+            .iter()
+            .any(|term| term.mask.count_ones() as usize == self.inputs)
+        {
+            return Source::Const(!inverted);
+        }
+
+        let terms = terms
+            .iter()
+            .map(|term| {
+                let literals = term
+                    .literals(self.inputs)
+                    .into_iter()
+                    .map(|(i, positive)| self.literal(i, positive != inverted))
+                    .collect();
+                self.gate(term_kind, literals)
+            })
+            .collect();
+        self.gate(sum_kind, terms)
+    }
+
+    /// An input or its inverse
+    fn literal(&mut self, input: usize, positive: bool) -> Source {
+        if positive {
+            Source::Input(input)
+        } else {
+            self.not(Source::Input(input))
+        }
+    }
+
+    /// Inverts a source using the style's inverter, cancelling double inversions
+    fn not(&mut self, source: Source) -> Source {
+        let kind = match self.style {
+            GateStyle::AndOr => GateKind::Not,
+            GateStyle::Nand => GateKind::Nand,
+            GateStyle::Nor => GateKind::Nor,
+        };
+        if let Source::Gate(g) = source {
+            let (gate_kind, inputs) = &self.gates[g];
+            if *gate_kind == kind && inputs.len() == 1 {
+                return inputs[0];
+            }
+        }
+        self.add(kind, vec![source])
+    }
+
+    /// A gate of any width, split into a tree if it's wider than the widest chip
+    fn gate(&mut self, kind: GateKind, mut inputs: Vec<Source>) -> Source {
+        if inputs.len() == 1 {
+            return match kind {
+                GateKind::Nand | GateKind::Nor => self.not(inputs[0]),
+                _ => inputs[0],
+            };
+        }
+
+        let max = max_fan_in(kind);
+        while inputs.len() > max {
+            inputs = inputs
+                .chunks(max)
+                .map(|chunk| match chunk {
+                    [one] => *one,
+                    _ => {
+                        let gate = self.add(kind, chunk.to_vec());
+                        match kind {
+                            // Inverted to feed the next level as an and/or
+                            GateKind::Nand | GateKind::Nor => self.not(gate),
+                            _ => gate,
+                        }
+                    }
+                })
+                .collect();
+        }
+        self.add(kind, inputs)
+    }
+
+    /// Adds a gate, reusing an identical one
+    fn add(&mut self, kind: GateKind, mut inputs: Vec<Source>) -> Source {
+        inputs.sort();
+        if let Some(g) = self.cache.get(&(kind, inputs.clone())) {
+            return Source::Gate(*g);
+        }
+        self.gates.push((kind, inputs.clone()));
+        self.cache.insert((kind, inputs), self.gates.len() - 1);
+        Source::Gate(self.gates.len() - 1)
+    }
+
+    /// The style of gates in the network
+    pub fn style(&self) -> GateStyle {
+        self.style
+    }
+
+    /// Number of inputs
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    /// The gates, in an order where every gate comes after the gates feeding it
+    pub fn gates(&self) -> &[(GateKind, Vec<Source>)] {
+        &self.gates
+    }
+
+    /// Where each output comes from
+    pub fn outputs(&self) -> &[Source] {
+        &self.outputs
+    }
+
+    /// Number of gates between the inputs and the slowest output
+    pub fn depth(&self) -> usize {
+        let mut depths = Vec::with_capacity(self.gates.len());
+        for (_, inputs) in &self.gates {
+            let depth = inputs
+                .iter()
+                .map(|source| match source {
+                    Source::Gate(g) => depths[*g],
+                    _ => 0,
+                })
+                .max()
+                .unwrap_or(0);
+            depths.push(depth + 1);
+        }
+        self.outputs
+            .iter()
+            .map(|source| match source {
+                Source::Gate(g) => depths[*g],
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Output word for an input word, least-significant first
+    pub fn eval(&self, input: usize) -> usize {
+        let mut values: Vec<bool> = Vec::with_capacity(self.gates.len());
+        let get = |values: &[bool], source: &Source| match source {
+            Source::Input(i) => input & (1 << i) != 0,
+            Source::Gate(g) => values[*g],
+            Source::Const(val) => *val,
+        };
+        for (kind, inputs) in &self.gates {
+            let mut ins = inputs.iter().map(|source| get(&values, source));
+            let value = match kind {
+                GateKind::Not => !ins.next().unwrap(),
+                GateKind::And => ins.all(|v| v),
+                GateKind::Or => ins.any(|v| v),
+                GateKind::Nand => !ins.all(|v| v),
+                GateKind::Nor => !ins.any(|v| v),
+            };
+            values.push(value);
+        }
+        self.outputs
+            .iter()
+            .enumerate()
+            .map(|(i, source)| (get(&values, source) as usize) << i)
+            .sum()
+    }
+
+    /// Estimated 74-series chips to build the network, as (part number, count)
+    ///
+    /// Gates are only packed with gates that use the same chip, so mixing widths can cost a little
+    /// more than a careful layout.
+    pub fn chips(&self) -> Vec<(&'static str, usize)> {
+        let mut used = vec![0usize; GATE_CHIPS.len()];
+        for (kind, inputs) in &self.gates {
+            let chip = GATE_CHIPS
+                .iter()
+                .position(|chip| chip.1 == *kind && chip.2 >= inputs.len())
+                .unwrap();
+            used[chip] += 1;
+        }
+        GATE_CHIPS
+            .iter()
+            .zip(used)
+            .filter(|(_, gates)| *gates > 0)
+            .map(|(chip, gates)| (chip.0, gates.div_ceil(chip.3)))
+            .collect()
+    }
+
+    /// Estimated total number of 74-series chips to build the network
+    pub fn chip_count(&self) -> usize {
+        self.chips().iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// A truth table built out of logic gates in the graph
+///
+/// The gates are named after the module, e.g. `name.nand3`. Each gate adds one tick of delay, so the
+/// outputs settle after `GateNetwork::depth` ticks. Outputs with the same function share a pin, and
+/// an output that's just an input is the input pin itself.
+#[derive(Debug)]
+pub struct SynthesizedLogic {
+    input: Vec<Pin>,
+    output: Vec<Pin>,
+    network: GateNetwork,
+}
+
+impl SynthesizedLogic {
+    /// Input pins, least-significant first
+    pub fn input(&self) -> &[Pin] {
+        &self.input
+    }
+
+    /// Output pins, least-significant first
+    pub fn output(&self) -> &[Pin] {
+        &self.output
+    }
+
+    /// The network the gates were built from
+    pub fn network(&self) -> &GateNetwork {
+        &self.network
+    }
+
+    /// Minimizes a truth table and builds it from gates of the given style
+    pub fn new(graph: &mut Graph, name: &str, table: &TruthTable, style: GateStyle) -> Self {
+        Self::from_network(graph, name, GateNetwork::new(table, style))
+    }
+
+    /// Builds a network that's already been synthesized
+    pub fn from_network(graph: &mut Graph, name: &str, network: GateNetwork) -> Self {
+        let make_name = |n: String| format!("{}.{}", name, n);
+        let input = graph.new_pins(
+            &make_name("input".to_string()),
+            &vec![PinState::INPUT; network.inputs()],
+        );
+
+        let mut gates: Vec<Pin> = Vec::with_capacity(network.gates().len());
+        for (g, (kind, sources)) in network.gates().iter().enumerate() {
+            let gate_name = make_name(format!("{:?}{}", kind, g).to_lowercase());
+            let width = sources.len();
+            let (inputs, output) = match kind {
+                GateKind::Not => {
+                    let gate = not_gate(graph, &gate_name);
+                    (vec![gate.input().clone()], gate.output().clone())
+                }
+                _ => {
+                    let gate = match kind {
+                        GateKind::And => and_nary(graph, &gate_name, width),
+                        GateKind::Or => or_nary(graph, &gate_name, width),
+                        GateKind::Nand => nand_nary(graph, &gate_name, width),
+                        _ => nor_nary(graph, &gate_name, width),
+                    };
+                    (gate.input().to_vec(), gate.output().clone())
+                }
+            };
+            for (pin, source) in inputs.iter().zip(sources) {
+                match source {
+                    Source::Input(i) => graph.connect(&input[*i], pin),
+                    Source::Gate(g) => graph.connect(&gates[*g], pin),
+                    Source::Const(_) => unreachable!("Constants are never gate inputs"),
+                }
+            }
+            gates.push(output);
+        }
+
+        let output = network
+            .outputs()
+            .iter()
+            .enumerate()
+            .map(|(i, source)| match source {
+                Source::Input(n) => input[*n].clone(),
+                Source::Gate(g) => gates[*g].clone(),
+                Source::Const(val) => {
+                    graph.new_output(&make_name(format!("output{}", i)), Signal::from(*val))
+                }
+            })
+            .collect();
+
+        Self {
+            input,
+            output,
+            network,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_synthesis {
+    use crate::test_util::*;
+    use crate::*;
+
+    const STYLES: [GateStyle; 3] = [GateStyle::AndOr, GateStyle::Nand, GateStyle::Nor];
+
+    /// 7-segment patterns for hex digits, segment a in bit 0
+    const SEGMENTS: [usize; 16] = [
+        0x3f, 0x06, 0x5b, 0x4f, 0x66, 0x6d, 0x7d, 0x07, 0x7f, 0x6f, 0x77, 0x7c, 0x39, 0x5e, 0x79,
+        0x71,
+    ];
+
+    /// Checks that a network matches a table wherever the table cares
+    fn check(network: &GateNetwork, table: &TruthTable) {
+        for (i, (value, dont_care)) in table.values.iter().zip(&table.dont_care).enumerate() {
+            assert_eq!(
+                network.eval(i) & !dont_care,
+                value & !dont_care,
+                "{:?} input {:b}",
+                network.style(),
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn test_minimize() {
+        let table = TruthTable::from_expr("a & b | b & c | a & c").unwrap();
+        let terms = minimize(&table, 0);
+        assert_eq!(terms.len(), 3);
+        assert!(terms.iter().all(|term| term.literals(3).len() == 2));
+
+        // x & !y | !x & y | x & y only needs two terms
+        let table = TruthTable::from_expr("x | y").unwrap();
+        assert_eq!(
+            minimize(&table, 0),
+            vec![
+                Implicant { value: 1, mask: 2 },
+                Implicant { value: 2, mask: 1 }
+            ]
+        );
+
+        assert_eq!(
+            minimize(&TruthTable::from_expr("a & !a").unwrap(), 0),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_dont_care() {
+        // BCD digits 5 and up, with 10-15 unused
+        let mut table = TruthTable::from_fn(4, 1, |i| (i >= 5) as usize).unwrap();
+        for i in 10..16 {
+            table.dont_care[i] = 1;
+        }
+        // d3 | d2 & d0 | d2 & d1
+        let terms = minimize(&table, 0);
+        assert_eq!(terms.len(), 3);
+        assert_eq!(terms.iter().map(|t| t.literals(4).len()).sum::<usize>(), 5);
+        for style in STYLES {
+            check(&GateNetwork::new(&table, style), &table);
+        }
+    }
+
+    #[test]
+    fn test_styles() {
+        let table = TruthTable::from_fn(4, 7, |i| SEGMENTS[i]).unwrap();
+        for style in STYLES {
+            let network = GateNetwork::new(&table, style);
+            check(&network, &table);
+            assert!(network.gates().iter().all(|(kind, _)| match style {
+                GateStyle::AndOr => [GateKind::Not, GateKind::And, GateKind::Or].contains(kind),
+                GateStyle::Nand => *kind == GateKind::Nand,
+                GateStyle::Nor => *kind == GateKind::Nor,
+            }));
+        }
+
+        // Constants and plain inputs need no gates
+        let table = TruthTable::from_fn(2, 3, |i| 0b100 | (i & 1)).unwrap();
+        for style in STYLES {
+            let network = GateNetwork::new(&table, style);
+            assert_eq!(
+                network.outputs(),
+                &[Source::Input(0), Source::Const(false), Source::Const(true)]
+            );
+            assert_eq!(network.chip_count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_wide_gates() {
+        let table = TruthTable::from_fn(16, 1, |i| (i == 0xffff) as usize).unwrap();
+
+        let network = GateNetwork::new(&table, GateStyle::AndOr);
+        check(&network, &table);
+        // 4 x 4 inputs, then 4 more
+        assert_eq!(network.chips(), vec![("7421", 3)]);
+        assert_eq!(network.depth(), 2);
+
+        let network = GateNetwork::new(&table, GateStyle::Nand);
+        check(&network, &table);
+        // Two 13 and 3-input nands, inverted into the final nand, inverted again
+        assert_eq!(
+            network.chips(),
+            vec![("7400", 1), ("7410", 1), ("74133", 1)]
+        );
+        assert_eq!(network.depth(), 4);
+    }
+
+    #[test]
+    fn test_chips() {
+        // Majority vote: three 2-input ands and a 3-input or
+        let table = TruthTable::from_expr("a & b | b & c | a & c").unwrap();
+        let network = GateNetwork::new(&table, GateStyle::AndOr);
+        assert_eq!(network.chips(), vec![("7408", 1), ("74HC4075", 1)]);
+
+        let network = GateNetwork::new(&table, GateStyle::Nand);
+        assert_eq!(network.chips(), vec![("7400", 1), ("7410", 1)]);
+        assert_eq!(network.chip_count(), 2);
+
+        // Xor needs inverters
+        let table = TruthTable::from_expr("a ^ b").unwrap();
+        let network = GateNetwork::new(&table, GateStyle::AndOr);
+        assert_eq!(network.chips(), vec![("7404", 1), ("7408", 1), ("7432", 1)]);
+        let network = GateNetwork::new(&table, GateStyle::Nor);
+        assert_eq!(network.gates().len(), 5);
+        assert_eq!(network.chips(), vec![("7402", 2)]);
+    }
+
+    #[test]
+    fn test_graph() {
+        let table = TruthTable::from_fn(4, 7, |i| SEGMENTS[i]).unwrap();
+        for style in STYLES {
+            let mut graph = Graph::new();
+            let logic = SynthesizedLogic::new(&mut graph, "seg", &table, style);
+            let mut drivers = drive(&mut graph, logic.input());
+
+            for (i, segments) in SEGMENTS.iter().enumerate() {
+                set_bus(&mut drivers, i);
+                graph.run();
+                assert_eq!(
+                    logic.output().iter().val().unwrap(),
+                    *segments,
+                    "{:?} digit {:x}",
+                    style,
+                    i
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Host drivers shared by the part tests
+
+use crate::*;
+use std::borrow::Borrow;
+
+/// Creates a host output for each pin and connects them, starting Low
+///
+/// Outputs are named after the pins they drive
+pub(crate) fn drive<P: Borrow<Pin>>(
+    graph: &mut Graph,
+    pins: impl IntoIterator<Item = P>,
+) -> Vec<Pin> {
+    pins.into_iter()
+        .map(|pin| drive_pin(graph, pin.borrow(), Signal::Low))
+        .collect()
+}
+
+/// Creates a host output for one pin and connects it
+pub(crate) fn drive_pin(graph: &mut Graph, pin: &Pin, sig: Signal) -> Pin {
+    let driver = graph.new_output(&format!("drive.{}", pin.name()), sig);
+    graph.connect(&driver, pin);
+    driver
+}
+
+/// Sets host outputs to the given signals
+pub(crate) fn set_signals(drivers: &mut [Pin], sigs: &[Signal]) {
+    for (driver, sig) in drivers.iter_mut().zip(sigs) {
+        driver.set_output(*sig);
+    }
+}
+
+/// Sets host outputs to the bits of a value, least significant first
+pub(crate) fn set_bus(drivers: &mut [Pin], val: usize) {
+    for (i, driver) in drivers.iter_mut().enumerate() {
+        driver.set_output(Signal::from(val & (1 << i) != 0));
+    }
+}
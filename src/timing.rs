@@ -0,0 +1,289 @@
+use crate::*;
+use std::fmt::{Display, Formatter};
+
+/// A combinational path through the graph
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimingPath {
+    /// Total delay of the parts along the path
+    pub ticks: usize,
+
+    /// Names of the pins along the path, in the direction signals flow
+    pub pins: Vec<String>,
+}
+
+impl Display for TimingPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ticks: {}", self.ticks, self.pins.join(" -> "))
+    }
+}
+
+/// Results of `Graph::timing`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimingReport {
+    /// The longest combinational path, if there are any combinational parts
+    pub critical: Option<TimingPath>,
+
+    /// Combinational loops, each as the pins around the loop
+    pub loops: Vec<Vec<String>>,
+}
+
+/// One step back from a net through a combinational part
+struct Edge {
+    /// Net feeding the part's input
+    from: usize,
+
+    /// The part's input pin
+    input: PinId,
+
+    /// The part's output pin, in the later net
+    output: PinId,
+
+    /// Delay of the part
+    ticks: usize,
+}
+
+impl GraphImpl {
+    /// Worst-case delay of a part in ticks
+    fn part_ticks(&self, part_id: usize) -> usize {
+        match self.part_delays[part_id].or(self.default_delay) {
+            Some(delay) => {
+                let rise = delay.to_ticks(PinState::Output(Signal::High), self.tick_ns);
+                let fall = delay.to_ticks(PinState::Output(Signal::Low), self.tick_ns);
+                rise.max(fall)
+            }
+            None => 1,
+        }
+    }
+
+    /// Builds the edges leading back from each net, indexed by node id
+    ///
+    /// Every input of a combinational part is assumed to affect every output. Sequential parts,
+    /// i.e. those marked with `Graph::mark_sequential`, start and end paths instead of passing
+    /// through.
+    fn timing_edges(&self) -> Vec<Vec<Edge>> {
+        let mut edges: Vec<Vec<Edge>> = (0..self.next_node).map(|_| Vec::new()).collect();
+        for (part_id, (_, pin_range)) in self.parts.iter().enumerate() {
+            if self.part_sequential[part_id] {
+                continue;
+            }
+            let is_input = |pin_id: &PinId| matches!(self.pin_states[*pin_id], PinState::Input(_));
+            let (inputs, outputs): (Vec<PinId>, Vec<PinId>) = pin_range.clone().partition(is_input);
+            let ticks = self.part_ticks(part_id);
+            for output in outputs.iter() {
+                for input in inputs.iter() {
+                    edges[self.pin_nodes[*output]].push(Edge {
+                        from: self.pin_nodes[*input],
+                        input: *input,
+                        output: *output,
+                        ticks,
+                    });
+                }
+            }
+        }
+        edges
+    }
+
+    /// Finds the longest combinational path and any combinational loops
+    pub fn timing(&self) -> TimingReport {
+        let edges = self.timing_edges();
+        let name = |pin_id: PinId| self.pin_names[pin_id].clone();
+
+        // Depth-first search back from every net, tracking the longest way to reach each one
+        const UNVISITED: u8 = 0;
+        const VISITING: u8 = 1;
+        const DONE: u8 = 2;
+        let mut state = vec![UNVISITED; edges.len()];
+        let mut arrival = vec![0; edges.len()];
+        let mut best: Vec<Option<usize>> = vec![None; edges.len()];
+        let mut loops = Vec::new();
+
+        for root in self.nodes.keys() {
+            if state[*root] != UNVISITED {
+                continue;
+            }
+            // Each entry is a net and the number of its edges explored so far
+            let mut stack = vec![(*root, 0)];
+            state[*root] = VISITING;
+            while let Some((net, explored)) = stack.last_mut() {
+                let net = *net;
+                if let Some(edge) = edges[net].get(*explored) {
+                    *explored += 1;
+                    match state[edge.from] {
+                        UNVISITED => {
+                            state[edge.from] = VISITING;
+                            stack.push((edge.from, 0));
+                        }
+                        VISITING => {
+                            // The loop runs from `edge.from` up the stack to here. Signals flow
+                            // the other way
+                            let start = stack.iter().position(|(n, _)| *n == edge.from).unwrap();
+                            let mut pins = Vec::new();
+                            for (n, e) in stack[start..].iter().rev() {
+                                let edge = &edges[*n][e - 1];
+                                pins.push(name(edge.input));
+                                pins.push(name(edge.output));
+                            }
+                            loops.push(pins);
+                        }
+                        _ => (),
+                    }
+                    continue;
+                }
+
+                // Every earlier net is done, except ones in a loop which are left out
+                for (i, edge) in edges[net].iter().enumerate() {
+                    if state[edge.from] == DONE && arrival[edge.from] + edge.ticks > arrival[net] {
+                        arrival[net] = arrival[edge.from] + edge.ticks;
+                        best[net] = Some(i);
+                    }
+                }
+                state[net] = DONE;
+                stack.pop();
+            }
+        }
+
+        let critical = (0..edges.len())
+            .filter(|net| best[*net].is_some())
+            .max_by_key(|net| (arrival[*net], std::cmp::Reverse(*net)))
+            .map(|end| {
+                let mut pins = Vec::new();
+
+                // Finish at a sequential or primary input, if one reads the last net
+                let net_pins = |net: usize| {
+                    (0..self.pin_nodes.len()).filter(move |pin_id| self.pin_nodes[*pin_id] == net)
+                };
+                let is_endpoint = |pin_id: &PinId| {
+                    matches!(self.pin_states[*pin_id], PinState::Input(_))
+                        && !edges.iter().flatten().any(|edge| edge.input == *pin_id)
+                };
+                if let Some(pin_id) = net_pins(end).find(is_endpoint) {
+                    pins.push(name(pin_id));
+                }
+
+                let mut net = end;
+                while let Some(i) = best[net] {
+                    let edge = &edges[net][i];
+                    pins.push(name(edge.output));
+                    pins.push(name(edge.input));
+                    net = edge.from;
+                }
+
+                // Start from whatever drives the first net
+                let driver = net_pins(net)
+                    .find(|pin_id| !matches!(self.pin_states[*pin_id], PinState::Input(_)));
+                if let Some(pin_id) = driver {
+                    pins.push(name(pin_id));
+                }
+
+                pins.reverse();
+                TimingPath {
+                    ticks: arrival[end],
+                    pins,
+                }
+            });
+
+        TimingReport { critical, loops }
+    }
+}
+
+impl Graph {
+    /// Static timing analysis over the whole graph
+    ///
+    /// Finds the longest combinational path between sequential parts (clocks, flip-flops, RAM and
+    /// other parts marked with `mark_sequential`) and primary inputs and outputs, measured in part
+    /// delays, along with any loops of combinational parts. Pin directions are taken as they are
+    /// now, with HiZ pins counting as outputs.
+    pub fn timing(&self) -> TimingReport {
+        self.inner().timing()
+    }
+}
+
+#[cfg(test)]
+mod test_timing {
+    use crate::*;
+
+    #[test]
+    fn test_chain() {
+        let mut graph = Graph::new();
+        let a = graph.new_output("a", Signal::Low);
+        let b = graph.new_output("b", Signal::Low);
+        let out = graph.new_input("out");
+        let not = not_gate(&mut graph, "not");
+        let and = and_gate(&mut graph, "and");
+        graph.connect(&a, not.input());
+        graph.connect(not.output(), and.input_a());
+        graph.connect(&b, and.input_b());
+        graph.connect(and.output(), &out);
+
+        let report = graph.timing();
+        assert_eq!(report.loops, Vec::<Vec<String>>::new());
+        let critical = report.critical.unwrap();
+        assert_eq!(critical.ticks, 2);
+        assert_eq!(
+            critical.pins,
+            ["a", "not[0]", "not[1]", "and[0]", "and[2]", "out"]
+        );
+
+        graph.set_delay(and.output(), Delay::ticks(2, 4));
+        assert_eq!(graph.timing().critical.unwrap().ticks, 5);
+    }
+
+    #[test]
+    fn test_sequential() {
+        // The flip-flop splits the chain into a 1 tick path and a 3 tick path
+        let mut graph = Graph::new();
+        let a = graph.new_output("a", Signal::Low);
+        let out = graph.new_input("out");
+        let ff = TFlipFlop::new(&mut graph, "ff");
+        let low = graph.new_output("low", Signal::Low);
+        graph.connect_all(&[&low, ff.set(), ff.reset()]);
+        graph.connect(&!&a, ff.toggle());
+        graph.connect(&!&!&!ff.output(), &out);
+
+        let critical = graph.timing().critical.unwrap();
+        assert_eq!(critical.ticks, 3);
+        assert_eq!(critical.pins.first().unwrap(), "ff[3]");
+        assert_eq!(critical.pins.last().unwrap(), "out");
+    }
+
+    #[test]
+    fn test_loops() {
+        let mut graph = Graph::new();
+        let a = not_gate(&mut graph, "a");
+        let b = not_gate(&mut graph, "b");
+        graph.connect(a.output(), b.input());
+        graph.connect(b.output(), a.input());
+
+        let loops = graph.timing().loops;
+        assert_eq!(loops.len(), 1);
+        let mut pins = loops[0].clone();
+        pins.sort();
+        assert_eq!(pins, ["a[0]", "a[1]", "b[0]", "b[1]"]);
+
+        // A flip-flop in the loop breaks it
+        let mut graph = Graph::new();
+        let ff = TFlipFlop::new(&mut graph, "ff");
+        let low = graph.new_output("low", Signal::Low);
+        graph.connect_all(&[&low, ff.set(), ff.reset()]);
+        graph.connect(&!ff.output(), ff.toggle());
+        let report = graph.timing();
+        assert!(report.loops.is_empty());
+        assert_eq!(report.critical.unwrap().ticks, 1);
+    }
+
+    #[test]
+    fn test_mark_sequential() {
+        // A part that only copies its input looks combinational until it's marked
+        let mut graph = Graph::new();
+        let pins = graph.new_part("latch", &[PinState::INPUT, PinState::OUTPUT], |pins| {
+            pins[1] = PinState::Output(pins[0].sig());
+        });
+        graph.connect(&!&pins[1], &pins[0]);
+        assert_eq!(graph.timing().loops.len(), 1);
+
+        graph.mark_sequential(&pins[1]);
+        let report = graph.timing();
+        assert!(report.loops.is_empty());
+        assert_eq!(report.critical.unwrap().pins.first().unwrap(), "latch[1]");
+    }
+}